@@ -7,6 +7,8 @@ pub struct ConsumerConfig {
     pub enable_database_consumer: bool,
     /// 是否启用通知消费者
     pub enable_kafka_consumer: bool,
+    /// 是否启用Webhook消费者
+    pub enable_webhook_consumer: bool,
     /// 消费者通道容量
     pub channel_capacity: usize,
 }
@@ -16,6 +18,7 @@ impl Default for ConsumerConfig {
         Self {
             enable_database_consumer: false,
             enable_kafka_consumer: false,
+            enable_webhook_consumer: false,
             channel_capacity: 10000,
         }
     }
@@ -27,15 +30,19 @@ impl ConsumerConfig {
         Self {
             enable_database_consumer: false,
             enable_kafka_consumer: false,
+            enable_webhook_consumer: false,
             ..Default::default()
         }
     }
 
     /// 创建启用所有消费者的配置
-    pub fn enable_consumer(enable_database_consumer: bool, enable_kafka_consumer: bool) -> Self {
+    pub fn enable_consumer(
+        enable_database_consumer: bool, enable_kafka_consumer: bool, enable_webhook_consumer: bool,
+    ) -> Self {
         Self {
-            enable_database_consumer: enable_database_consumer,
-            enable_kafka_consumer: enable_kafka_consumer,
+            enable_database_consumer,
+            enable_kafka_consumer,
+            enable_webhook_consumer,
             ..Default::default()
         }
     }
@@ -45,17 +52,20 @@ impl ConsumerConfig {
         Self {
             enable_database_consumer: true,
             enable_kafka_consumer: true,
+            enable_webhook_consumer: true,
             ..Default::default()
         }
     }
 
     /// 创建自定义配置
     pub fn new(
-        enable_database_consumer: bool, enable_kafka_consumer: bool, channel_capacity: usize,
+        enable_database_consumer: bool, enable_kafka_consumer: bool, enable_webhook_consumer: bool,
+        channel_capacity: usize,
     ) -> Self {
         Self {
             enable_database_consumer,
             enable_kafka_consumer,
+            enable_webhook_consumer,
             channel_capacity,
         }
     }
@@ -1,5 +1,6 @@
 use crate::consumer::Consumer;
 use crate::consumer::stats::{ScanStats, StatsCalculator};
+use crate::job::job_dir;
 use crate::scan::ScanMessage;
 use std::path::Path;
 use std::time::Instant;
@@ -21,6 +22,8 @@ impl Consumer for ConsoleConsumer {
             let mut base_path;
             let mut last_progress_time = Instant::now();
             let mut config_received = false;
+            // 作业目录，收到配置后写入统计摘要
+            let mut summary_dir = None;
 
             // 处理队列消息并广播给消费者
             println!("🚀 terrasync 3.0.0; (c) 2025 LenovoNetapp, Inc.\n");
@@ -42,26 +45,8 @@ impl Consumer for ConsoleConsumer {
 
                         let calc = calculator.as_ref().unwrap();
 
-                        // 更新基本统计
-                        if result.is_dir {
-                            stats.total_dirs += 1;
-                        } else {
-                            stats.total_files += 1;
-                            stats.total_size += result.size as i64;
-                        }
-
-                        // 使用StatsCalculator更新扩展统计信息
-                        if result.is_dir {
-                            let depth = calc.calculate_depth(Path::new(&result.file_path));
-                            calc.update_dir_stats(&mut stats, &result.file_name, depth);
-                        } else {
-                            calc.update_file_stats(
-                                &mut stats,
-                                &result.file_name,
-                                result.size,
-                                result.is_symlink,
-                            );
-                        }
+                        // 使用StatsCalculator更新统计信息
+                        calc.update(&mut stats, &result);
 
                         // 每10秒打印一次进度
                         if last_progress_time.elapsed().as_secs() >= 10 {
@@ -87,7 +72,8 @@ impl Consumer for ConsoleConsumer {
                             .id
                             .clone()
                             .unwrap_or_else(|| "unknown".to_string());
-                        stats.log_path = ScanStats::build_log_path();
+                        stats.log_path = ScanStats::build_log_path(&consumer_config.job_dir);
+                        summary_dir = Some(consumer_config.job_dir.clone());
                         config_received = true;
                         log::info!("[ConsoleConsumer] Received scan configuration");
                     }
@@ -102,13 +88,33 @@ impl Consumer for ConsoleConsumer {
                         if !config_received {
                             stats.command = "terrasync scan".to_string();
                             stats.job_id = "unknown".to_string();
-                            stats.log_path = ScanStats::build_log_path();
+                            stats.log_path =
+                                ScanStats::build_log_path(&job_dir("scan", &stats.job_id));
                         }
 
                         // 打印最终统计信息
+                        stats.finalize();
                         println!("\n{}", stats);
+
+                        if let Some(job_dir) = &summary_dir {
+                            match stats.write_summary(job_dir) {
+                                Ok(path) => {
+                                    log::info!(
+                                        "[ConsoleConsumer] Wrote scan summary to {}",
+                                        path.display()
+                                    )
+                                }
+                                Err(e) => log::error!(
+                                    "[ConsoleConsumer] Failed to write scan summary: {}",
+                                    e
+                                ),
+                            }
+                        }
                         break;
                     }
+                    Ok(ScanMessage::Failed(reason)) => {
+                        eprintln!("❌ Scan failed: {}", reason);
+                    }
                     Err(broadcast::error::RecvError::Closed) => {
                         log::warn!("[ConsoleConsumer] Channel closed");
                         break;
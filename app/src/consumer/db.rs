@@ -1,17 +1,115 @@
 use crate::consumer::Consumer;
+use crate::consumer::rollup::DirRollupBuilder;
+use crate::consumer::spool::{DbSpool, RetryPolicy};
+use crate::database::database_config;
+use crate::job::job_type;
 use crate::scan::ScanMessage;
-use db::config::DatabaseConfig;
+use db::catalog::{self, JobRun};
 use db::factory::create_database;
-use db::traits::Database;
-use db::traits::FileScanRecord;
+use db::traits::{Database, FileScanRecord};
 use std::sync::Arc;
-use std::time::UNIX_EPOCH;
+use std::time::Duration;
 use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 use utils::error::Result;
 
 /// 数据库消费者 - 将扫描结果存储到数据库
 pub struct DatabaseConsumer;
 
+/// 流式写入时每次发送给数据库的记录数
+const STREAM_CHUNK_SIZE: usize = 1024;
+
+/// 流式写入时发送剩余记录并检查提交阈值的间隔，扫描较慢时也能按时间提交
+const STREAM_TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 流式写入缓冲：保留已发送但尚未提交的记录，写入失败时改为按批次重试或写入spool
+#[derive(Default)]
+struct StreamBuffer {
+    records: Vec<FileScanRecord>,
+    /// 已发送给数据库的记录数
+    sent: usize,
+}
+
+impl StreamBuffer {
+    /// 追加记录，未发送的记录达到一个分块时发送，返回未能写入数据库的记录数
+    async fn push(
+        &mut self, db: &dyn Database, record: FileScanRecord, policy: &RetryPolicy, spool: &DbSpool,
+    ) -> usize {
+        self.records.push(record);
+        if self.records.len() - self.sent < STREAM_CHUNK_SIZE {
+            return 0;
+        }
+        self.send(db, false, policy, spool).await
+    }
+
+    /// 定时调用：存在未提交的记录时发送剩余记录，由数据库检查时间阈值
+    async fn tick(&mut self, db: &dyn Database, policy: &RetryPolicy, spool: &DbSpool) -> usize {
+        if self.records.is_empty() {
+            return 0;
+        }
+        self.send(db, false, policy, spool).await
+    }
+
+    /// 发送剩余记录，`flush` 为true时提交全部未提交的记录
+    async fn send(
+        &mut self, db: &dyn Database, flush: bool, policy: &RetryPolicy, spool: &DbSpool,
+    ) -> usize {
+        let mut result = db
+            .stream_insert_base_records(&self.records[self.sent..])
+            .await;
+        if flush && result.is_ok() {
+            result = db.flush_base_records().await.map(|_| true);
+        }
+
+        match result {
+            Ok(true) => {
+                self.records.clear();
+                self.sent = 0;
+                0
+            }
+            Ok(false) => {
+                self.sent = self.records.len();
+                0
+            }
+            Err(e) => {
+                // 流式写入出错时未提交的记录已被丢弃，改为整批重新写入
+                log::warn!(
+                    "[DatabaseConsumer] Streaming insert failed: {}, retrying {} uncommitted records as a batch",
+                    e,
+                    self.records.len()
+                );
+                self.sent = 0;
+                insert_or_spool(db, std::mem::take(&mut self.records), true, policy, spool).await
+            }
+        }
+    }
+}
+
+/// 按重试策略写入批次，最终失败时写入spool文件，返回未能写入数据库的记录数
+async fn insert_or_spool(
+    db: &dyn Database, records: Vec<FileScanRecord>, sync: bool, policy: &RetryPolicy,
+    spool: &DbSpool,
+) -> usize {
+    let Err(e) = policy.insert(db, &records, sync).await else {
+        return 0;
+    };
+
+    log::error!(
+        "[DatabaseConsumer] Failed to insert batch of {} records: {}, spooling to {}",
+        records.len(),
+        e,
+        spool.path().display()
+    );
+    if let Err(e) = spool.append(&records).await {
+        log::error!(
+            "[DatabaseConsumer] Failed to spool batch, {} records lost: {}",
+            records.len(),
+            e
+        );
+    }
+    records.len()
+}
+
 #[async_trait::async_trait]
 impl Consumer for DatabaseConsumer {
     async fn start(
@@ -22,77 +120,81 @@ impl Consumer for DatabaseConsumer {
             let mut batch_size: Option<u32> = None;
             let mut current_batch = Vec::with_capacity(batch_size.unwrap_or(100_000) as usize);
             let mut next_batch = Vec::with_capacity(batch_size.unwrap_or(100_000) as usize);
+            // 本次运行的目录记录，数据库初始化成功后创建
+            let mut run: Option<JobRun> = None;
+            let mut retry_policy: Option<RetryPolicy> = None;
+            let mut spool: Option<Arc<DbSpool>> = None;
+            // 进行中的异步写入任务，完成时返回未能写入的记录数
+            let mut pending_inserts: Vec<JoinHandle<usize>> = Vec::new();
+            // 数据库支持流式写入时使用，不再在内存中累积大批次
+            let mut stream_buffer: Option<StreamBuffer> = None;
+            let mut spooled = 0;
+            // 按目录累加的汇总，扫描完成后写入scan_dirs表
+            let mut rollups = DirRollupBuilder::new();
+            let mut ticker = tokio::time::interval(STREAM_TICK_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
             loop {
-                match receiver.recv().await {
+                let message = tokio::select! {
+                    message = receiver.recv() => message,
+                    _ = ticker.tick() => {
+                        if let (Some(db), Some(policy), Some(spool), Some(buffer)) =
+                            (&database, &retry_policy, &spool, &mut stream_buffer)
+                        {
+                            spooled += buffer.tick(db.as_ref(), policy, spool).await;
+                        }
+                        continue;
+                    }
+                };
+                match message {
                     Ok(ScanMessage::Result(entity)) => {
                         let actual_batch_size = batch_size.unwrap_or(400_000) as usize;
-                        if let Some(db) = &database {
-                            // Convert SystemTime to u64 timestamp
-                            let ctime = entity.ctime;
-                            let mtime = entity.mtime;
-                            let atime = entity.atime;
-
-                            let record = FileScanRecord {
-                                path: entity.file_path,
-                                size: entity.size,
-                                ext: entity.extension,
-                                atime: atime
-                                    .duration_since(UNIX_EPOCH)
-                                    .ok()
-                                    .map(|duration| {
-                                        duration.as_secs() as i64 * 1000
-                                            + duration.subsec_millis() as i64
-                                    })
-                                    .unwrap(),
-                                ctime: ctime
-                                    .duration_since(UNIX_EPOCH)
-                                    .ok()
-                                    .map(|duration| {
-                                        duration.as_secs() as i64 * 1000
-                                            + duration.subsec_millis() as i64
-                                    })
-                                    .unwrap(),
-                                mtime: mtime
-                                    .duration_since(UNIX_EPOCH)
-                                    .ok()
-                                    .map(|duration| {
-                                        duration.as_secs() as i64 * 1000
-                                            + duration.subsec_millis() as i64
-                                    })
-                                    .unwrap(),
-                                perm: entity.permissions,
-                                is_symlink: entity.is_symlink,
-                                is_dir: entity.is_dir,
-                                is_regular_file: !entity.is_dir,
-                                hard_links: entity.hard_links.unwrap_or_default(),
-                                current_state: 0,
-                            };
-                            current_batch.push(record);
+                        if let (Some(db), Some(policy), Some(spool)) =
+                            (&database, &retry_policy, &spool)
+                        {
+                            if let Some(run) = &mut run {
+                                if entity.is_dir {
+                                    run.total_dirs += 1;
+                                } else {
+                                    run.total_files += 1;
+                                    run.total_bytes += entity.size;
+                                }
+                            }
+                            rollups.add(&entity);
+                            if let Some(buffer) = &mut stream_buffer {
+                                spooled += buffer
+                                    .push(db.as_ref(), entity.to_file_scan_record(), policy, spool)
+                                    .await;
+                                continue;
+                            }
+
+                            current_batch.push(entity.to_file_scan_record());
 
                             // 达到批量大小则异步插入数据库并切换缓冲
                             if current_batch.len() >= actual_batch_size {
                                 // 交换当前缓冲和备用缓冲
                                 std::mem::swap(&mut current_batch, &mut next_batch);
                                 let db_clone = Arc::clone(db);
+                                let policy = policy.clone();
+                                let spool = Arc::clone(spool);
                                 let batch_to_insert = std::mem::take(&mut next_batch);
 
                                 // 异步执行数据库插入操作
-                                tokio::spawn(async move {
+                                pending_inserts.retain(|handle| !handle.is_finished());
+                                pending_inserts.push(tokio::spawn(async move {
                                     log::info!(
                                         "[DatabaseConsumer] Inserting batch of {} records",
                                         batch_to_insert.len()
                                     );
-                                    if let Err(e) = db_clone
-                                        .batch_insert_base_record_async(batch_to_insert)
-                                        .await
-                                    {
-                                        log::error!(
-                                            "[DatabaseConsumer] Failed to insert batch: {}",
-                                            e
-                                        );
-                                    }
-                                });
+                                    insert_or_spool(
+                                        db_clone.as_ref(),
+                                        batch_to_insert,
+                                        false,
+                                        &policy,
+                                        &spool,
+                                    )
+                                    .await
+                                }));
 
                                 // 为下一批数据预留容量
                                 current_batch.reserve(actual_batch_size);
@@ -104,17 +206,93 @@ impl Consumer for DatabaseConsumer {
                             "[DatabaseConsumer] Scan completed, flushing remaining records..."
                         );
 
+                        // 提交流式写入的剩余记录
+                        if let (Some(db), Some(policy), Some(spool), Some(buffer)) =
+                            (&database, &retry_policy, &spool, &mut stream_buffer)
+                        {
+                            spooled += buffer.send(db.as_ref(), true, policy, spool).await;
+                        }
+
                         // 如果有剩余记录，插入数据库
+                        if let (Some(db), Some(policy), Some(spool)) =
+                            (&database, &retry_policy, &spool)
+                            && !current_batch.is_empty()
+                        {
+                            log::info!(
+                                "[DatabaseConsumer] Inserting final batch of {} records",
+                                current_batch.len()
+                            );
+                            spooled += insert_or_spool(
+                                db.as_ref(),
+                                std::mem::take(&mut current_batch),
+                                true,
+                                policy,
+                                spool,
+                            )
+                            .await;
+                        }
+
+                        // 等待进行中的异步写入完成，确保失败批次已写入spool
+                        for handle in pending_inserts.drain(..) {
+                            spooled += handle.await.unwrap_or_default();
+                        }
+
+                        if let Some(spool) = &spool
+                            && spooled > 0
+                        {
+                            log::warn!(
+                                "[DatabaseConsumer] {} records were spooled to {}, run `db replay` to insert them",
+                                spooled,
+                                spool.path().display()
+                            );
+                            if let Some(run) = &mut run {
+                                run.errors += 1;
+                                run.error_message = format!(
+                                    "{} records spooled to {}",
+                                    spooled,
+                                    spool.path().display()
+                                );
+                            }
+                        }
+
+                        // 全部记录写入后才提交本轮代次，查询在此之前读取上次扫描的结果
                         if let Some(db) = &database {
-                            if !current_batch.is_empty() {
-                                log::info!(
-                                    "[DatabaseConsumer] Inserting final batch of {} records",
-                                    current_batch.len()
+                            if spooled > 0 {
+                                log::warn!(
+                                    "[DatabaseConsumer] Scan generation left uncommitted until the spool is replayed"
                                 );
-                                let _ = db
-                                    .batch_insert_base_record_sync(current_batch.clone())
-                                    .await;
-                                current_batch.clear();
+                            } else if let Err(e) = db.commit_scan_generation().await {
+                                log::error!(
+                                    "[DatabaseConsumer] Failed to commit scan generation: {}",
+                                    e
+                                );
+                            }
+                        }
+
+                        // 写入目录汇总，失败不影响扫描结果入库
+                        if let Some(db) = &database {
+                            let rollups = std::mem::take(&mut rollups).finish();
+                            let result = async {
+                                db.create_table(db::SCAN_DIRS_TABLE_BASE_NAME).await?;
+                                db.replace_dir_rollups(&rollups).await
+                            };
+                            match result.await {
+                                Ok(()) => log::info!(
+                                    "[DatabaseConsumer] Stored rollups for {} directories",
+                                    rollups.len()
+                                ),
+                                Err(e) => log::error!(
+                                    "[DatabaseConsumer] Failed to store directory rollups: {}",
+                                    e
+                                ),
+                            }
+                        }
+
+                        // 记录运行结果
+                        if let (Some(db), Some(run)) = (&database, &mut run) {
+                            run.finish();
+                            if let Err(e) = catalog::record_run(db.as_ref(), run).await {
+                                log::error!("[DatabaseConsumer] Failed to record job run: {}", e);
                             }
                         }
 
@@ -131,21 +309,11 @@ impl Consumer for DatabaseConsumer {
                         );
 
                         // 构建数据库配置
-                        let db_config = DatabaseConfig {
-                            enabled: config.app_config.database.enabled,
-                            db_type: config.app_config.database.r#type.clone(),
-                            batch_size: config.app_config.database.batch_size,
-                            clickhouse: Some(db::config::ClickHouseConfig {
-                                dsn: config.app_config.database.clickhouse.dsn.clone(),
-                                dial_timeout: config.app_config.database.clickhouse.dial_timeout,
-                                read_timeout: config.app_config.database.clickhouse.read_timeout,
-                                database: config.app_config.database.clickhouse.database.clone(),
-                                username: config.app_config.database.clickhouse.username.clone(),
-                                password: config.app_config.database.clickhouse.password.clone(),
-                            }),
-                        };
+                        let db_config = database_config(&config.app_config.database);
 
                         batch_size = Some(db_config.batch_size);
+                        retry_policy = Some(RetryPolicy::new(&config.app_config.database));
+                        spool = Some(Arc::new(DbSpool::new(&config.job_dir)));
 
                         // 通过DatabaseFactory创建数据库实例
                         match create_database(&db_config, current_job_id.clone()) {
@@ -171,6 +339,14 @@ impl Consumer for DatabaseConsumer {
                                     continue;
                                 }
 
+                                if let Err(e) = db_instance.start_scan_generation().await {
+                                    log::error!(
+                                        "[DatabaseConsumer] Failed to start scan generation: {}",
+                                        e
+                                    );
+                                    continue;
+                                }
+
                                 if let Err(e) = db_instance
                                     .create_table(db::SCAN_STATE_TABLE_BASE_NAME)
                                     .await
@@ -195,6 +371,35 @@ impl Consumer for DatabaseConsumer {
                                     current_job_id
                                 );
 
+                                // 在作业目录中登记本次运行，失败不影响扫描结果入库
+                                let params = serde_json::to_string(&config.scan_config.params)
+                                    .unwrap_or_default();
+                                let new_run = JobRun::start(
+                                    &current_job_id,
+                                    &job_type(&config.job_dir),
+                                    params,
+                                );
+                                match catalog::ensure_catalog_tables(db_instance.as_ref()).await {
+                                    Ok(()) => {
+                                        match catalog::record_run(db_instance.as_ref(), &new_run)
+                                            .await
+                                        {
+                                            Ok(()) => run = Some(new_run),
+                                            Err(e) => log::error!(
+                                                "[DatabaseConsumer] Failed to record job run: {}",
+                                                e
+                                            ),
+                                        }
+                                    }
+                                    Err(e) => log::error!(
+                                        "[DatabaseConsumer] Failed to create catalog tables: {}",
+                                        e
+                                    ),
+                                }
+
+                                if db_instance.supports_streaming_insert() {
+                                    stream_buffer = Some(StreamBuffer::default());
+                                }
                                 database = Some(db_instance);
                             }
                             Err(e) => {
@@ -205,6 +410,14 @@ impl Consumer for DatabaseConsumer {
                             }
                         }
                     }
+                    Ok(ScanMessage::Failed(error)) => {
+                        // 失败后仍会收到Complete，由其完成收尾
+                        if let Some(run) = &mut run {
+                            run.errors += 1;
+                            run.error_message = error;
+                        }
+                        continue;
+                    }
                     Err(broadcast::error::RecvError::Closed) => {
                         log::info!("[DatabaseConsumer] Broadcast channel closed, shutting down...");
                         break;
@@ -228,3 +441,63 @@ impl Consumer for DatabaseConsumer {
         "database_consumer"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::SqliteDatabase;
+
+    fn test_record(path: &str) -> FileScanRecord {
+        FileScanRecord {
+            path: path.to_string(),
+            size: 1,
+            ext: None,
+            ctime: 0,
+            mtime: 0,
+            atime: 0,
+            perm: None,
+            is_symlink: false,
+            is_dir: false,
+            is_regular_file: true,
+            hard_links: 1,
+            current_state: 0,
+            checksum: None,
+        }
+    }
+
+    /// 测试不足一个分块的记录在定时检查时发送，不必等到扫描完成
+    #[tokio::test]
+    async fn test_tick_sends_partial_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = DbSpool::new(dir.path());
+        let policy = RetryPolicy {
+            retries: 0,
+            backoff: Duration::from_millis(1),
+        };
+        let db = SqliteDatabase::new_in_memory("tick".to_string()).unwrap();
+        db.create_table(db::SCAN_BASE_TABLE_BASE_NAME)
+            .await
+            .unwrap();
+
+        let mut buffer = StreamBuffer::default();
+        for path in ["/a", "/b"] {
+            assert_eq!(
+                buffer.push(&db, test_record(path), &policy, &spool).await,
+                0
+            );
+        }
+        assert!(
+            db.query_scan_base_table(&["path"])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(buffer.tick(&db, &policy, &spool).await, 0);
+        assert!(buffer.records.is_empty());
+        assert_eq!(db.query_scan_base_table(&["path"]).await.unwrap().len(), 2);
+
+        // 没有未提交的记录时不访问数据库
+        assert_eq!(buffer.tick(&db, &policy, &spool).await, 0);
+    }
+}
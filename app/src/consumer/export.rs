@@ -0,0 +1,629 @@
+use crate::consumer::Consumer;
+use crate::scan::ScanMessage;
+use arrow_array::builder::{
+    BooleanBuilder, StringBuilder, TimestampMillisecondBuilder, UInt8Builder, UInt64Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, SecondsFormat, Utc};
+use db::traits::FileScanRecord;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use utils::error::{Error, Result};
+
+/// Parquet写入时每个RecordBatch缓冲的行数
+const PARQUET_BATCH_ROWS: usize = 8192;
+
+/// 导出文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+impl OutputFormat {
+    /// 根据输出文件扩展名推断格式
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(OutputFormat::Csv),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            "parquet" => Some(OutputFormat::Parquet),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(OutputFormat::Csv),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "parquet" => Ok(OutputFormat::Parquet),
+            _ => Err(Error::new(&format!("Unsupported output format: {}", s))),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Parquet => write!(f, "parquet"),
+        }
+    }
+}
+
+/// 可导出的列，与 `FileScanRecord` 字段一一对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportColumn {
+    Path,
+    Size,
+    Ext,
+    Ctime,
+    Mtime,
+    Atime,
+    Perm,
+    IsSymlink,
+    IsDir,
+    IsRegularFile,
+    HardLinks,
+    CurrentState,
+    Checksum,
+}
+
+impl ExportColumn {
+    /// 所有列，按 `FileScanRecord` 字段顺序排列
+    pub const ALL: [ExportColumn; 13] = [
+        ExportColumn::Path,
+        ExportColumn::Size,
+        ExportColumn::Ext,
+        ExportColumn::Ctime,
+        ExportColumn::Mtime,
+        ExportColumn::Atime,
+        ExportColumn::Perm,
+        ExportColumn::IsSymlink,
+        ExportColumn::IsDir,
+        ExportColumn::IsRegularFile,
+        ExportColumn::HardLinks,
+        ExportColumn::CurrentState,
+        ExportColumn::Checksum,
+    ];
+
+    /// 列名
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportColumn::Path => "path",
+            ExportColumn::Size => "size",
+            ExportColumn::Ext => "ext",
+            ExportColumn::Ctime => "ctime",
+            ExportColumn::Mtime => "mtime",
+            ExportColumn::Atime => "atime",
+            ExportColumn::Perm => "perm",
+            ExportColumn::IsSymlink => "is_symlink",
+            ExportColumn::IsDir => "is_dir",
+            ExportColumn::IsRegularFile => "is_regular_file",
+            ExportColumn::HardLinks => "hard_links",
+            ExportColumn::CurrentState => "current_state",
+            ExportColumn::Checksum => "checksum",
+        }
+    }
+
+    /// 解析列名列表，空列表表示导出所有列
+    pub fn parse_list(columns: &[String]) -> Result<Vec<ExportColumn>> {
+        if columns.is_empty() {
+            return Ok(Self::ALL.to_vec());
+        }
+
+        columns
+            .iter()
+            .flat_map(|c| c.split(','))
+            .map(|name| {
+                let name = name.trim();
+                Self::ALL
+                    .iter()
+                    .find(|c| c.name() == name)
+                    .copied()
+                    .ok_or_else(|| Error::new(&format!("Unknown export column: {}", name)))
+            })
+            .collect()
+    }
+
+    /// 将列值格式化为文本，空值返回None
+    fn text_value(&self, record: &FileScanRecord) -> Option<String> {
+        match self {
+            ExportColumn::Path => Some(record.path.clone()),
+            ExportColumn::Size => Some(record.size.to_string()),
+            ExportColumn::Ext => record.ext.clone(),
+            ExportColumn::Ctime => Some(format_timestamp(record.ctime)),
+            ExportColumn::Mtime => Some(format_timestamp(record.mtime)),
+            ExportColumn::Atime => Some(format_timestamp(record.atime)),
+            ExportColumn::Perm => record.perm.clone(),
+            ExportColumn::IsSymlink => Some(record.is_symlink.to_string()),
+            ExportColumn::IsDir => Some(record.is_dir.to_string()),
+            ExportColumn::IsRegularFile => Some(record.is_regular_file.to_string()),
+            ExportColumn::HardLinks => Some(record.hard_links.to_string()),
+            ExportColumn::CurrentState => Some(record.current_state.to_string()),
+            ExportColumn::Checksum => record.checksum.clone(),
+        }
+    }
+
+    /// 将列值转换为JSON值
+    fn json_value(&self, record: &FileScanRecord) -> serde_json::Value {
+        use serde_json::Value;
+        match self {
+            ExportColumn::Size => Value::from(record.size),
+            ExportColumn::IsSymlink => Value::from(record.is_symlink),
+            ExportColumn::IsDir => Value::from(record.is_dir),
+            ExportColumn::IsRegularFile => Value::from(record.is_regular_file),
+            ExportColumn::HardLinks => Value::from(record.hard_links),
+            ExportColumn::CurrentState => Value::from(record.current_state),
+            _ => self.text_value(record).map_or(Value::Null, Value::from),
+        }
+    }
+
+    /// Parquet/Arrow字段定义
+    fn arrow_field(&self) -> Field {
+        let timestamp = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+        match self {
+            ExportColumn::Path => Field::new(self.name(), DataType::Utf8, false),
+            ExportColumn::Size => Field::new(self.name(), DataType::UInt64, false),
+            ExportColumn::Ext | ExportColumn::Perm | ExportColumn::Checksum => {
+                Field::new(self.name(), DataType::Utf8, true)
+            }
+            ExportColumn::Ctime | ExportColumn::Mtime | ExportColumn::Atime => {
+                Field::new(self.name(), timestamp, false)
+            }
+            ExportColumn::IsSymlink | ExportColumn::IsDir | ExportColumn::IsRegularFile => {
+                Field::new(self.name(), DataType::Boolean, false)
+            }
+            ExportColumn::HardLinks | ExportColumn::CurrentState => {
+                Field::new(self.name(), DataType::UInt8, false)
+            }
+        }
+    }
+}
+
+/// 将Unix毫秒时间戳格式化为RFC 3339字符串
+fn format_timestamp(millis: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// 导出写入器接口
+pub trait RecordWriter: Send {
+    /// 写入单条记录
+    fn write(&mut self, record: &FileScanRecord) -> Result<()>;
+
+    /// 完成写入并关闭文件
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// CSV写入器，首行为列名
+pub struct CsvRecordWriter {
+    writer: csv::Writer<File>,
+    columns: Vec<ExportColumn>,
+}
+
+impl CsvRecordWriter {
+    pub fn new(file: File, columns: Vec<ExportColumn>) -> Result<Self> {
+        let mut writer = csv::Writer::from_writer(file);
+        writer
+            .write_record(columns.iter().map(|c| c.name()))
+            .map_err(|e| Error::with_source("Failed to write CSV header", Box::new(e)))?;
+        Ok(Self { writer, columns })
+    }
+}
+
+impl RecordWriter for CsvRecordWriter {
+    fn write(&mut self, record: &FileScanRecord) -> Result<()> {
+        self.writer
+            .write_record(
+                self.columns
+                    .iter()
+                    .map(|c| c.text_value(record).unwrap_or_default()),
+            )
+            .map_err(|e| Error::with_source("Failed to write CSV record", Box::new(e)))
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// NDJSON写入器，每行一个JSON对象
+pub struct NdjsonRecordWriter {
+    writer: BufWriter<File>,
+    columns: Vec<ExportColumn>,
+}
+
+impl NdjsonRecordWriter {
+    pub fn new(file: File, columns: Vec<ExportColumn>) -> Self {
+        Self {
+            writer: BufWriter::new(file),
+            columns,
+        }
+    }
+}
+
+impl RecordWriter for NdjsonRecordWriter {
+    fn write(&mut self, record: &FileScanRecord) -> Result<()> {
+        let object: serde_json::Map<String, serde_json::Value> = self
+            .columns
+            .iter()
+            .map(|c| (c.name().to_string(), c.json_value(record)))
+            .collect();
+        serde_json::to_writer(&mut self.writer, &object)
+            .map_err(|e| Error::with_source("Failed to write NDJSON record", Box::new(e)))?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Parquet写入器，按批次缓冲后写入，内存占用与批次大小成正比
+pub struct ParquetRecordWriter {
+    writer: ArrowWriter<File>,
+    schema: Arc<Schema>,
+    columns: Vec<ExportColumn>,
+    buffer: Vec<FileScanRecord>,
+}
+
+impl ParquetRecordWriter {
+    pub fn new(file: File, columns: Vec<ExportColumn>) -> Result<Self> {
+        let schema = Arc::new(Schema::new(
+            columns.iter().map(|c| c.arrow_field()).collect::<Vec<_>>(),
+        ));
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(file, Arc::clone(&schema), Some(properties))
+            .map_err(|e| Error::with_source("Failed to create Parquet writer", Box::new(e)))?;
+
+        Ok(Self {
+            writer,
+            schema,
+            columns,
+            buffer: Vec::with_capacity(PARQUET_BATCH_ROWS),
+        })
+    }
+
+    fn build_column(&self, column: ExportColumn) -> ArrayRef {
+        let records = &self.buffer;
+        match column {
+            ExportColumn::Path => {
+                let mut builder = StringBuilder::new();
+                records.iter().for_each(|r| builder.append_value(&r.path));
+                Arc::new(builder.finish())
+            }
+            ExportColumn::Ext | ExportColumn::Perm | ExportColumn::Checksum => {
+                let mut builder = StringBuilder::new();
+                for r in records {
+                    let value = match column {
+                        ExportColumn::Ext => &r.ext,
+                        ExportColumn::Perm => &r.perm,
+                        _ => &r.checksum,
+                    };
+                    builder.append_option(value.as_deref());
+                }
+                Arc::new(builder.finish())
+            }
+            ExportColumn::Size => {
+                let mut builder = UInt64Builder::with_capacity(records.len());
+                records.iter().for_each(|r| builder.append_value(r.size));
+                Arc::new(builder.finish())
+            }
+            ExportColumn::Ctime | ExportColumn::Mtime | ExportColumn::Atime => {
+                let mut builder =
+                    TimestampMillisecondBuilder::with_capacity(records.len()).with_timezone("UTC");
+                for r in records {
+                    builder.append_value(match column {
+                        ExportColumn::Ctime => r.ctime,
+                        ExportColumn::Mtime => r.mtime,
+                        _ => r.atime,
+                    });
+                }
+                Arc::new(builder.finish())
+            }
+            ExportColumn::IsSymlink | ExportColumn::IsDir | ExportColumn::IsRegularFile => {
+                let mut builder = BooleanBuilder::with_capacity(records.len());
+                for r in records {
+                    builder.append_value(match column {
+                        ExportColumn::IsSymlink => r.is_symlink,
+                        ExportColumn::IsDir => r.is_dir,
+                        _ => r.is_regular_file,
+                    });
+                }
+                Arc::new(builder.finish())
+            }
+            ExportColumn::HardLinks | ExportColumn::CurrentState => {
+                let mut builder = UInt8Builder::with_capacity(records.len());
+                for r in records {
+                    builder.append_value(if column == ExportColumn::HardLinks {
+                        r.hard_links
+                    } else {
+                        r.current_state
+                    });
+                }
+                Arc::new(builder.finish())
+            }
+        }
+    }
+
+    fn flush_batch(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let arrays = self
+            .columns
+            .iter()
+            .map(|c| self.build_column(*c))
+            .collect::<Vec<_>>();
+        let batch = RecordBatch::try_new(Arc::clone(&self.schema), arrays)
+            .map_err(|e| Error::with_source("Failed to build record batch", Box::new(e)))?;
+        self.writer
+            .write(&batch)
+            .map_err(|e| Error::with_source("Failed to write Parquet batch", Box::new(e)))?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl RecordWriter for ParquetRecordWriter {
+    fn write(&mut self, record: &FileScanRecord) -> Result<()> {
+        self.buffer.push(record.clone());
+        if self.buffer.len() >= PARQUET_BATCH_ROWS {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush_batch()?;
+        self.writer
+            .close()
+            .map_err(|e| Error::with_source("Failed to close Parquet writer", Box::new(e)))?;
+        Ok(())
+    }
+}
+
+/// 创建指定格式的导出写入器
+pub fn create_record_writer(
+    path: &str, format: OutputFormat, columns: Vec<ExportColumn>,
+) -> Result<Box<dyn RecordWriter>> {
+    if let Some(parent) = Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = File::create(path)?;
+
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvRecordWriter::new(file, columns)?),
+        OutputFormat::Ndjson => Box::new(NdjsonRecordWriter::new(file, columns)),
+        OutputFormat::Parquet => Box::new(ParquetRecordWriter::new(file, columns)?),
+    })
+}
+
+/// 导出消费者 - 将扫描结果写入CSV、NDJSON或Parquet文件
+pub struct ExportConsumer;
+
+#[async_trait::async_trait]
+impl Consumer for ExportConsumer {
+    async fn start(
+        &mut self, mut receiver: broadcast::Receiver<ScanMessage>,
+    ) -> Result<tokio::task::JoinHandle<Result<()>>> {
+        // 文件写入为阻塞操作，放在独立线程中执行
+        let handle = tokio::task::spawn_blocking(move || {
+            let mut writer: Option<Box<dyn RecordWriter>> = None;
+            let mut exported: u64 = 0;
+
+            loop {
+                match receiver.blocking_recv() {
+                    Ok(ScanMessage::Result(result)) => {
+                        if let Some(writer) = writer.as_mut() {
+                            writer.write(&result.to_file_scan_record())?;
+                            exported += 1;
+                        }
+                    }
+                    Ok(ScanMessage::Complete) => {
+                        break;
+                    }
+                    Ok(ScanMessage::Config(config)) => {
+                        let params = &config.scan_config.params;
+                        let Some(output) = params.output.as_deref() else {
+                            continue;
+                        };
+                        let format = params
+                            .output_format
+                            .or_else(|| OutputFormat::from_path(output))
+                            .unwrap_or(OutputFormat::Csv);
+                        let columns = ExportColumn::parse_list(&params.columns)?;
+
+                        log::info!("[ExportConsumer] Exporting {} to {}", format, output);
+                        writer = Some(create_record_writer(output, format, columns)?);
+                    }
+                    Ok(ScanMessage::Failed(_)) => {
+                        // 失败后仍会收到Complete，由其完成收尾
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!(
+                            "[ExportConsumer] Channel lagged, skipped {} messages",
+                            skipped
+                        );
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(writer) = writer {
+                writer.finish()?;
+                log::info!("[ExportConsumer] Exported {} records", exported);
+            }
+            Ok(())
+        });
+
+        Ok(handle)
+    }
+
+    fn name(&self) -> &'static str {
+        "export_consumer"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::TimestampMillisecondType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn test_record(path: &str, ext: Option<&str>) -> FileScanRecord {
+        FileScanRecord {
+            path: path.to_string(),
+            size: 1024,
+            ext: ext.map(String::from),
+            ctime: 1_000,
+            mtime: 1_700_000_000_123,
+            atime: 2_000,
+            perm: Some("rw-r--r--".to_string()),
+            is_symlink: false,
+            is_dir: false,
+            is_regular_file: true,
+            hard_links: 1,
+            current_state: 0,
+            checksum: None,
+        }
+    }
+
+    fn write_records(path: &Path, format: OutputFormat, columns: &[&str]) {
+        let columns =
+            ExportColumn::parse_list(&columns.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+                .unwrap();
+        let mut writer = create_record_writer(path.to_str().unwrap(), format, columns).unwrap();
+        writer
+            .write(&test_record("/data/a.txt", Some("txt")))
+            .unwrap();
+        writer.write(&test_record("/data/Makefile", None)).unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        assert_eq!(ExportColumn::parse_list(&[]).unwrap().len(), 13);
+        assert_eq!(
+            ExportColumn::parse_list(&["path,size".to_string(), "mtime".to_string()]).unwrap(),
+            vec![ExportColumn::Path, ExportColumn::Size, ExportColumn::Mtime]
+        );
+        assert!(ExportColumn::parse_list(&["owner".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!(
+            OutputFormat::from_path("out.parquet"),
+            Some(OutputFormat::Parquet)
+        );
+        assert_eq!(
+            OutputFormat::from_path("out.jsonl"),
+            Some(OutputFormat::Ndjson)
+        );
+        assert_eq!(OutputFormat::from_path("out"), None);
+        assert_eq!("CSV".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert!("xlsx".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.csv");
+        write_records(&path, OutputFormat::Csv, &["path", "ext", "mtime"]);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "path,ext,mtime\n\
+             /data/a.txt,txt,2023-11-14T22:13:20.123Z\n\
+             /data/Makefile,,2023-11-14T22:13:20.123Z\n"
+        );
+    }
+
+    #[test]
+    fn test_export_ndjson() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.ndjson");
+        write_records(&path, OutputFormat::Ndjson, &["path", "size", "ext"]);
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["path"], "/data/a.txt");
+        assert_eq!(lines[0]["size"], 1024);
+        assert_eq!(lines[1]["ext"], serde_json::Value::Null);
+        assert!(lines[0].get("mtime").is_none());
+    }
+
+    #[test]
+    fn test_export_parquet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.parquet");
+        write_records(&path, OutputFormat::Parquet, &[]);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 13);
+
+        let schema = batch.schema();
+        let ext_field = schema.field_with_name("ext").unwrap();
+        assert!(ext_field.is_nullable());
+        assert_eq!(
+            schema.field_with_name("mtime").unwrap().data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        );
+
+        let ext = batch.column_by_name("ext").unwrap().as_string::<i32>();
+        assert_eq!(ext.value(0), "txt");
+        assert!(ext.is_null(1));
+
+        let mtime = batch
+            .column_by_name("mtime")
+            .unwrap()
+            .as_primitive::<TimestampMillisecondType>();
+        assert_eq!(mtime.value(0), 1_700_000_000_123);
+    }
+}
@@ -1,9 +1,182 @@
 use crate::consumer::Consumer;
-use crate::scan::ScanMessage;
-use tokio::sync::broadcast;
+use crate::scan::{ScanMessage, StorageEntity};
+use rdkafka::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer};
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{Semaphore, broadcast};
+use tokio::task::JoinSet;
+use utils::app_config::KafkaConfig;
 use utils::error::Result;
 
-/// 通知消费者 - 发送通知到其他系统
+/// 队列已满时的重试间隔
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
+
+/// 完成时等待所有消息投递的最长时间
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 发布到Kafka的事件，以 `event` 字段区分类型
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum KafkaEvent<'a> {
+    /// 单个扫描结果
+    Result {
+        job_id: &'a str,
+        entity: &'a StorageEntity,
+    },
+    /// 扫描完成事件，携带已发布的结果数
+    Complete { job_id: &'a str, total: u64 },
+}
+
+/// 投递统计
+#[derive(Debug, Default)]
+pub struct DeliveryStats {
+    pub delivered: AtomicU64,
+    pub failed: AtomicU64,
+}
+
+/// Kafka发布器 - 以job_id为key将事件发布到配置的topic
+///
+/// 消息按到达顺序入队，由librdkafka负责批量发送和重试；
+/// 未确认的消息数量受 `concurrency` 限制。
+pub struct KafkaPublisher {
+    producer: FutureProducer,
+    topic: String,
+    in_flight: Arc<Semaphore>,
+    deliveries: JoinSet<()>,
+    stats: Arc<DeliveryStats>,
+    published: u64,
+}
+
+impl KafkaPublisher {
+    /// 根据Kafka配置创建发布器
+    pub fn new(config: &KafkaConfig) -> Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", bootstrap_servers(config))
+            .set("acks", &config.acks)
+            .set("enable.idempotence", (config.acks == "all").to_string())
+            .set("message.send.max.retries", config.retries.to_string())
+            .set("batch.num.messages", config.batch_size.max(1).to_string())
+            .set("linger.ms", config.linger_ms.to_string())
+            .set("message.timeout.ms", config.message_timeout_ms.to_string())
+            .set("compression.type", "lz4")
+            .create()
+            .map_err(|e| {
+                utils::error::Error::with_source("Failed to create Kafka producer", Box::new(e))
+            })?;
+
+        Ok(Self {
+            producer,
+            topic: config.topic.clone(),
+            in_flight: Arc::new(Semaphore::new(config.concurrency.max(1) as usize)),
+            deliveries: JoinSet::new(),
+            stats: Arc::new(DeliveryStats::default()),
+            published: 0,
+        })
+    }
+
+    /// 发布单个扫描结果
+    pub async fn publish_result(&mut self, job_id: &str, entity: &StorageEntity) -> Result<()> {
+        self.publish(job_id, &KafkaEvent::Result { job_id, entity })
+            .await?;
+        self.published += 1;
+        Ok(())
+    }
+
+    /// 发布扫描完成事件，并等待所有消息投递确认
+    pub async fn publish_complete(&mut self, job_id: &str) -> Result<()> {
+        let total = self.published;
+        self.publish(job_id, &KafkaEvent::Complete { job_id, total })
+            .await?;
+        self.flush().await
+    }
+
+    /// 等待所有已入队消息得到确认
+    pub async fn flush(&mut self) -> Result<()> {
+        while self.deliveries.join_next().await.is_some() {}
+
+        // librdkafka的flush会阻塞当前线程，放到阻塞线程池中执行
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || producer.flush(FLUSH_TIMEOUT))
+            .await
+            .map_err(|e| utils::error::Error::with_source("Kafka flush task failed", Box::new(e)))?
+            .map_err(|e| {
+                utils::error::Error::with_source("Failed to flush Kafka producer", Box::new(e))
+            })
+    }
+
+    /// 获取投递统计
+    pub fn stats(&self) -> &DeliveryStats {
+        &self.stats
+    }
+
+    async fn publish(&mut self, job_id: &str, event: &KafkaEvent<'_>) -> Result<()> {
+        let payload = serde_json::to_vec(event).map_err(|e| {
+            utils::error::Error::with_source("Failed to serialize Kafka event", Box::new(e))
+        })?;
+
+        // 超过并发上限时等待已有消息确认
+        let permit = Arc::clone(&self.in_flight)
+            .acquire_owned()
+            .await
+            .map_err(|e| utils::error::Error::with_source("Kafka publisher closed", Box::new(e)))?;
+
+        let delivery = self.enqueue(job_id, &payload).await?;
+
+        let stats = Arc::clone(&self.stats);
+        self.deliveries.spawn(async move {
+            match delivery.await {
+                Ok(Ok(_)) => {
+                    stats.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(Err((e, _))) => {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    log::error!("[KafkaConsumer] Failed to deliver message: {}", e);
+                }
+                Err(_) => {
+                    stats.failed.fetch_add(1, Ordering::Relaxed);
+                    log::error!("[KafkaConsumer] Delivery canceled");
+                }
+            }
+            drop(permit);
+        });
+
+        // 回收已完成的投递任务
+        while self.deliveries.try_join_next().is_some() {}
+
+        Ok(())
+    }
+
+    /// 将消息放入生产者队列，队列已满时等待后重试
+    async fn enqueue(&self, key: &str, payload: &[u8]) -> Result<DeliveryFuture> {
+        let mut record = FutureRecord::to(&self.topic).key(key).payload(payload);
+        loop {
+            match self.producer.send_result(record) {
+                Ok(delivery) => return Ok(delivery),
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    record = returned;
+                    tokio::time::sleep(QUEUE_FULL_BACKOFF).await;
+                }
+                Err((e, _)) => {
+                    return Err(utils::error::Error::with_source(
+                        "Failed to enqueue Kafka message",
+                        Box::new(e),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// 根据host和port构建bootstrap.servers
+fn bootstrap_servers(config: &KafkaConfig) -> String {
+    format!("{}:{}", config.host, config.port)
+}
+
+/// 通知消费者 - 将扫描结果和完成事件发布到Kafka
 pub struct KafkaConsumer;
 
 #[async_trait::async_trait]
@@ -12,19 +185,61 @@ impl Consumer for KafkaConsumer {
         &mut self, mut receiver: broadcast::Receiver<ScanMessage>,
     ) -> Result<tokio::task::JoinHandle<Result<()>>> {
         let handle = tokio::spawn(async move {
+            let mut publisher: Option<KafkaPublisher> = None;
+            let mut job_id = String::from("unknown");
+
             loop {
                 match receiver.recv().await {
-                    Ok(ScanMessage::Result(_result)) => {
-                        // TODO: 实现通知逻辑
+                    Ok(ScanMessage::Result(result)) => {
+                        if let Some(publisher) = publisher.as_mut()
+                            && let Err(e) = publisher.publish_result(&job_id, &result).await
+                        {
+                            log::error!("[KafkaConsumer] Failed to publish scan result: {}", e);
+                        }
                     }
                     Ok(ScanMessage::Complete) => {
+                        if let Some(publisher) = publisher.as_mut() {
+                            if let Err(e) = publisher.publish_complete(&job_id).await {
+                                log::error!("[KafkaConsumer] Failed to publish completion: {}", e);
+                            }
+                            log::info!(
+                                "[KafkaConsumer] Scan completed, {} messages delivered, {} failed",
+                                publisher.stats().delivered.load(Ordering::Relaxed),
+                                publisher.stats().failed.load(Ordering::Relaxed)
+                            );
+                        }
                         break;
                     }
-                    Ok(ScanMessage::Config(_)) => {}
+                    Ok(ScanMessage::Config(config)) => {
+                        job_id = config.job_id.clone();
+
+                        match KafkaPublisher::new(&config.app_config.kafka) {
+                            Ok(instance) => {
+                                log::info!(
+                                    "[KafkaConsumer] Publishing job {} to topic {} on {}",
+                                    job_id,
+                                    config.app_config.kafka.topic,
+                                    bootstrap_servers(&config.app_config.kafka)
+                                );
+                                publisher = Some(instance);
+                            }
+                            Err(e) => {
+                                log::error!("[KafkaConsumer] Failed to create publisher: {}", e);
+                            }
+                        }
+                    }
+                    Ok(ScanMessage::Failed(_)) => {
+                        // 失败后仍会收到Complete，由其完成收尾
+                        continue;
+                    }
                     Err(broadcast::error::RecvError::Closed) => {
                         break;
                     }
-                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!(
+                            "[KafkaConsumer] Channel lagged, skipped {} messages",
+                            skipped
+                        );
                         continue;
                     }
                 }
@@ -39,3 +254,89 @@ impl Consumer for KafkaConsumer {
         "kafka_consumer"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdkafka::Message;
+    use rdkafka::consumer::{Consumer as _, StreamConsumer};
+    use rdkafka::mocking::MockCluster;
+    use std::time::SystemTime;
+
+    fn test_config(bootstrap: &str, topic: &str) -> KafkaConfig {
+        let (host, port) = bootstrap.rsplit_once(':').unwrap();
+        KafkaConfig {
+            enabled: true,
+            host: host.to_string(),
+            port: port.parse().unwrap(),
+            topic: topic.to_string(),
+            concurrency: 2,
+            acks: "all".to_string(),
+            retries: 3,
+            batch_size: 100,
+            linger_ms: 5,
+            message_timeout_ms: 5000,
+        }
+    }
+
+    fn test_entity(name: &str) -> StorageEntity {
+        StorageEntity {
+            extension: Some("txt".to_string()),
+            mtime: SystemTime::UNIX_EPOCH + Duration::from_millis(1500),
+            mode: Some(0o644),
+            permissions: Some("rw-r--r--".to_string()),
+            hard_links: Some(1),
+            ..StorageEntity::test_file(name, 42)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_publish_results_and_completion() {
+        const TOPIC: &str = "scan_events";
+        let cluster = MockCluster::new(1).unwrap();
+        let config = test_config(&cluster.bootstrap_servers(), TOPIC);
+
+        let mut publisher = KafkaPublisher::new(&config).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            publisher
+                .publish_result("job1", &test_entity(name))
+                .await
+                .unwrap();
+        }
+        publisher.publish_complete("job1").await.unwrap();
+
+        assert_eq!(publisher.stats().delivered.load(Ordering::Relaxed), 4);
+        assert_eq!(publisher.stats().failed.load(Ordering::Relaxed), 0);
+
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", cluster.bootstrap_servers())
+            .set("group.id", "terrasync-kafka-test")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .unwrap();
+        consumer.subscribe(&[TOPIC]).unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..4 {
+            let message = consumer.recv().await.unwrap();
+            assert_eq!(message.key(), Some("job1".as_bytes()));
+            let event: serde_json::Value =
+                serde_json::from_slice(message.payload().unwrap()).unwrap();
+            events.push(event);
+        }
+
+        assert_eq!(events[0]["event"], "result");
+        assert_eq!(events[0]["job_id"], "job1");
+        assert_eq!(events[0]["entity"]["file_name"], "a.txt");
+        assert_eq!(events[0]["entity"]["mtime"], 1500);
+        assert_eq!(events[2]["entity"]["file_name"], "c.txt");
+        assert_eq!(events[3]["event"], "complete");
+        assert_eq!(events[3]["total"], 3);
+    }
+
+    #[test]
+    fn test_bootstrap_servers() {
+        let config = test_config("localhost:9092", "scan");
+        assert_eq!(bootstrap_servers(&config), "localhost:9092");
+    }
+}
@@ -1,9 +1,136 @@
 use crate::consumer::Consumer;
-use crate::scan::ScanMessage;
+use crate::scan::{ScanMessage, StorageEntity, to_epoch_millis};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tokio::sync::broadcast;
 use utils::error::Result;
 
-/// 日志消费者 - 将扫描结果记录到日志
+/// 作业审计日志文件名
+pub const AUDIT_LOG_FILE_NAME: &str = "audit.jsonl";
+
+/// 记录上次运行开始时间（Unix毫秒）的文件名，用于 "changes" 模式
+const LAST_RUN_FILE_NAME: &str = "audit.last_run";
+
+/// 审计日志中的单行记录
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    job_id: &'a str,
+    #[serde(flatten)]
+    entity: &'a StorageEntity,
+}
+
+/// 按大小轮转的JSON Lines写入器
+///
+/// 当前文件超过 `max_size` 字节时重命名为 `<file>.1`，已有备份依次后移，
+/// 超出 `max_backups` 的最旧备份被删除；启用压缩时备份以 `.gz` 结尾。
+pub struct RotatingWriter {
+    path: PathBuf,
+    max_size: u64,
+    max_backups: u32,
+    compress: bool,
+    writer: BufWriter<File>,
+    written: u64,
+}
+
+impl RotatingWriter {
+    /// 以追加方式打开日志文件，`max_size` 为0时不轮转
+    pub fn open(
+        path: PathBuf, max_size: u64, max_backups: u32, compress: bool,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            max_backups,
+            compress,
+            writer: BufWriter::new(file),
+            written,
+        })
+    }
+
+    /// 写入一行，必要时先轮转
+    pub fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let line_len = line.len() as u64 + 1;
+        if self.max_size > 0 && self.written > 0 && self.written + line_len > self.max_size {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(line)?;
+        self.writer.write_all(b"\n")?;
+        self.written += line_len;
+        Ok(())
+    }
+
+    /// 刷新缓冲区
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// 获取第index个备份的路径
+    fn backup_path(&self, index: u32) -> PathBuf {
+        let suffix = if self.compress { ".gz" } else { "" };
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}{}", index, suffix));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+
+        if self.max_backups > 0 {
+            // 删除最旧的备份，其余备份依次后移
+            let oldest = self.backup_path(self.max_backups);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for index in (1..self.max_backups).rev() {
+                let from = self.backup_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.backup_path(index + 1))?;
+                }
+            }
+
+            let first = self.backup_path(1);
+            if self.compress {
+                let mut encoder = GzEncoder::new(File::create(&first)?, Compression::default());
+                io::copy(&mut File::open(&self.path)?, &mut encoder)?;
+                encoder.finish()?;
+            } else {
+                fs::rename(&self.path, &first)?;
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// 读取上次运行的开始时间
+fn read_last_run(job_dir: &Path) -> Option<i64> {
+    fs::read_to_string(job_dir.join(LAST_RUN_FILE_NAME))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// 判断实体在指定时间之后是否发生变化
+fn changed_since(entity: &StorageEntity, since: i64) -> bool {
+    to_epoch_millis(entity.mtime) >= since || to_epoch_millis(entity.ctime) >= since
+}
+
+/// 日志消费者 - 将每个扫描实体以JSON Lines写入作业审计日志
 pub struct LogConsumer;
 
 #[async_trait::async_trait]
@@ -11,24 +138,102 @@ impl Consumer for LogConsumer {
     async fn start(
         &mut self, mut receiver: broadcast::Receiver<ScanMessage>,
     ) -> Result<tokio::task::JoinHandle<Result<()>>> {
-        let handle = tokio::spawn(async move {
+        // 文件写入为阻塞操作，放在独立线程中执行
+        let handle = tokio::task::spawn_blocking(move || {
+            let run_started = to_epoch_millis(SystemTime::now());
+            let mut writer: Option<RotatingWriter> = None;
+            let mut job_id = String::from("unknown");
+            let mut job_dir: Option<PathBuf> = None;
+            let mut changed_after: Option<i64> = None;
+
             loop {
-                match receiver.recv().await {
-                    Ok(ScanMessage::Result(_result)) => {
+                match receiver.blocking_recv() {
+                    Ok(ScanMessage::Result(result)) => {
+                        let Some(writer) = writer.as_mut() else {
+                            continue;
+                        };
+                        if let Some(since) = changed_after
+                            && !changed_since(&result, since)
+                        {
+                            continue;
+                        }
+
+                        let record = AuditRecord {
+                            job_id: &job_id,
+                            entity: &result,
+                        };
+                        match serde_json::to_vec(&record) {
+                            Ok(line) => {
+                                if let Err(e) = writer.write_line(&line) {
+                                    log::error!("[LogConsumer] Failed to write audit log: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                log::error!("[LogConsumer] Failed to serialize entity: {}", e);
+                            }
+                        }
                     }
                     Ok(ScanMessage::Complete) => {
+                        if let Some(writer) = writer.as_mut()
+                            && let Err(e) = writer.flush()
+                        {
+                            log::error!("[LogConsumer] Failed to flush audit log: {}", e);
+                        }
+                        if let Some(dir) = &job_dir
+                            && let Err(e) =
+                                fs::write(dir.join(LAST_RUN_FILE_NAME), run_started.to_string())
+                        {
+                            log::error!("[LogConsumer] Failed to record run time: {}", e);
+                        }
                         break;
                     }
-                    Ok(ScanMessage::Config(_)) => {
+                    Ok(ScanMessage::Config(config)) => {
+                        let log_config = &config.app_config.log;
+                        job_id = config.job_id.clone();
+
+                        if let Err(e) = fs::create_dir_all(&config.job_dir) {
+                            log::error!("[LogConsumer] Failed to create job directory: {}", e);
+                            continue;
+                        }
+
+                        if log_config.audit_mode == "changes" {
+                            changed_after = read_last_run(&config.job_dir);
+                        }
+
+                        let path = config.job_dir.join(AUDIT_LOG_FILE_NAME);
+                        match RotatingWriter::open(
+                            path.clone(),
+                            log_config.max_size * 1024 * 1024,
+                            log_config.max_backups,
+                            log_config.compress,
+                        ) {
+                            Ok(instance) => {
+                                log::info!("[LogConsumer] Writing audit log to {}", path.display());
+                                writer = Some(instance);
+                                job_dir = Some(config.job_dir.clone());
+                            }
+                            Err(e) => {
+                                log::error!("[LogConsumer] Failed to open audit log: {}", e);
+                            }
+                        }
+                    }
+                    Ok(ScanMessage::Failed(_)) => {
+                        // 失败后仍会收到Complete，由其完成收尾
+                        continue;
                     }
                     Err(broadcast::error::RecvError::Closed) => {
                         break;
                     }
-                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("[LogConsumer] Channel lagged, skipped {} messages", skipped);
                         continue;
                     }
                 }
             }
+
+            if let Some(writer) = writer.as_mut() {
+                writer.flush()?;
+            }
             Ok(())
         });
 
@@ -39,3 +244,80 @@ impl Consumer for LogConsumer {
         "log_consumer"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rotating_writer_keeps_max_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE_NAME);
+        let mut writer = RotatingWriter::open(path.clone(), 20, 2, false).unwrap();
+
+        for i in 0..5 {
+            writer
+                .write_line(format!("line-{:010}", i).as_bytes())
+                .unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line-0000000004\n");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("audit.jsonl.1")).unwrap(),
+            "line-0000000003\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("audit.jsonl.2")).unwrap(),
+            "line-0000000002\n"
+        );
+        assert!(!dir.path().join("audit.jsonl.3").exists());
+    }
+
+    #[test]
+    fn test_rotating_writer_compresses_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE_NAME);
+        let mut writer = RotatingWriter::open(path.clone(), 10, 1, true).unwrap();
+
+        writer.write_line(b"first-line").unwrap();
+        writer.write_line(b"second-line").unwrap();
+        writer.flush().unwrap();
+
+        let mut content = String::new();
+        GzDecoder::new(File::open(dir.path().join("audit.jsonl.1.gz")).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "first-line\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second-line\n");
+    }
+
+    #[test]
+    fn test_rotating_writer_appends_to_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE_NAME);
+        fs::write(&path, "previous\n").unwrap();
+
+        let mut writer = RotatingWriter::open(path.clone(), 0, 1, false).unwrap();
+        writer.write_line(b"current").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "previous\ncurrent\n");
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let entity = StorageEntity {
+            ctime: SystemTime::UNIX_EPOCH + Duration::from_millis(1000),
+            mtime: SystemTime::UNIX_EPOCH + Duration::from_millis(2000),
+            ..StorageEntity::test_file("a.txt", 1)
+        };
+
+        assert!(changed_since(&entity, 1500));
+        assert!(changed_since(&entity, 2000));
+        assert!(!changed_since(&entity, 2001));
+    }
+}
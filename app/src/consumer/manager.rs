@@ -2,7 +2,9 @@ use tokio::sync::broadcast;
 use utils::error::Result;
 
 use crate::consumer::config::ConsumerConfig;
-use crate::consumer::{ConsoleConsumer, Consumer, DatabaseConsumer, KafkaConsumer, LogConsumer};
+use crate::consumer::{
+    ConsoleConsumer, Consumer, DatabaseConsumer, KafkaConsumer, LogConsumer, WebhookConsumer,
+};
 use crate::scan::ScanMessage;
 
 /// 消费者管理器 - 管理多个消费者
@@ -15,10 +17,13 @@ pub struct ConsumerManager {
 
 impl ConsumerManager {
     /// 创建新的消费者管理器
-    pub fn new(enable_database_consumer: bool, enable_kafka_consumer: bool) -> Self {
+    pub fn new(
+        enable_database_consumer: bool, enable_kafka_consumer: bool, enable_webhook_consumer: bool,
+    ) -> Self {
         Self::with_config(&ConsumerConfig::enable_consumer(
             enable_database_consumer,
             enable_kafka_consumer,
+            enable_webhook_consumer,
         ))
     }
 
@@ -36,6 +41,9 @@ impl ConsumerManager {
         if config.enable_kafka_consumer {
             manager.add_consumer(Box::new(KafkaConsumer));
         }
+        if config.enable_webhook_consumer {
+            manager.add_consumer(Box::new(WebhookConsumer));
+        }
         // 始终添加控制台消费者
         manager.add_consumer(Box::new(ConsoleConsumer));
         // 始终添加日志消费者
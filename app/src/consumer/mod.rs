@@ -6,10 +6,14 @@ use crate::scan::ScanMessage;
 // 子模块声明
 mod console;
 mod db;
+mod export;
 mod kafka;
 mod log;
 mod manager;
+pub mod rollup;
+pub mod spool;
 mod stats;
+mod webhook;
 
 // 公共模块
 pub mod config;
@@ -17,9 +21,12 @@ pub mod config;
 // 重新导出重要的类型，方便用户从crate根导入
 pub use console::ConsoleConsumer;
 pub use db::DatabaseConsumer;
+pub use export::{ExportColumn, ExportConsumer, OutputFormat};
 pub use kafka::KafkaConsumer;
 pub use log::LogConsumer;
 pub use manager::ConsumerManager;
+pub(crate) use stats::format_bytes;
+pub use webhook::WebhookConsumer;
 
 /// 消费者 trait - 定义消费者接口
 #[async_trait::async_trait]
@@ -0,0 +1,165 @@
+use crate::scan::{StorageEntity, to_epoch_millis};
+use db::DirRollup;
+use std::collections::HashMap;
+
+/// 扫描过程中按目录累加的汇总，文件计入所在目录及其全部上级目录
+#[derive(Debug, Clone, Default)]
+pub struct DirRollupBuilder {
+    dirs: HashMap<String, DirRollup>,
+}
+
+/// 相对路径的上级目录，根目录下的条目返回空字符串
+fn parent_of(path: &str) -> Option<&str> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(path.rfind('/').map_or("", |index| &path[..index]))
+}
+
+impl DirRollupBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entry(&mut self, path: &str) -> &mut DirRollup {
+        self.dirs
+            .entry(path.to_string())
+            .or_insert_with(|| DirRollup {
+                path: path.to_string(),
+                depth: if path.is_empty() {
+                    0
+                } else {
+                    path.split('/').count() as u32
+                },
+                ..DirRollup::default()
+            })
+    }
+
+    /// 累加一个扫描条目，路径使用相对扫描根目录的 `relative_path`
+    pub fn add(&mut self, entity: &StorageEntity) {
+        let path = entity.relative_path.trim_matches('/');
+        if entity.is_dir {
+            self.entry(path);
+        }
+        let Some(parent) = parent_of(path) else {
+            return;
+        };
+
+        let own = self.entry(parent);
+        if entity.is_dir {
+            own.own_dirs += 1;
+        } else {
+            own.own_files += 1;
+            own.own_bytes += entity.size;
+        }
+
+        let mtime = to_epoch_millis(entity.mtime);
+        let atime = to_epoch_millis(entity.atime);
+        let mut ancestor = Some(parent);
+        while let Some(dir) = ancestor {
+            let rollup = self.entry(dir);
+            if entity.is_dir {
+                rollup.total_dirs += 1;
+            } else {
+                // 0表示子树中尚无文件
+                if rollup.total_files == 0 {
+                    rollup.newest_mtime = mtime;
+                    rollup.oldest_atime = atime;
+                } else {
+                    rollup.newest_mtime = rollup.newest_mtime.max(mtime);
+                    rollup.oldest_atime = rollup.oldest_atime.min(atime);
+                }
+                rollup.total_files += 1;
+                rollup.total_bytes += entity.size;
+            }
+            ancestor = parent_of(dir);
+        }
+    }
+
+    /// 已累加的目录汇总，顺序不定
+    pub fn iter(&self) -> impl Iterator<Item = &DirRollup> {
+        self.dirs.values()
+    }
+
+    /// 结束累加，按路径排序返回全部目录汇总
+    pub fn finish(self) -> Vec<DirRollup> {
+        let mut rollups: Vec<DirRollup> = self.dirs.into_values().collect();
+        rollups.sort_by(|a, b| a.path.cmp(&b.path));
+        rollups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn entity(relative_path: &str, is_dir: bool, size: u64, secs: u64) -> StorageEntity {
+        let time = UNIX_EPOCH + Duration::from_secs(secs);
+        StorageEntity {
+            is_dir,
+            atime: time,
+            mtime: time,
+            ..StorageEntity::test_file(relative_path, size)
+        }
+    }
+
+    #[test]
+    fn test_rollup_own_and_recursive() {
+        let mut builder = DirRollupBuilder::new();
+        for e in [
+            entity("", true, 0, 0),
+            entity("a", true, 0, 0),
+            entity("a/b", true, 0, 0),
+            entity("top.txt", false, 10, 100),
+            entity("a/one.txt", false, 20, 200),
+            entity("a/b/two.txt", false, 30, 300),
+            entity("a/b/three.txt", false, 40, 50),
+            entity("empty", true, 0, 0),
+        ] {
+            builder.add(&e);
+        }
+
+        let rollups = builder.finish();
+        let paths: Vec<&str> = rollups.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["", "a", "a/b", "empty"]);
+
+        let root = &rollups[0];
+        assert_eq!((root.depth, root.own_files, root.own_dirs), (0, 1, 2));
+        assert_eq!(root.own_bytes, 10);
+        assert_eq!(
+            (root.total_files, root.total_dirs, root.total_bytes),
+            (4, 3, 100)
+        );
+        assert_eq!(root.newest_mtime, 300_000);
+        assert_eq!(root.oldest_atime, 50_000);
+
+        let a = &rollups[1];
+        assert_eq!(
+            (a.depth, a.own_files, a.own_bytes, a.own_dirs),
+            (1, 1, 20, 1)
+        );
+        assert_eq!((a.total_files, a.total_bytes, a.total_dirs), (3, 90, 1));
+
+        let b = &rollups[2];
+        assert_eq!((b.depth, b.own_files, b.total_bytes), (2, 2, 70));
+        assert_eq!((b.newest_mtime, b.oldest_atime), (300_000, 50_000));
+
+        let empty = &rollups[3];
+        assert_eq!(
+            (empty.total_files, empty.newest_mtime, empty.oldest_atime),
+            (0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_rollup_creates_missing_ancestors() {
+        let mut builder = DirRollupBuilder::new();
+        builder.add(&entity("x/y/z.bin", false, 5, 1));
+
+        let rollups = builder.finish();
+        let paths: Vec<&str> = rollups.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["", "x", "x/y"]);
+        assert!(rollups.iter().all(|r| r.total_bytes == 5));
+    }
+}
@@ -0,0 +1,274 @@
+use db::traits::{Database, FileScanRecord};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+use utils::app_config::DatabaseConfig;
+use utils::error::{Error, Result};
+
+/// 作业目录下保存写入失败批次的spool文件名，每行一条JSON格式的扫描记录
+pub const DB_SPOOL_FILE_NAME: &str = "db_spool.ndjson";
+
+/// 重试等待时间上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 批量写入的重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub(crate) retries: u32,
+    pub(crate) backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(config: &DatabaseConfig) -> Self {
+        Self {
+            retries: config.retries,
+            backoff: Duration::from_millis(config.backoff_ms),
+        }
+    }
+
+    /// 写入base表，失败时按指数退避重试，`sync` 为false时使用异步插入
+    pub async fn insert(
+        &self, db: &dyn Database, records: &[FileScanRecord], sync: bool,
+    ) -> db::Result<()> {
+        let mut attempt = 0;
+        loop {
+            let result = if sync {
+                db.batch_insert_base_record_sync(records.to_vec()).await
+            } else {
+                db.batch_insert_base_record_async(records.to_vec()).await
+            };
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < self.retries => {
+                    let delay = self
+                        .backoff
+                        .saturating_mul(2u32.saturating_pow(attempt))
+                        .min(MAX_BACKOFF);
+                    log::warn!(
+                        "[DatabaseConsumer] Failed to insert batch of {} records: {}, retrying in {:?} ({}/{})",
+                        records.len(),
+                        e,
+                        delay,
+                        attempt + 1,
+                        self.retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// 写入失败批次的本地spool文件
+pub struct DbSpool {
+    path: PathBuf,
+    /// 多个写入任务并发追加时保证批次不交错
+    lock: Mutex<()>,
+}
+
+impl DbSpool {
+    pub fn new(job_dir: &Path) -> Self {
+        Self {
+            path: job_dir.join(DB_SPOOL_FILE_NAME),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 将批次追加到spool文件
+    pub async fn append(&self, records: &[FileScanRecord]) -> Result<()> {
+        let mut buffer = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut buffer, record).map_err(|e| {
+                Error::with_source("Failed to serialize spooled record", Box::new(e))
+            })?;
+            buffer.push(b'\n');
+        }
+
+        let _guard = self.lock.lock().await;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            file.write_all(&buffer)?;
+            file.sync_data()
+        })
+        .await
+        .map_err(|e| Error::with_source("Spool writer task failed", Box::new(e)))?
+        .map_err(|e| Error::with_source("Failed to write database spool file", Box::new(e)))
+    }
+}
+
+/// 将spool文件中的记录按批次重新写入base表
+/// 全部成功后删除spool文件；某批失败时将该批及之后的记录写回spool文件并返回
+/// 返回（已写入的记录数，仍留在spool中的记录数）
+pub async fn replay_spool(
+    db: &dyn Database, path: &Path, batch_size: usize,
+) -> Result<(usize, usize)> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| Error::with_source("Failed to open database spool file", Box::new(e)))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut replayed = 0;
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut batch_lines = Vec::with_capacity(batch_size);
+
+    loop {
+        let line = lines
+            .next_line()
+            .await
+            .map_err(|e| Error::with_source("Failed to read database spool file", Box::new(e)))?;
+        let at_end = line.is_none();
+        if let Some(line) = line {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: FileScanRecord = serde_json::from_str(&line)
+                .map_err(|e| Error::with_source("Invalid record in spool file", Box::new(e)))?;
+            batch.push(record);
+            batch_lines.push(line);
+        }
+
+        if !batch.is_empty() && (batch.len() >= batch_size || at_end) {
+            if let Err(e) = db
+                .batch_insert_base_record_sync(std::mem::take(&mut batch))
+                .await
+            {
+                log::error!("Failed to replay spooled batch: {}", e);
+                let remaining = rewrite_remaining(path, batch_lines, lines).await?;
+                return Ok((replayed, remaining));
+            }
+            replayed += batch_lines.len();
+            batch_lines.clear();
+        }
+
+        if at_end {
+            break;
+        }
+    }
+
+    tokio::fs::remove_file(path)
+        .await
+        .map_err(|e| Error::with_source("Failed to remove database spool file", Box::new(e)))?;
+    Ok((replayed, 0))
+}
+
+/// 将未写入的记录写入临时文件后替换原spool文件，返回剩余记录数
+async fn rewrite_remaining(
+    path: &Path, pending: Vec<String>, mut lines: tokio::io::Lines<BufReader<tokio::fs::File>>,
+) -> Result<usize> {
+    let tmp_path = path.with_extension("ndjson.tmp");
+    let map_err = |e| Error::with_source("Failed to rewrite database spool file", Box::new(e));
+
+    let mut tmp = tokio::fs::File::create(&tmp_path).await.map_err(map_err)?;
+    let mut remaining = 0;
+    for line in pending {
+        tmp.write_all(line.as_bytes()).await.map_err(map_err)?;
+        tmp.write_all(b"\n").await.map_err(map_err)?;
+        remaining += 1;
+    }
+    while let Some(line) = lines.next_line().await.map_err(map_err)? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        tmp.write_all(line.as_bytes()).await.map_err(map_err)?;
+        tmp.write_all(b"\n").await.map_err(map_err)?;
+        remaining += 1;
+    }
+    tmp.sync_all().await.map_err(map_err)?;
+    drop(tmp);
+
+    tokio::fs::rename(&tmp_path, path).await.map_err(map_err)?;
+    Ok(remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::SqliteDatabase;
+
+    fn test_record(path: &str) -> FileScanRecord {
+        FileScanRecord {
+            path: path.to_string(),
+            size: 1,
+            ext: None,
+            ctime: 0,
+            mtime: 0,
+            atime: 0,
+            perm: None,
+            is_symlink: false,
+            is_dir: false,
+            is_regular_file: true,
+            hard_links: 1,
+            current_state: 0,
+            checksum: None,
+        }
+    }
+
+    fn test_policy(retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            retries,
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_retries() {
+        // 未创建base表，每次写入都会失败
+        let db = SqliteDatabase::new_in_memory("retry".to_string()).unwrap();
+        let records = vec![test_record("/a")];
+
+        assert!(test_policy(2).insert(&db, &records, true).await.is_err());
+
+        db.create_table(db::SCAN_BASE_TABLE_BASE_NAME)
+            .await
+            .unwrap();
+        assert!(test_policy(0).insert(&db, &records, false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_spool_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = DbSpool::new(dir.path());
+        spool
+            .append(&[test_record("/a"), test_record("/b")])
+            .await
+            .unwrap();
+        spool.append(&[test_record("/c")]).await.unwrap();
+
+        // 表不存在时重放失败，记录保留在spool中
+        let db = SqliteDatabase::new_in_memory("replay".to_string()).unwrap();
+        let (replayed, remaining) = replay_spool(&db, spool.path(), 2).await.unwrap();
+        assert_eq!((replayed, remaining), (0, 3));
+        assert!(spool.path().exists());
+
+        db.create_table(db::SCAN_BASE_TABLE_BASE_NAME)
+            .await
+            .unwrap();
+        let (replayed, remaining) = replay_spool(&db, spool.path(), 2).await.unwrap();
+        assert_eq!((replayed, remaining), (3, 0));
+        assert!(!spool.path().exists());
+
+        let mut paths: Vec<_> = db
+            .query_scan_base_table(&["path"])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/a", "/b", "/c"]);
+    }
+}
@@ -1,7 +1,151 @@
-use crate::scan::ScanParams;
+use crate::consumer::log::AUDIT_LOG_FILE_NAME;
+use crate::consumer::rollup::DirRollupBuilder;
+use crate::scan::{ScanParams, StorageEntity, to_epoch_millis};
 use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 作业目录中的扫描统计摘要文件名
+pub const SUMMARY_FILE_NAME: &str = "summary.json";
+
+/// 排行榜（扩展名、最大文件和目录）保留的条目数
+const TOP_N: usize = 10;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// 时间分布的区间上限（天），最后一个区间没有上限
+const AGE_BUCKETS: [(&str, Option<u64>); 9] = [
+    ("< 1 day", Some(1)),
+    ("1-7 days", Some(7)),
+    ("7-30 days", Some(30)),
+    ("30-90 days", Some(90)),
+    ("90-180 days", Some(180)),
+    ("180-365 days", Some(365)),
+    ("1-2 years", Some(730)),
+    ("2-5 years", Some(1825)),
+    ("> 5 years", None),
+];
+
+/// 分布区间：[min, max)，max为None表示没有上限
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistogramBucket {
+    pub label: String,
+    pub min: u64,
+    pub max: Option<u64>,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl HistogramBucket {
+    fn add(&mut self, size: u64) {
+        self.files += 1;
+        self.bytes += size;
+    }
+}
+
+/// 按扩展名汇总的文件数和容量，无扩展名的文件记为空字符串
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExtensionStats {
+    pub extension: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// 排行榜中的文件或目录，目录大小为递归容量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizedPath {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 按文件类型的条目数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileTypeCounts {
+    pub regular: u64,
+    pub directory: u64,
+    pub symlink: u64,
+    pub socket: u64,
+    pub fifo: u64,
+    pub block_device: u64,
+    pub char_device: u64,
+}
+
+impl FileTypeCounts {
+    /// 优先根据mode中的文件类型位分类，mode不含类型位时（如NFS）按目录/符号链接/普通文件分类
+    fn add(&mut self, entity: &StorageEntity) {
+        const S_IFMT: u32 = 0o170000;
+        let counter = match entity.mode.map(|mode| mode & S_IFMT) {
+            Some(0o140000) => &mut self.socket,
+            Some(0o010000) => &mut self.fifo,
+            Some(0o060000) => &mut self.block_device,
+            Some(0o020000) => &mut self.char_device,
+            _ if entity.is_dir => &mut self.directory,
+            _ if entity.is_symlink => &mut self.symlink,
+            _ => &mut self.regular,
+        };
+        *counter += 1;
+    }
+}
+
+/// 文件大小按2的幂次分区间：0字节单独一个区间，其余区间为[2^(i-1), 2^i)
+fn size_bucket(index: usize) -> HistogramBucket {
+    if index == 0 {
+        return HistogramBucket {
+            label: "0 B".to_string(),
+            min: 0,
+            max: Some(1),
+            ..HistogramBucket::default()
+        };
+    }
+    let min = 1u64 << (index - 1);
+    let max = 1u64.checked_shl(index as u32);
+    HistogramBucket {
+        label: match max {
+            Some(max) => format!(
+                "{} - {}",
+                format_bytes(min as f64),
+                format_bytes(max as f64)
+            ),
+            None => format!(">= {}", format_bytes(min as f64)),
+        },
+        min,
+        max,
+        ..HistogramBucket::default()
+    }
+}
+
+/// 预先创建全部时间区间，便于不同扫描之间对比
+fn age_buckets() -> Vec<HistogramBucket> {
+    let mut min = 0;
+    AGE_BUCKETS
+        .iter()
+        .map(|(label, max)| {
+            let bucket = HistogramBucket {
+                label: label.to_string(),
+                min,
+                max: *max,
+                ..HistogramBucket::default()
+            };
+            min = max.unwrap_or(min);
+            bucket
+        })
+        .collect()
+}
+
+/// 将条目加入容量最小堆，只保留最大的 `TOP_N` 个
+fn push_top(heap: &mut BinaryHeap<Reverse<(u64, String)>>, size: u64, path: &str) {
+    if heap.len() < TOP_N {
+        heap.push(Reverse((size, path.to_string())));
+    } else if let Some(Reverse((smallest, _))) = heap.peek()
+        && size > *smallest
+    {
+        heap.pop();
+        heap.push(Reverse((size, path.to_string())));
+    }
+}
 
 /// 扫描统计结构体 - 整体统计信息
 #[derive(Debug, Clone, Serialize)]
@@ -19,6 +163,25 @@ pub struct ScanStats {
     pub total_dir_depth: i64,    // 总目录深度
     pub max_dir_depth: usize,    // 最大目录深度
 
+    // 分布统计，排行榜由 `finalize` 生成
+    pub file_types: FileTypeCounts,
+    pub size_histogram: Vec<HistogramBucket>,
+    pub mtime_histogram: Vec<HistogramBucket>,
+    pub atime_histogram: Vec<HistogramBucket>,
+    pub top_extensions_by_count: Vec<ExtensionStats>,
+    pub top_extensions_by_bytes: Vec<ExtensionStats>,
+    pub largest_files: Vec<SizedPath>,
+    pub largest_dirs: Vec<SizedPath>,
+
+    // 生成排行榜所需的中间状态，不序列化
+    #[serde(skip)]
+    extensions: HashMap<String, ExtensionStats>,
+    #[serde(skip)]
+    largest_file_heap: BinaryHeap<Reverse<(u64, String)>>,
+    /// 按目录累加的汇总，最大目录排行取其递归容量
+    #[serde(skip)]
+    rollups: DirRollupBuilder,
+
     // 显示相关元数据
     pub command: String,
     pub job_id: String,
@@ -49,15 +212,25 @@ impl ScanStats {
                 params.exclude_expressions.join(" \"")
             ));
         }
+        if let Some(output) = &params.output {
+            command_parts.push(format!("--output \"{}\"", output));
+        }
+        if let Some(format) = &params.output_format {
+            command_parts.push(format!("--output-format {}", format));
+        }
+        if !params.columns.is_empty() {
+            command_parts.push(format!("--columns \"{}\"", params.columns.join(",")));
+        }
 
         command_parts.join(" ")
     }
 
-    /// 构建日志文件路径（使用当前执行目录）
-    pub fn build_log_path() -> String {
+    /// 构建作业审计日志路径（相对于当前执行目录）
+    pub fn build_log_path(job_dir: &Path) -> String {
         let current_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
         current_dir
-            .join("terrasync.log")
+            .join(job_dir)
+            .join(AUDIT_LOG_FILE_NAME)
             .to_string_lossy()
             .to_string()
     }
@@ -75,6 +248,55 @@ impl ScanStats {
         self.total_dir_depth = other.total_dir_depth;
         self.max_dir_depth = self.max_dir_depth.max(other.max_dir_depth);
     }
+
+    /// 根据累加的中间状态生成扩展名、最大文件和最大目录排行榜
+    pub fn finalize(&mut self) {
+        let mut extensions: Vec<ExtensionStats> = self.extensions.values().cloned().collect();
+        extensions.sort_by(|a, b| {
+            b.files
+                .cmp(&a.files)
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        self.top_extensions_by_count = extensions.iter().take(TOP_N).cloned().collect();
+        extensions.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        self.top_extensions_by_bytes = extensions.into_iter().take(TOP_N).collect();
+
+        self.largest_files = self
+            .largest_file_heap
+            .clone()
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, path))| SizedPath { path, size })
+            .collect();
+
+        let mut dirs = BinaryHeap::new();
+        for rollup in self.rollups.iter() {
+            // 扫描根目录的相对路径为空字符串
+            let path = if rollup.path.is_empty() {
+                "."
+            } else {
+                &rollup.path
+            };
+            push_top(&mut dirs, rollup.total_bytes, path);
+        }
+        self.largest_dirs = dirs
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, path))| SizedPath { path, size })
+            .collect();
+    }
+
+    /// 将统计摘要以JSON写入作业目录，返回文件路径
+    pub fn write_summary(&self, job_dir: &Path) -> std::io::Result<PathBuf> {
+        let path = job_dir.join(SUMMARY_FILE_NAME);
+        let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(path)
+    }
 }
 
 impl Default for ScanStats {
@@ -93,6 +315,19 @@ impl Default for ScanStats {
             total_dir_depth: 0,
             max_dir_depth: 0,
 
+            // 分布统计
+            file_types: FileTypeCounts::default(),
+            size_histogram: Vec::new(),
+            mtime_histogram: age_buckets(),
+            atime_histogram: age_buckets(),
+            top_extensions_by_count: Vec::new(),
+            top_extensions_by_bytes: Vec::new(),
+            largest_files: Vec::new(),
+            largest_dirs: Vec::new(),
+            extensions: HashMap::new(),
+            largest_file_heap: BinaryHeap::new(),
+            rollups: DirRollupBuilder::new(),
+
             // 显示相关元数据
             command: String::from("terrasync scan"),
             job_id: String::new(),
@@ -102,6 +337,24 @@ impl Default for ScanStats {
     }
 }
 
+/// 格式化字节大小
+pub(crate) fn format_bytes(bytes: f64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        format!("{:.0} {}", size, UNITS[unit_index])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit_index])
+    }
+}
+
 impl fmt::Display for ScanStats {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let total_items = self.total_files + self.total_dirs;
@@ -121,24 +374,6 @@ impl fmt::Display for ScanStats {
             0.0
         };
 
-        // 格式化字节大小
-        fn format_bytes(bytes: f64) -> String {
-            const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
-            let mut size = bytes;
-            let mut unit_index = 0;
-
-            while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-                size /= 1024.0;
-                unit_index += 1;
-            }
-
-            if unit_index == 0 {
-                format!("{:.0} {}", size, UNITS[unit_index])
-            } else {
-                format!("{:.2} {}", size, UNITS[unit_index])
-            }
-        }
-
         writeln!(
             f,
             "=================================================================="
@@ -218,6 +453,57 @@ impl fmt::Display for ScanStats {
             "   Max:                                           {}",
             self.max_dir_depth
         )?;
+        writeln!(
+            f,
+            " -------------------------- File Types ---------------------------"
+        )?;
+        let types = &self.file_types;
+        for (label, count) in [
+            ("Regular files", types.regular),
+            ("Directories", types.directory),
+            ("Symlinks", types.symlink),
+            ("Sockets", types.socket),
+            ("FIFOs", types.fifo),
+            ("Block devices", types.block_device),
+            ("Char devices", types.char_device),
+        ] {
+            writeln!(f, "   {:<44}{}", format!("{}:", label), count)?;
+        }
+        writeln!(
+            f,
+            " ----------------------- Size Distribution -----------------------"
+        )?;
+        write_histogram(f, &self.size_histogram)?;
+        writeln!(
+            f,
+            " ------------------------ Modified Age ---------------------------"
+        )?;
+        write_histogram(f, &self.mtime_histogram)?;
+        writeln!(
+            f,
+            " ------------------------ Accessed Age ---------------------------"
+        )?;
+        write_histogram(f, &self.atime_histogram)?;
+        writeln!(
+            f,
+            " ------------------- Top Extensions by Count ---------------------"
+        )?;
+        write_extensions(f, &self.top_extensions_by_count)?;
+        writeln!(
+            f,
+            " ------------------- Top Extensions by Size ----------------------"
+        )?;
+        write_extensions(f, &self.top_extensions_by_bytes)?;
+        writeln!(
+            f,
+            " ------------------------ Largest Files --------------------------"
+        )?;
+        write_sized_paths(f, &self.largest_files)?;
+        writeln!(
+            f,
+            " --------------------- Largest Directories -----------------------"
+        )?;
+        write_sized_paths(f, &self.largest_dirs)?;
         writeln!(
             f,
             " -------------------------------------------------------------"
@@ -229,18 +515,118 @@ impl fmt::Display for ScanStats {
     }
 }
 
+/// 输出非空的分布区间：区间、文件数、容量
+fn write_histogram(f: &mut fmt::Formatter<'_>, buckets: &[HistogramBucket]) -> fmt::Result {
+    for bucket in buckets.iter().filter(|bucket| bucket.files > 0) {
+        writeln!(
+            f,
+            "   {:<26}{:>12} files {:>14}",
+            bucket.label,
+            bucket.files,
+            format_bytes(bucket.bytes as f64)
+        )?;
+    }
+    Ok(())
+}
+
+fn write_extensions(f: &mut fmt::Formatter<'_>, extensions: &[ExtensionStats]) -> fmt::Result {
+    for ext in extensions {
+        let label = if ext.extension.is_empty() {
+            "(none)"
+        } else {
+            &ext.extension
+        };
+        writeln!(
+            f,
+            "   {:<26}{:>12} files {:>14}",
+            label,
+            ext.files,
+            format_bytes(ext.bytes as f64)
+        )?;
+    }
+    Ok(())
+}
+
+fn write_sized_paths(f: &mut fmt::Formatter<'_>, entries: &[SizedPath]) -> fmt::Result {
+    for entry in entries {
+        writeln!(
+            f,
+            "   {:>12}  {}",
+            format_bytes(entry.size as f64),
+            entry.path
+        )?;
+    }
+    Ok(())
+}
+
 /// 统计计算器 - 处理所有统计相关的计算逻辑
 pub struct StatsCalculator {
     base_path: String,
+    /// 计算文件时间分布的基准时间（Unix毫秒时间戳）
+    now: i64,
 }
 
 impl StatsCalculator {
     pub fn new(base_path: &str) -> Self {
         Self {
             base_path: base_path.to_string(),
+            now: to_epoch_millis(SystemTime::now()),
+        }
+    }
+
+    /// 根据单个扫描结果更新统计信息
+    pub fn update(&self, stats: &mut ScanStats, entity: &StorageEntity) {
+        stats.file_types.add(entity);
+        if entity.is_dir {
+            stats.total_dirs += 1;
+            let depth = self.calculate_depth(Path::new(&entity.file_path));
+            self.update_dir_stats(stats, &entity.file_name, depth);
+        } else {
+            stats.total_files += 1;
+            self.update_file_stats(stats, &entity.file_name, entity.size, entity.is_symlink);
+            self.update_distributions(stats, entity);
         }
     }
 
+    /// 更新文件大小、时间、扩展名分布以及最大文件和目录
+    fn update_distributions(&self, stats: &mut ScanStats, entity: &StorageEntity) {
+        let size = entity.size;
+
+        let index = (u64::BITS - size.leading_zeros()) as usize;
+        while stats.size_histogram.len() <= index {
+            let next = stats.size_histogram.len();
+            stats.size_histogram.push(size_bucket(next));
+        }
+        stats.size_histogram[index].add(size);
+        stats.mtime_histogram[self.age_bucket(entity.mtime)].add(size);
+        stats.atime_histogram[self.age_bucket(entity.atime)].add(size);
+
+        let extension = entity.extension.clone().unwrap_or_default();
+        let extension_stats = stats
+            .extensions
+            .entry(extension.clone())
+            .or_insert_with(|| ExtensionStats {
+                extension,
+                ..ExtensionStats::default()
+            });
+        extension_stats.files += 1;
+        extension_stats.bytes += size;
+
+        push_top(&mut stats.largest_file_heap, size, &entity.file_path);
+
+        // 文件容量计入其所在目录直至扫描根目录的每一级
+        stats.rollups.add(entity);
+    }
+
+    /// 文件时间距扫描开始的天数所在的区间，晚于扫描开始的时间计入第一个区间
+    fn age_bucket(&self, time: SystemTime) -> usize {
+        let days = ((self.now - to_epoch_millis(time)).max(0) / MILLIS_PER_DAY) as u64;
+        AGE_BUCKETS
+            .iter()
+            .position(|(_, max)| max.is_none_or(|max| days < max))
+            .unwrap_or(AGE_BUCKETS.len() - 1)
+    }
+
     /// 更新文件统计信息
     pub fn update_file_stats(
         &self, stats: &mut ScanStats, file_name: &str, file_size: u64, is_symlink: bool,
@@ -282,3 +668,154 @@ impl StatsCalculator {
             .unwrap_or(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn test_entity(relative_path: &str, size: u64, age_days: u64, mode: u32) -> StorageEntity {
+        let time = SystemTime::now() - Duration::from_secs(age_days * 86_400 + 60);
+        let entity = StorageEntity::test_file(relative_path, size);
+        StorageEntity {
+            extension: entity
+                .file_name
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_string()),
+            is_dir: mode & 0o170000 == 0o040000,
+            is_symlink: mode & 0o170000 == 0o120000,
+            ctime: time,
+            mtime: time,
+            mode: Some(mode),
+            ..entity
+        }
+    }
+
+    fn scan_stats(entities: &[StorageEntity]) -> ScanStats {
+        let calculator = StatsCalculator::new("/");
+        let mut stats = ScanStats::default();
+        for entity in entities {
+            calculator.update(&mut stats, entity);
+        }
+        stats.finalize();
+        stats
+    }
+
+    #[test]
+    fn test_distributions_and_rankings() {
+        let stats = scan_stats(&[
+            test_entity("", 0, 0, 0o040755),
+            test_entity("a", 0, 0, 0o040755),
+            test_entity("a/big.iso", 5_000, 400, 0o100644),
+            test_entity("a/one.txt", 3, 0, 0o100644),
+            test_entity("two.txt", 2, 10, 0o100644),
+            test_entity("empty", 0, 2000, 0o100644),
+            test_entity("link", 8, 0, 0o120777),
+            test_entity("run.sock", 0, 0, 0o140755),
+            test_entity("pipe", 0, 0, 0o010644),
+            test_entity("sda", 0, 0, 0o060660),
+            test_entity("tty", 0, 0, 0o020620),
+        ]);
+
+        let types = &stats.file_types;
+        assert_eq!((types.directory, types.regular, types.symlink), (2, 4, 1));
+        assert_eq!((types.socket, types.fifo), (1, 1));
+        assert_eq!((types.block_device, types.char_device), (1, 1));
+
+        // 0字节、[2,4)、[8,16)、[4096,8192)
+        let size_buckets: Vec<(u64, u64)> = stats
+            .size_histogram
+            .iter()
+            .filter(|bucket| bucket.files > 0)
+            .map(|bucket| (bucket.min, bucket.files))
+            .collect();
+        assert_eq!(size_buckets, [(0, 5), (2, 2), (8, 1), (4096, 1)]);
+        assert_eq!(stats.size_histogram[0].label, "0 B");
+        assert_eq!(stats.size_histogram[13].label, "4.00 KiB - 8.00 KiB");
+
+        let mtime: Vec<(&str, u64)> = stats
+            .mtime_histogram
+            .iter()
+            .map(|bucket| (bucket.label.as_str(), bucket.files))
+            .filter(|(_, files)| *files > 0)
+            .collect();
+        assert_eq!(
+            mtime,
+            [
+                ("< 1 day", 6),
+                ("7-30 days", 1),
+                ("1-2 years", 1),
+                ("> 5 years", 1)
+            ]
+        );
+        // atime为纪元时间，全部落入最后一个区间
+        assert_eq!(stats.atime_histogram.last().unwrap().files, 9);
+
+        assert_eq!(stats.top_extensions_by_count[0].extension, "");
+        assert_eq!(stats.top_extensions_by_count[0].files, 5);
+        assert_eq!(stats.top_extensions_by_count[1].extension, "txt");
+        assert_eq!(stats.top_extensions_by_count[1].files, 2);
+        assert_eq!(stats.top_extensions_by_bytes[0].extension, "iso");
+
+        assert_eq!(stats.largest_files[0].path, "/data/a/big.iso");
+        assert_eq!(stats.largest_files[1].size, 8);
+        assert_eq!(
+            stats.largest_dirs[..2],
+            [
+                SizedPath {
+                    path: ".".to_string(),
+                    size: 5_013
+                },
+                SizedPath {
+                    path: "a".to_string(),
+                    size: 5_003
+                }
+            ]
+        );
+
+        let report = stats.to_string();
+        assert!(report.contains("Sockets:"));
+        assert!(report.contains("/data/a/big.iso"));
+    }
+
+    #[test]
+    fn test_largest_files_keep_top_n() {
+        let entities: Vec<StorageEntity> = (0..25)
+            .map(|i| test_entity(&format!("f{}.bin", i), i * 100, 0, 0o100644))
+            .collect();
+        let stats = scan_stats(&entities);
+
+        let sizes: Vec<u64> = stats.largest_files.iter().map(|f| f.size).collect();
+        assert_eq!(sizes, (15..25).rev().map(|i| i * 100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let stats = scan_stats(&[test_entity("a.txt", 10, 0, 0o100644)]);
+
+        let path = stats.write_summary(dir.path()).unwrap();
+        assert_eq!(path, dir.path().join(SUMMARY_FILE_NAME));
+
+        let summary: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        assert_eq!(summary["total_files"], 1);
+        assert_eq!(summary["file_types"]["regular"], 1);
+        assert_eq!(summary["largest_files"][0]["path"], "/data/a.txt");
+        assert_eq!(summary["mtime_histogram"].as_array().unwrap().len(), 9);
+        assert!(summary.get("extensions").is_none());
+    }
+
+    #[test]
+    fn test_update_counts_file_size_once() {
+        let calculator = StatsCalculator::new("/data");
+        let mut stats = ScanStats::default();
+        calculator.update(&mut stats, &test_entity("dir", 4096, 0, 0o040755));
+        calculator.update(&mut stats, &test_entity("dir/a.txt", 100, 0, 0o100644));
+        calculator.update(&mut stats, &test_entity("b.txt", 50, 0, 0o100644));
+
+        assert_eq!(stats.total_dirs, 1);
+        assert_eq!(stats.total_files, 2);
+        assert_eq!(stats.total_size, 150);
+    }
+}
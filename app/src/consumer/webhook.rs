@@ -0,0 +1,486 @@
+use crate::consumer::Consumer;
+use crate::consumer::stats::{ScanStats, StatsCalculator};
+use crate::scan::{ScanMessage, ScanParams, to_epoch_millis};
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::Sha256;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, mpsc};
+use utils::app_config::WebhookConfig;
+use utils::error::{Error, Result};
+
+/// 事件类型请求头
+pub const EVENT_HEADER: &str = "X-Terrasync-Event";
+
+/// 签名请求头，值为 `sha256=<hex>`，对请求体做HMAC-SHA256
+pub const SIGNATURE_HEADER: &str = "X-Terrasync-Signature";
+
+/// 重试等待时间上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 待发送事件队列容量，队列满时丢弃进度事件
+const EVENT_QUEUE_CAPACITY: usize = 64;
+
+/// 作业生命周期事件，以 `event` 字段区分类型
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event")]
+pub enum WebhookEvent {
+    /// 作业开始，携带扫描参数
+    #[serde(rename = "job_started")]
+    Started { params: ScanParams },
+    /// 周期性进度
+    #[serde(rename = "job_progress")]
+    Progress {
+        total_files: usize,
+        total_dirs: usize,
+        total_size: i64,
+    },
+    /// 作业完成，携带最终统计
+    #[serde(rename = "job_completed")]
+    Completed { stats: ScanStats },
+    /// 作业失败，携带失败原因及失败前的统计
+    #[serde(rename = "job_failed")]
+    Failed { error: String, stats: ScanStats },
+}
+
+impl WebhookEvent {
+    /// 事件名称，与序列化后的 `event` 字段一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::Started { .. } => "job_started",
+            WebhookEvent::Progress { .. } => "job_progress",
+            WebhookEvent::Completed { .. } => "job_completed",
+            WebhookEvent::Failed { .. } => "job_failed",
+        }
+    }
+}
+
+/// 请求体
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    job_id: &'a str,
+    /// 事件产生时间（Unix毫秒）
+    timestamp: i64,
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+}
+
+/// 计算请求体签名
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Webhook客户端 - 将事件POST到所有配置的URL
+///
+/// 网络错误、429和5xx响应按指数退避重试，其余4xx响应视为永久失败。
+pub struct WebhookClient {
+    client: reqwest::Client,
+    urls: Vec<String>,
+    secret: Option<Vec<u8>>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl WebhookClient {
+    /// 根据Webhook配置创建客户端
+    pub fn new(config: &WebhookConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout.max(1)))
+            .build()
+            .map_err(|e| Error::with_source("Failed to create webhook client", Box::new(e)))?;
+
+        Ok(Self {
+            client,
+            urls: config.urls.clone(),
+            secret: config
+                .secret
+                .as_ref()
+                .filter(|s| !s.is_empty())
+                .map(|s| s.as_bytes().to_vec()),
+            retries: config.retries,
+            backoff: Duration::from_millis(config.backoff_ms),
+        })
+    }
+
+    /// 发送事件到所有URL，任一URL最终失败时返回错误
+    pub async fn send(&self, job_id: &str, event: &WebhookEvent) -> Result<()> {
+        let payload = WebhookPayload {
+            job_id,
+            timestamp: to_epoch_millis(SystemTime::now()),
+            event,
+        };
+        let body = serde_json::to_vec(&payload)
+            .map_err(|e| Error::with_source("Failed to serialize webhook event", Box::new(e)))?;
+
+        let mut failed = Vec::new();
+        for url in &self.urls {
+            if let Err(e) = self.post(url, event.name(), &body).await {
+                log::error!(
+                    "[WebhookConsumer] Failed to deliver {} to {}: {}",
+                    event.name(),
+                    url,
+                    e
+                );
+                failed.push(url.as_str());
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(&format!(
+                "Failed to deliver {} to {}",
+                event.name(),
+                failed.join(", ")
+            )))
+        }
+    }
+
+    async fn post(&self, url: &str, event_name: &str, body: &[u8]) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event_name)
+                .body(body.to_vec());
+            if let Some(secret) = &self.secret {
+                request = request.header(SIGNATURE_HEADER, sign(secret, body));
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) if !is_retryable(response.status()) => {
+                    return Err(Error::new(&format!(
+                        "Webhook rejected with status {}",
+                        response.status()
+                    )));
+                }
+                Ok(response) => {
+                    Error::new(&format!("Webhook returned status {}", response.status()))
+                }
+                Err(e) => Error::with_source("Webhook request failed", Box::new(e)),
+            };
+
+            if attempt >= self.retries {
+                return Err(error);
+            }
+            let delay = self
+                .backoff
+                .saturating_mul(2u32.saturating_pow(attempt))
+                .min(MAX_BACKOFF);
+            log::warn!(
+                "[WebhookConsumer] {}, retrying in {:?} ({}/{})",
+                error,
+                delay,
+                attempt + 1,
+                self.retries
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// 判断响应状态是否值得重试
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Webhook消费者 - 将作业开始、进度、完成和失败事件POST到配置的URL
+pub struct WebhookConsumer;
+
+#[async_trait::async_trait]
+impl Consumer for WebhookConsumer {
+    async fn start(
+        &mut self, mut receiver: broadcast::Receiver<ScanMessage>,
+    ) -> Result<tokio::task::JoinHandle<Result<()>>> {
+        let handle = tokio::spawn(async move {
+            let start_time = Instant::now();
+            let mut stats = ScanStats::default();
+            let mut calculator = None::<StatsCalculator>;
+            let mut progress_interval = None::<Duration>;
+            let mut last_progress_time = Instant::now();
+            let mut failure = None::<String>;
+            // 事件由独立任务按顺序发送，避免阻塞广播接收
+            let mut events = None::<mpsc::Sender<WebhookEvent>>;
+            let mut dispatcher = None::<tokio::task::JoinHandle<()>>;
+
+            loop {
+                match receiver.recv().await {
+                    Ok(ScanMessage::Result(result)) => {
+                        let Some(events) = &events else {
+                            continue;
+                        };
+
+                        let calc = calculator.get_or_insert_with(|| {
+                            let base_path = Path::new(&result.file_path)
+                                .parent()
+                                .map(|p| p.to_string_lossy().to_string())
+                                .unwrap_or_else(|| ".".to_string());
+                            StatsCalculator::new(&base_path)
+                        });
+                        calc.update(&mut stats, &result);
+
+                        if let Some(interval) = progress_interval
+                            && last_progress_time.elapsed() >= interval
+                        {
+                            let progress = WebhookEvent::Progress {
+                                total_files: stats.total_files,
+                                total_dirs: stats.total_dirs,
+                                total_size: stats.total_size,
+                            };
+                            if events.try_send(progress).is_err() {
+                                log::warn!("[WebhookConsumer] Event queue full, dropping progress");
+                            }
+                            last_progress_time = Instant::now();
+                        }
+                    }
+                    Ok(ScanMessage::Failed(reason)) => {
+                        failure = Some(reason);
+                    }
+                    Ok(ScanMessage::Complete) => {
+                        if let Some(events) = events.take() {
+                            stats.total_time =
+                                format!("{:.2}s", start_time.elapsed().as_secs_f64());
+                            stats.finalize();
+                            let event = match failure.take() {
+                                Some(error) => WebhookEvent::Failed {
+                                    error,
+                                    stats: stats.clone(),
+                                },
+                                None => WebhookEvent::Completed {
+                                    stats: stats.clone(),
+                                },
+                            };
+                            let _ = events.send(event).await;
+                        }
+                        break;
+                    }
+                    Ok(ScanMessage::Config(config)) => {
+                        let webhook_config = &config.app_config.webhook;
+                        if webhook_config.urls.is_empty() {
+                            log::warn!("[WebhookConsumer] No webhook URLs configured");
+                            continue;
+                        }
+
+                        let client = match WebhookClient::new(webhook_config) {
+                            Ok(client) => client,
+                            Err(e) => {
+                                log::error!("[WebhookConsumer] Failed to create client: {}", e);
+                                continue;
+                            }
+                        };
+
+                        let params = &config.scan_config.params;
+                        stats.command = ScanStats::build_command(params);
+                        stats.job_id = config.job_id.clone();
+                        stats.log_path = ScanStats::build_log_path(&config.job_dir);
+                        progress_interval =
+                            Some(Duration::from_secs(webhook_config.progress_interval))
+                                .filter(|interval| !interval.is_zero());
+
+                        let (sender, mut queue) = mpsc::channel(EVENT_QUEUE_CAPACITY);
+                        let job_id = config.job_id.clone();
+                        dispatcher = Some(tokio::spawn(async move {
+                            while let Some(event) = queue.recv().await {
+                                let _ = client.send(&job_id, &event).await;
+                            }
+                        }));
+
+                        let started = WebhookEvent::Started {
+                            params: params.clone(),
+                        };
+                        let _ = sender.send(started).await;
+                        events = Some(sender);
+                        last_progress_time = Instant::now();
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!(
+                            "[WebhookConsumer] Channel lagged, skipped {} messages",
+                            skipped
+                        );
+                        continue;
+                    }
+                }
+            }
+
+            // 关闭事件队列并等待剩余事件发送完毕
+            drop(events);
+            if let Some(dispatcher) = dispatcher {
+                let _ = dispatcher.await;
+            }
+            Ok(())
+        });
+
+        Ok(handle)
+    }
+
+    fn name(&self) -> &'static str {
+        "webhook_consumer"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::scan::ConsumerConfig;
+    use crate::scan::{ScanConfig, StorageEntity};
+    use std::path::PathBuf;
+    use utils::app_config::AppConfig;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_config(urls: Vec<String>) -> WebhookConfig {
+        WebhookConfig {
+            enabled: true,
+            urls,
+            secret: Some("s3cret".to_string()),
+            timeout: 5,
+            retries: 2,
+            backoff_ms: 10,
+            progress_interval: 0,
+        }
+    }
+
+    fn request_body(request: &wiremock::Request) -> serde_json::Value {
+        serde_json::from_slice(&request.body).unwrap()
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 测试用例2
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_server_errors_and_signs_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header(EVENT_HEADER, "job_failed"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client =
+            WebhookClient::new(&test_config(vec![format!("{}/hook", server.uri())])).unwrap();
+        let event = WebhookEvent::Failed {
+            error: "boom".to_string(),
+            stats: ScanStats::default(),
+        };
+        client.send("job1", &event).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let last = requests.last().unwrap();
+        let signature = last
+            .headers
+            .get(SIGNATURE_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert_eq!(signature, sign(b"s3cret", &last.body));
+
+        let body = request_body(last);
+        assert_eq!(body["event"], "job_failed");
+        assert_eq!(body["job_id"], "job1");
+        assert_eq!(body["error"], "boom");
+    }
+
+    #[tokio::test]
+    async fn test_send_does_not_retry_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = WebhookClient::new(&test_config(vec![server.uri()])).unwrap();
+        let event = WebhookEvent::Progress {
+            total_files: 1,
+            total_dirs: 0,
+            total_size: 1,
+        };
+        assert!(client.send("job1", &event).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_consumer_posts_lifecycle_events() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        AppConfig::init(Some(include_str!(
+            "../../../src/resources/default_config.toml"
+        )))
+        .unwrap();
+        let mut app_config = AppConfig::fetch().unwrap();
+        app_config.webhook = test_config(vec![server.uri()]);
+
+        let params = ScanParams {
+            id: Some("job1".to_string()),
+            path: "/data".to_string(),
+            ..ScanParams::default()
+        };
+        let config = ConsumerConfig {
+            app_config,
+            scan_config: ScanConfig {
+                params,
+                expressions: Vec::new(),
+                exclude_expressions: Vec::new(),
+            },
+            job_id: "job1".to_string(),
+            job_dir: PathBuf::from("jobs/scan_job1"),
+        };
+
+        let (sender, receiver) = broadcast::channel(16);
+        let handle = WebhookConsumer.start(receiver).await.unwrap();
+        sender.send(ScanMessage::Config(config)).unwrap();
+        sender
+            .send(ScanMessage::Result(StorageEntity {
+                is_dir: true,
+                ..StorageEntity::test_file("dir", 100)
+            }))
+            .unwrap();
+        sender
+            .send(ScanMessage::Result(StorageEntity::test_file("a.txt", 100)))
+            .unwrap();
+        sender.send(ScanMessage::Complete).unwrap();
+        handle.await.unwrap().unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let started = request_body(&requests[0]);
+        assert_eq!(started["event"], "job_started");
+        assert_eq!(started["params"]["path"], "/data");
+
+        let completed = request_body(&requests[1]);
+        assert_eq!(completed["event"], "job_completed");
+        assert_eq!(completed["job_id"], "job1");
+        assert_eq!(completed["stats"]["total_files"], 1);
+        assert_eq!(completed["stats"]["total_dirs"], 1);
+        assert_eq!(completed["stats"]["total_size"], 100);
+    }
+}
@@ -0,0 +1,155 @@
+use db::config::{ClickHouseConfig, DatabaseConfig, PostgresConfig, SqliteConfig};
+use db::factory::create_database;
+use db::migration::{self, MigrationStatus};
+use std::path::PathBuf;
+use utils::app_config::{AppConfig, DatabaseConfig as AppDatabaseConfig};
+use utils::error::{Error, Result};
+
+use crate::consumer::spool::{DB_SPOOL_FILE_NAME, replay_spool};
+use crate::job::JOBS_DIR;
+
+/// 配置文件中的空字符串视为未配置
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.clone().filter(|value| !value.is_empty())
+}
+
+/// 根据应用配置构建数据库配置
+pub fn database_config(config: &AppDatabaseConfig) -> DatabaseConfig {
+    DatabaseConfig {
+        enabled: config.enabled,
+        db_type: config.r#type.clone(),
+        batch_size: config.batch_size,
+        clickhouse: Some(ClickHouseConfig {
+            dsn: config.clickhouse.dsn.clone(),
+            dial_timeout: config.clickhouse.dial_timeout,
+            read_timeout: config.clickhouse.read_timeout,
+            database: config.clickhouse.database.clone(),
+            username: config.clickhouse.username.clone(),
+            password: config.clickhouse.password.clone(),
+            inserter_max_rows: config.clickhouse.inserter_max_rows,
+            inserter_max_bytes: config.clickhouse.inserter_max_bytes,
+            inserter_period: config.clickhouse.inserter_period,
+            compression: config.clickhouse.compression,
+            password_env: non_empty(&config.clickhouse.password_env),
+            password_file: non_empty(&config.clickhouse.password_file),
+            ca_cert: non_empty(&config.clickhouse.ca_cert),
+            client_cert: non_empty(&config.clickhouse.client_cert),
+            client_key: non_empty(&config.clickhouse.client_key),
+            settings: config.clickhouse.settings.clone(),
+            table_layout: config.clickhouse.table_layout.clone(),
+            retain_generations: config.clickhouse.retain_generations,
+        }),
+        sqlite: Some(SqliteConfig {
+            path: config.sqlite.path.clone(),
+        }),
+        postgres: Some(PostgresConfig {
+            dsn: config.postgres.dsn.clone(),
+            dial_timeout: config.postgres.dial_timeout,
+            database: config.postgres.database.clone(),
+            username: config.postgres.username.clone(),
+            password: config.postgres.password.clone(),
+        }),
+    }
+}
+
+/// 查询全部作业表的表结构迁移状态，`apply` 为true时先应用待执行的迁移
+pub async fn migrate(apply: bool) -> Result<Vec<MigrationStatus>> {
+    let app_config = AppConfig::fetch()
+        .map_err(|e| Error::with_source("Failed to load application configuration", Box::new(e)))?;
+    let db_config = database_config(&app_config.database);
+
+    // 迁移面向全部作业表，job_id仅用于满足工厂接口
+    let db = create_database(&db_config, "migrate".to_string())
+        .map_err(|e| Error::with_source("Failed to create database", Box::new(e)))?;
+    db.ping()
+        .await
+        .map_err(|e| Error::with_source("Failed to connect to database", Box::new(e)))?;
+
+    let result = if apply {
+        migration::migrate_all(db.as_ref()).await
+    } else {
+        migration::migration_status(db.as_ref()).await
+    };
+    let _ = db.close().await;
+
+    result.map_err(|e| Error::with_source("Failed to migrate database schema", Box::new(e)))
+}
+
+/// 单个作业spool文件的重放结果
+#[derive(Debug, Clone)]
+pub struct ReplayReport {
+    pub job_id: String,
+    pub spool_path: PathBuf,
+    pub replayed: usize,
+    pub remaining: usize,
+}
+
+/// 查找作业目录下的spool文件，返回（job_id, spool文件路径）
+fn find_spools(job_id: Option<&str>) -> Result<Vec<(String, PathBuf)>> {
+    let entries = match std::fs::read_dir(JOBS_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(Error::with_source(
+                "Failed to read jobs directory",
+                Box::new(e),
+            ));
+        }
+    };
+
+    let mut spools = Vec::new();
+    for entry in entries.flatten() {
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        // 作业目录名为 <type>_<id>
+        let Some((_, id)) = dir_name.split_once('_') else {
+            continue;
+        };
+        if job_id.is_some_and(|job_id| job_id != id) {
+            continue;
+        }
+        let spool_path = entry.path().join(DB_SPOOL_FILE_NAME);
+        if spool_path.is_file() {
+            spools.push((id.to_string(), spool_path));
+        }
+    }
+    spools.sort();
+    Ok(spools)
+}
+
+/// 将作业目录中写入失败的spool记录重新写入数据库，未指定作业时处理全部作业
+pub async fn replay(job_id: Option<&str>) -> Result<Vec<ReplayReport>> {
+    let app_config = AppConfig::fetch()
+        .map_err(|e| Error::with_source("Failed to load application configuration", Box::new(e)))?;
+    let db_config = database_config(&app_config.database);
+    let batch_size = (db_config.batch_size as usize).max(1);
+
+    let mut reports = Vec::new();
+    for (job_id, spool_path) in find_spools(job_id)? {
+        let db = create_database(&db_config, job_id.clone())
+            .map_err(|e| Error::with_source("Failed to create database", Box::new(e)))?;
+        db.ping()
+            .await
+            .map_err(|e| Error::with_source("Failed to connect to database", Box::new(e)))?;
+        db.create_table(db::SCAN_BASE_TABLE_BASE_NAME)
+            .await
+            .map_err(|e| Error::with_source("Failed to create scan_base table", Box::new(e)))?;
+
+        let result = replay_spool(db.as_ref(), &spool_path, batch_size).await;
+        // spool全部写入后提交扫描时未能提交的代次
+        if let Ok((_, 0)) = result {
+            db.commit_scan_generation()
+                .await
+                .map_err(|e| Error::with_source("Failed to commit scan generation", Box::new(e)))?;
+        }
+        let _ = db.close().await;
+        let (replayed, remaining) = result?;
+
+        reports.push(ReplayReport {
+            job_id,
+            spool_path,
+            replayed,
+            remaining,
+        });
+    }
+    Ok(reports)
+}
@@ -0,0 +1,18 @@
+use std::path::{Path, PathBuf};
+
+/// 作业根目录
+pub const JOBS_DIR: &str = "jobs";
+
+/// 构建作业目录路径：jobs/<type>_<id>
+pub fn job_dir(job_type: &str, job_id: &str) -> PathBuf {
+    PathBuf::from(JOBS_DIR).join(format!("{}_{}", job_type, job_id))
+}
+
+/// 从作业目录名 `<type>_<id>` 中解析作业类型
+pub fn job_type(job_dir: &Path) -> String {
+    job_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split_once('_'))
+        .map_or_else(|| "scan".to_string(), |(job_type, _)| job_type.to_string())
+}
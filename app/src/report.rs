@@ -0,0 +1,114 @@
+use chrono::{Local, TimeZone};
+use db::DirRollup;
+use db::factory::create_database;
+use std::fmt::Write;
+use utils::app_config::AppConfig;
+use utils::error::{Error, Result};
+
+use crate::consumer::format_bytes;
+use crate::database::database_config;
+
+/// 查询作业的目录汇总，`depth` 限制相对扫描根目录的最大深度
+pub async fn dir_tree(job_id: &str, depth: Option<u32>) -> Result<Vec<DirRollup>> {
+    let app_config = AppConfig::fetch()
+        .map_err(|e| Error::with_source("Failed to load application configuration", Box::new(e)))?;
+    let db_config = database_config(&app_config.database);
+
+    let db = create_database(&db_config, job_id.to_string())
+        .map_err(|e| Error::with_source("Failed to create database", Box::new(e)))?;
+    db.ping()
+        .await
+        .map_err(|e| Error::with_source("Failed to connect to database", Box::new(e)))?;
+
+    let table = db::get_scan_dirs_table_name(job_id);
+    let result = match db.table_exists(&table).await {
+        Ok(true) => db.query_dir_rollups(depth).await,
+        Ok(false) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    };
+    let _ = db.close().await;
+
+    result.map_err(|e| Error::with_source("Failed to query directory rollups", Box::new(e)))
+}
+
+/// 毫秒时间戳格式化为本地时间，0表示无文件
+fn format_time(millis: i64) -> String {
+    if millis == 0 {
+        return "-".to_string();
+    }
+    Local.timestamp_millis_opt(millis).single().map_or_else(
+        || "-".to_string(),
+        |t| t.format("%Y-%m-%d %H:%M").to_string(),
+    )
+}
+
+/// 将目录汇总渲染为按层级缩进的目录树，子目录紧跟在上级目录之后
+pub fn format_tree(rollups: &[DirRollup]) -> String {
+    let mut rows: Vec<&DirRollup> = rollups.iter().collect();
+    rows.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+
+    let mut out = format!(
+        "{:>12} {:>12} {:>10} {:>8} {:>16} {:>16}  {}\n",
+        "TOTAL", "OWN", "FILES", "DIRS", "NEWEST MTIME", "OLDEST ATIME", "PATH"
+    );
+    for rollup in rows {
+        let name = if rollup.path.is_empty() {
+            "."
+        } else {
+            rollup.path.rsplit('/').next().unwrap_or_default()
+        };
+        let _ = writeln!(
+            out,
+            "{:>12} {:>12} {:>10} {:>8} {:>16} {:>16}  {}{}",
+            format_bytes(rollup.total_bytes as f64),
+            format_bytes(rollup.own_bytes as f64),
+            rollup.total_files,
+            rollup.total_dirs,
+            format_time(rollup.newest_mtime),
+            format_time(rollup.oldest_atime),
+            "  ".repeat(rollup.depth as usize),
+            name
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rollup(path: &str, depth: u32, total_bytes: u64) -> DirRollup {
+        DirRollup {
+            path: path.to_string(),
+            depth,
+            total_bytes,
+            ..DirRollup::default()
+        }
+    }
+
+    #[test]
+    fn test_format_tree_nests_children_under_parents() {
+        // "a-b" 按字符串排序位于 "a" 和 "a/c" 之间，渲染时应排在 "a" 的子树之后
+        let rollups = [
+            rollup("a-b", 1, 0),
+            rollup("a/c", 2, 1024),
+            rollup("", 0, 2048),
+            rollup("a", 1, 1024),
+        ];
+        let tree = format_tree(&rollups);
+        let names: Vec<&str> = tree
+            .lines()
+            .skip(1)
+            .map(|line| line.rsplit("  ").next().unwrap())
+            .collect();
+        assert_eq!(names, [".", "a", "c", "a-b"]);
+        assert!(tree.lines().nth(3).unwrap().ends_with("      c"));
+        assert!(
+            tree.lines()
+                .nth(1)
+                .unwrap()
+                .trim_start()
+                .starts_with("2.00 KiB")
+        );
+    }
+}
@@ -272,8 +272,7 @@ pub fn evaluate_filter(
                         if value.starts_with('%') && value.ends_with('%') {
                             let pattern = &value[1..value.len() - 1];
                             file_name.contains(pattern)
-                        } else if value.starts_with('%') {
-                            let pattern = &value[1..];
+                        } else if let Some(pattern) = value.strip_prefix('%') {
                             file_name.ends_with(pattern)
                         } else if value.ends_with('%') {
                             let pattern = &value[..value.len() - 1];
@@ -310,8 +309,7 @@ pub fn evaluate_filter(
                         if value.starts_with('%') && value.ends_with('%') {
                             let pattern = &value[1..value.len() - 1];
                             file_path.contains(pattern)
-                        } else if value.starts_with('%') {
-                            let pattern = &value[1..];
+                        } else if let Some(pattern) = value.strip_prefix('%') {
                             file_path.ends_with(pattern)
                         } else if value.ends_with('%') {
                             let pattern = &value[..value.len() - 1];
@@ -379,8 +377,7 @@ pub fn evaluate_filter(
                         if value.starts_with('%') && value.ends_with('%') {
                             let pattern = &value[1..value.len() - 1];
                             extension.contains(pattern)
-                        } else if value.starts_with('%') {
-                            let pattern = &value[1..];
+                        } else if let Some(pattern) = value.strip_prefix('%') {
                             extension.ends_with(pattern)
                         } else if value.ends_with('%') {
                             let pattern = &value[..value.len() - 1];
@@ -413,11 +410,11 @@ pub fn evaluate_filter(
 
 /// Extract quoted string value from expression
 fn extract_quoted_value(expr: &str, prefix: &str) -> String {
-    if !prefix.is_empty() {
-        if let Some(start) = expr.find(prefix) {
-            let rest = &expr[start + prefix.len()..];
-            return extract_quoted_value(rest, "");
-        }
+    if !prefix.is_empty()
+        && let Some(start) = expr.find(prefix)
+    {
+        let rest = &expr[start + prefix.len()..];
+        return extract_quoted_value(rest, "");
     }
 
     let rest = expr.trim_start();
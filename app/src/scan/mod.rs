@@ -1,7 +1,9 @@
 mod filter;
+#[allow(clippy::module_inception)]
 pub mod scan;
 
 pub use filter::{FilterCondition, FilterExpression, evaluate_filter, parse_filter_expression};
 pub use scan::{
-    ScanConfig, ScanMessage, ScanParams, ScanType, StorageEntity, parse_expressions, scan, walkdir,
+    ScanConfig, ScanMessage, ScanParams, ScanType, StorageEntity, parse_expressions, scan,
+    to_epoch_millis, walkdir,
 };
@@ -1,7 +1,12 @@
+use db::traits::FileScanRecord;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
 use storage::Storage;
+use storage::common::StorageEntry;
+use storage::throttle::RateLimiter;
 use tokio::sync::mpsc;
 use tokio::time;
 use utils::app_config::AppConfig;
@@ -23,8 +28,10 @@ fn format_permissions(mode: u32) -> String {
 }
 use utils::error::Result;
 
-use crate::consumer::ConsumerManager;
+use crate::consumer::{ConsumerManager, ExportConsumer, OutputFormat};
+use crate::job::job_dir;
 use crate::scan::filter::{FilterExpression, evaluate_filter, parse_filter_expression};
+use crate::throttle::Throttle;
 
 /// 辅助函数：解析表达式列表
 pub fn parse_expressions(expressions: &[String]) -> Result<Vec<FilterExpression>> {
@@ -39,11 +46,13 @@ pub fn parse_expressions(expressions: &[String]) -> Result<Vec<FilterExpression>
 }
 
 /// 辅助函数：检查文件是否应该被跳过
+#[allow(clippy::too_many_arguments)]
 fn should_skip_file(
     expressions: &[FilterExpression], exclude_expressions: &[FilterExpression], file_name: &str,
     file_path: &str, file_type: &str, modified_days: f64, size: u64, extension: &str,
 ) -> bool {
     /// 内部辅助函数：检查表达式列表中是否有匹配的表达式
+    #[allow(clippy::too_many_arguments)]
     fn has_matching_expression(
         expressions: &[FilterExpression], file_name: &str, file_path: &str, file_type: &str,
         modified_days: f64, size: u64, extension: &str,
@@ -122,6 +131,18 @@ pub struct ScanParams {
 
     /// 扫描类型
     pub scan_type: ScanType,
+
+    /// 导出文件路径，设置后将扫描结果写入该文件
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// 导出格式，未指定时根据文件扩展名推断
+    #[serde(default)]
+    pub output_format: Option<OutputFormat>,
+
+    /// 导出列，为空时导出所有列
+    #[serde(default)]
+    pub columns: Vec<String>,
 }
 
 impl Default for ScanParams {
@@ -133,23 +154,21 @@ impl Default for ScanParams {
             match_expressions: Vec::new(),
             exclude_expressions: Vec::new(),
             scan_type: ScanType::default(),
+            output: None,
+            output_format: None,
+            columns: Vec::new(),
         }
     }
 }
 
 /// 扫描类型枚举
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum ScanType {
+    #[default]
     Full,
     Incremental,
 }
 
-impl Default for ScanType {
-    fn default() -> Self {
-        ScanType::Full
-    }
-}
-
 impl std::fmt::Display for ScanType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -172,6 +191,8 @@ pub struct ConsumerConfig {
     pub app_config: AppConfig,
     pub scan_config: ScanConfig,
     pub job_id: String,
+    /// 作业目录，用于存放审计日志等作业产物
+    pub job_dir: PathBuf,
 }
 
 /// 扫描结果结构体 - 单个文件/目录的信息
@@ -184,21 +205,109 @@ pub struct StorageEntity {
     pub is_dir: bool,
     pub is_symlink: bool,
     pub size: u64,
+    #[serde(serialize_with = "serialize_epoch_millis")]
     pub atime: SystemTime,
+    #[serde(serialize_with = "serialize_epoch_millis")]
     pub ctime: SystemTime,
+    #[serde(serialize_with = "serialize_epoch_millis")]
     pub mtime: SystemTime,
     pub mode: Option<u32>,
     pub permissions: Option<String>,
     pub hard_links: Option<u8>,
+    /// 同步启用校验时复制过程中计算的源文件哈希
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+/// 将SystemTime转换为Unix毫秒时间戳，早于纪元的时间为负数
+pub fn to_epoch_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as i64,
+        Err(e) => -(e.duration().as_millis() as i64),
+    }
+}
+
+/// 以Unix毫秒时间戳序列化SystemTime，便于下游系统直接解析
+fn serialize_epoch_millis<S>(
+    time: &SystemTime, serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_i64(to_epoch_millis(*time))
+}
+
+impl StorageEntity {
+    /// 转换为数据库及导出使用的扫描记录
+    pub fn to_file_scan_record(&self) -> FileScanRecord {
+        FileScanRecord {
+            path: self.file_path.clone(),
+            size: self.size,
+            ext: self.extension.clone(),
+            ctime: to_epoch_millis(self.ctime),
+            mtime: to_epoch_millis(self.mtime),
+            atime: to_epoch_millis(self.atime),
+            perm: self.permissions.clone(),
+            is_symlink: self.is_symlink,
+            is_dir: self.is_dir,
+            is_regular_file: !self.is_dir,
+            hard_links: self.hard_links.unwrap_or_default(),
+            current_state: 0,
+            checksum: self.checksum.clone(),
+        }
+    }
+}
+
+/// 测试用的实体构造函数，测试中按需用结构体更新语法覆盖字段
+#[cfg(test)]
+impl StorageEntity {
+    /// 位于 `/data/<relative_path>` 的普通文件，时间均为Unix纪元
+    pub(crate) fn test_file(relative_path: &str, size: u64) -> Self {
+        Self {
+            file_name: relative_path.rsplit('/').next().unwrap().to_string(),
+            file_path: format!("/data/{}", relative_path),
+            relative_path: relative_path.to_string(),
+            extension: None,
+            is_dir: false,
+            is_symlink: false,
+            size,
+            atime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            mode: None,
+            permissions: None,
+            hard_links: None,
+            checksum: None,
+        }
+    }
+
+    /// 按磁盘上的条目创建实体，与walkdir一样不跟随符号链接
+    pub(crate) fn test_from_path(path: &std::path::Path, relative_path: &str) -> Self {
+        let metadata = std::fs::symlink_metadata(path).unwrap();
+        Self {
+            file_name: path.file_name().unwrap().to_string_lossy().to_string(),
+            file_path: path.to_string_lossy().to_string(),
+            is_dir: metadata.is_dir(),
+            is_symlink: metadata.file_type().is_symlink(),
+            size: metadata.len(),
+            atime: metadata.accessed().unwrap(),
+            ctime: metadata.modified().unwrap(),
+            mtime: metadata.modified().unwrap(),
+            ..Self::test_file(relative_path, 0)
+        }
+    }
 }
 
 /// 扫描消息枚举 - 用于队列通信的消息类型
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum ScanMessage {
     Result(StorageEntity),
     Complete,
     /// 扫描配置信息
     Config(ConsumerConfig),
+    /// 扫描失败原因，随后仍会发送Complete
+    Failed(String),
 }
 
 /// 返回 time 相对于 now 的天数差，正数表示 time 晚于 now，负数表示 time 早于 now
@@ -222,6 +331,38 @@ fn days_between(now: SystemTime, time: SystemTime) -> f64 {
     if now <= time { days } else { -days }
 }
 
+/// 获取小写的文件扩展名，没有扩展名时返回空字符串
+fn file_extension(file_path: &str) -> String {
+    std::path::Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// 判断条目是否被匹配/排除表达式过滤掉
+/// 同步删除多余文件时也用于判断目标条目是否属于被排除的文件
+pub(crate) fn is_filtered_out(config: &ScanConfig, entry: &StorageEntry) -> bool {
+    // 计算修改时间（天数）
+    let modified_days = {
+        let now = SystemTime::now();
+        let diff_ms = days_between(now, entry.modified);
+        diff_ms / 86400000.0
+    };
+    let file_type = if entry.is_dir { "dir" } else { "file" };
+
+    should_skip_file(
+        &config.expressions,
+        &config.exclude_expressions,
+        &entry.name,
+        &entry.path,
+        file_type,
+        modified_days,
+        entry.size,
+        &file_extension(&entry.path),
+    )
+}
+
 /// 主扫描函数 - 入口点
 pub async fn scan(params: ScanParams) -> Result<()> {
     log::info!("Starting scan with params: {:?}", params);
@@ -236,15 +377,27 @@ pub async fn scan(params: ScanParams) -> Result<()> {
         utils::error::Error::with_source("Failed to load application configuration", Box::new(e))
     })?;
 
+    let throttle = Throttle::from_config(&app_config.throttle)?;
+
+    let job_id = params.id.clone().unwrap_or_else(|| "unknown".to_string());
     let consumer_config = ConsumerConfig {
         app_config: app_config.clone(),
         scan_config: scan_config.clone(),
-        job_id: params.id.clone().unwrap_or_else(|| "unknown".to_string()),
+        job_dir: job_dir("scan", &job_id),
+        job_id,
     };
 
     // 创建消费者管理器（使用默认配置）
-    let mut consumer_manager =
-        ConsumerManager::new(app_config.database.enabled, app_config.kafka.enabled);
+    let mut consumer_manager = ConsumerManager::new(
+        app_config.database.enabled,
+        app_config.kafka.enabled,
+        app_config.webhook.enabled,
+    );
+
+    // 指定了导出文件时添加导出消费者
+    if params.output.is_some() {
+        consumer_manager.add_consumer(Box::new(ExportConsumer));
+    }
 
     // 启动所有消费者
     let consumer_handles = consumer_manager.start_consumers().await?;
@@ -264,7 +417,8 @@ pub async fn scan(params: ScanParams) -> Result<()> {
     time::sleep(Duration::from_secs(2)).await;
 
     // 启动walkdir任务（仅生成ScanResults）
-    let walkdir_handle = tokio::spawn(async move { walkdir(scan_config, tx).await });
+    let mut walkdir_handle =
+        tokio::spawn(async move { walkdir(scan_config, tx, throttle.ops).await });
 
     loop {
         match rx.recv().await {
@@ -280,12 +434,16 @@ pub async fn scan(params: ScanParams) -> Result<()> {
 
                 break;
             }
-            Some(ScanMessage::Config(_)) => {
+            Some(ScanMessage::Config(_)) | Some(ScanMessage::Failed(_)) => {
                 // 忽略配置消息，已在前面的步骤处理
             }
             None => {
                 log::warn!("Channel closed unexpectedly");
-                // 广播完成消息给所有消费者
+                // 通道提前关闭说明遍历失败，先通知失败原因再广播完成消息
+                if let Ok(Err(e)) = (&mut walkdir_handle).await {
+                    log::error!("Scan failed: {}", e);
+                    let _ = broadcaster.send(ScanMessage::Failed(e.to_string()));
+                }
                 let _ = broadcaster.send(ScanMessage::Complete);
                 break;
             }
@@ -293,9 +451,11 @@ pub async fn scan(params: ScanParams) -> Result<()> {
     }
 
     // 等待walkdir任务完成
-    let _ = walkdir_handle
-        .await
-        .map_err(|e| utils::error::Error::with_source("Walkdir task failed", Box::new(e)))?;
+    if !walkdir_handle.is_finished() {
+        let _ = walkdir_handle
+            .await
+            .map_err(|e| utils::error::Error::with_source("Walkdir task failed", Box::new(e)))?;
+    }
 
     // 等待所有消费者完成
     for handle in consumer_handles {
@@ -309,7 +469,10 @@ pub async fn scan(params: ScanParams) -> Result<()> {
 }
 
 /// 目录遍历函数 - 遍历目录并发送结果到队列（简化版本，直接处理）
-pub async fn walkdir(config: ScanConfig, tx: mpsc::Sender<ScanMessage>) -> Result<()> {
+/// `ops_limiter` 限制遍历时的元数据操作速率
+pub async fn walkdir(
+    config: ScanConfig, tx: mpsc::Sender<ScanMessage>, ops_limiter: Option<Arc<RateLimiter>>,
+) -> Result<()> {
     let scan_path = &config.params.path;
     let depth = if config.params.depth > 0 {
         Some(config.params.depth as usize)
@@ -318,18 +481,25 @@ pub async fn walkdir(config: ScanConfig, tx: mpsc::Sender<ScanMessage>) -> Resul
     };
 
     // 使用storage库的create_storage接口根据路径创建对应的存储类型
-    let storage_type = storage::create_storage(scan_path).map_err(|e| {
-        utils::error::Error::with_source(
-            "Failed to create storage",
-            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidInput, e)),
-        )
-    })?;
+    let storage_type = storage::create_storage(scan_path)
+        .map_err(|e| {
+            utils::error::Error::with_source(
+                "Failed to create storage",
+                Box::new(std::io::Error::new(std::io::ErrorKind::InvalidInput, e)),
+            )
+        })?
+        .with_ops_limiter(ops_limiter);
 
     // 使用Storage trait的统一接口获取遍历器
     let mut rx = storage_type.walkdir(None, depth).await;
 
     // 直接处理每个StorageEntry
     while let Some(entry) = rx.recv().await {
+        // 使用辅助函数检查是否应该跳过该文件
+        if is_filtered_out(&config, &entry) {
+            continue;
+        }
+
         let file_name = entry.name;
         let file_path = entry.path;
 
@@ -346,35 +516,8 @@ pub async fn walkdir(config: ScanConfig, tx: mpsc::Sender<ScanMessage>) -> Resul
         // 格式化Unix权限
         let permissions_str = entry.mode.map(format_permissions);
 
-        // 计算修改时间（天数）
-        let modified_days = {
-            let now = SystemTime::now();
-            let diff_ms = days_between(now, mtime);
-            diff_ms as f64 / 86400000.0
-        };
-
         // 获取文件扩展名
-        let extension = std::path::Path::new(&file_path)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
-        let file_type = if is_dir { "dir" } else { "file" };
-
-        // 使用辅助函数检查是否应该跳过该文件
-        if should_skip_file(
-            &config.expressions,
-            &config.exclude_expressions,
-            &file_name,
-            &file_path,
-            file_type,
-            modified_days,
-            size,
-            &extension,
-        ) {
-            continue;
-        }
+        let extension = file_extension(&file_path);
 
         // 创建扫描结果
         let scan_result = StorageEntity {
@@ -385,7 +528,7 @@ pub async fn walkdir(config: ScanConfig, tx: mpsc::Sender<ScanMessage>) -> Resul
             extension: if extension.is_empty() {
                 None
             } else {
-                Some(String::from(extension))
+                Some(extension)
             },
             is_symlink,
             size,
@@ -395,6 +538,7 @@ pub async fn walkdir(config: ScanConfig, tx: mpsc::Sender<ScanMessage>) -> Resul
             mode: entry.mode,
             permissions: permissions_str,
             hard_links: entry.hard_links,
+            checksum: None,
         };
 
         // 直接发送结果到队列
@@ -0,0 +1,136 @@
+use crate::sync::section::checkpoint_path;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use storage::{Storage, StorageType};
+
+/// 同步写入的临时文件名后缀，临时文件名为 `.目标文件名` 加该后缀
+pub const TEMP_SUFFIX: &str = ".terrasync-tmp";
+
+/// 目标文件在同一目录中的隐藏临时文件，写入完成后重命名到目标路径
+pub fn temp_path(dest: &Path) -> PathBuf {
+    let file_name = dest
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    dest.with_file_name(format!(".{}{}", file_name, TEMP_SUFFIX))
+}
+
+/// 临时文件对应的目标文件名，不是同步写入的临时文件时返回None
+pub fn target_name(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_prefix('.')?
+        .strip_suffix(TEMP_SUFFIX)
+        .filter(|name| !name.is_empty())
+}
+
+/// 将临时文件的数据写入磁盘
+pub async fn sync_temp(temp: &Path) -> std::io::Result<()> {
+    tokio::fs::File::open(temp).await?.sync_all().await
+}
+
+/// 将临时文件重命名到目标路径，替换已存在的文件
+/// `fsync` 时同时将所在目录落盘，保证重命名在断电后仍然有效
+pub async fn commit(temp: &Path, dest: &Path, fsync: bool) -> std::io::Result<()> {
+    tokio::fs::rename(temp, dest).await?;
+    if fsync {
+        sync_parent(dest).await?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn sync_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) => tokio::fs::File::open(parent).await?.sync_all().await,
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn sync_parent(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// 删除写入失败的临时文件，文件不存在时忽略
+pub async fn discard(temp: &Path) {
+    match tokio::fs::remove_file(temp).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            log::warn!("Failed to remove temporary file {}: {}", temp.display(), e);
+        }
+        _ => {}
+    }
+}
+
+/// 删除目标中上次运行中断留下的临时文件，返回删除的数量
+/// 作业目录中仍有分段断点的临时文件保留，重新运行时继续传输
+pub async fn remove_orphans(dest: &StorageType, checkpoint_dir: &Path) -> u64 {
+    let mut rx = dest.walkdir(None, None).await;
+    let mut orphans = Vec::new();
+    while let Some(entry) = rx.recv().await {
+        if entry.is_dir {
+            continue;
+        }
+        let Some(name) = target_name(&entry.name) else {
+            continue;
+        };
+        let relative_path = match Path::new(&entry.relative_path).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                format!("{}/{}", parent.to_string_lossy(), name)
+            }
+            _ => name.to_string(),
+        };
+        if checkpoint_path(checkpoint_dir, &relative_path).exists() {
+            log::debug!("Keeping resumable temporary file: {}", entry.path);
+            continue;
+        }
+        orphans.push(entry.path);
+    }
+
+    let mut removed = 0;
+    for path in orphans {
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {
+                log::info!("Removed orphaned temporary file: {}", path);
+                removed += 1;
+            }
+            Err(e) => log::warn!("Failed to remove orphaned temporary file {}: {}", path, e),
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_names() {
+        let temp = temp_path(Path::new("/data/dir/report.txt"));
+        assert_eq!(temp, Path::new("/data/dir/.report.txt.terrasync-tmp"));
+
+        let name = temp.file_name().unwrap().to_string_lossy().to_string();
+        assert_eq!(target_name(&name), Some("report.txt"));
+        assert_eq!(target_name(".report.txt"), None);
+        assert_eq!(target_name(".terrasync-tmp"), None);
+    }
+
+    #[tokio::test]
+    async fn test_remove_orphans() {
+        let dest = tempfile::tempdir().unwrap();
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dest.path().join("sub")).unwrap();
+        std::fs::write(dest.path().join("keep.txt"), "data").unwrap();
+        std::fs::write(temp_path(&dest.path().join("keep.txt")), "partial").unwrap();
+        std::fs::write(temp_path(&dest.path().join("sub/a.txt")), "partial").unwrap();
+        // 有断点的临时文件由分段传输继续使用
+        std::fs::write(temp_path(&dest.path().join("sub/big.bin")), "partial").unwrap();
+        std::fs::write(checkpoint_path(checkpoint_dir.path(), "sub/big.bin"), "{}").unwrap();
+
+        let storage = storage::create_storage(&dest.path().to_string_lossy()).unwrap();
+        assert_eq!(remove_orphans(&storage, checkpoint_dir.path()).await, 2);
+        assert!(dest.path().join("keep.txt").exists());
+        assert!(!temp_path(&dest.path().join("keep.txt")).exists());
+        assert!(!temp_path(&dest.path().join("sub/a.txt")).exists());
+        assert!(temp_path(&dest.path().join("sub/big.bin")).exists());
+    }
+}
@@ -0,0 +1,198 @@
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use storage::throttle::RateLimiter;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use utils::error::{Error, Result};
+use xxhash_rust::xxh3::Xxh3;
+
+/// 计算哈希时每次读取的字节数
+const CHECKSUM_BUFFER_SIZE: usize = 256 * 1024;
+
+/// 文件校验使用的哈希算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    #[default]
+    Md5,
+    /// 64位XXH3，非加密哈希，速度最快
+    Xxh3,
+    Blake3,
+}
+
+impl FromStr for ChecksumAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "md5" => Ok(ChecksumAlgorithm::Md5),
+            "xxh3" => Ok(ChecksumAlgorithm::Xxh3),
+            "blake3" => Ok(ChecksumAlgorithm::Blake3),
+            _ => Err(Error::new(&format!(
+                "Unsupported checksum algorithm: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumAlgorithm::Md5 => write!(f, "md5"),
+            ChecksumAlgorithm::Xxh3 => write!(f, "xxh3"),
+            ChecksumAlgorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+/// 增量计算哈希，结果格式为 `算法:十六进制摘要`
+pub enum Hasher {
+    Md5(Md5),
+    Xxh3(Box<Xxh3>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Md5 => Hasher::Md5(Md5::new()),
+            ChecksumAlgorithm::Xxh3 => Hasher::Xxh3(Box::new(Xxh3::new())),
+            ChecksumAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(hasher) => hasher.update(data),
+            Hasher::Xxh3(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> String {
+        match self {
+            Hasher::Md5(hasher) => format!("md5:{}", hex::encode(hasher.finalize())),
+            Hasher::Xxh3(hasher) => format!("xxh3:{:016x}", hasher.digest()),
+            Hasher::Blake3(hasher) => format!("blake3:{}", hasher.finalize().to_hex()),
+        }
+    }
+}
+
+/// 读取整个文件计算哈希
+pub async fn checksum_file(path: &Path, algorithm: ChecksumAlgorithm) -> std::io::Result<String> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Hasher::new(algorithm);
+    let mut buf = vec![0u8; CHECKSUM_BUFFER_SIZE];
+
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buf[..read]);
+    }
+}
+
+/// 流式复制文件，与 `tokio::fs::copy` 一样保留权限位
+/// 指定算法时在复制过程中计算源文件哈希，指定限速器时按字节限速读取
+pub async fn copy_stream(
+    src: &Path, dest: &Path, algorithm: Option<ChecksumAlgorithm>, limiter: Option<&RateLimiter>,
+) -> std::io::Result<Option<String>> {
+    let mut reader = File::open(src).await?;
+    let mut writer = File::create(dest).await?;
+    let mut hasher = algorithm.map(Hasher::new);
+    let mut buf = vec![0u8; CHECKSUM_BUFFER_SIZE];
+
+    loop {
+        let read = reader.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        if let Some(limiter) = limiter {
+            limiter.acquire(read as u64).await;
+        }
+        if let Some(hasher) = &mut hasher {
+            hasher.update(&buf[..read]);
+        }
+        writer.write_all(&buf[..read]).await?;
+    }
+    writer.flush().await?;
+
+    let permissions = reader.metadata().await?.permissions();
+    writer.set_permissions(permissions).await?;
+    Ok(hasher.map(Hasher::finalize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_checksum_algorithms() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty");
+        std::fs::write(&empty, "").unwrap();
+
+        assert_eq!(
+            checksum_file(&empty, ChecksumAlgorithm::Md5).await.unwrap(),
+            "md5:d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert_eq!(
+            checksum_file(&empty, ChecksumAlgorithm::Xxh3)
+                .await
+                .unwrap(),
+            "xxh3:2d06800538d394c2"
+        );
+        assert_eq!(
+            checksum_file(&empty, ChecksumAlgorithm::Blake3)
+                .await
+                .unwrap(),
+            "blake3:af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+
+        assert_eq!(
+            "BLAKE3".parse::<ChecksumAlgorithm>().unwrap(),
+            ChecksumAlgorithm::Blake3
+        );
+        assert!("sha1".parse::<ChecksumAlgorithm>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_copy_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dest = dir.path().join("dest.bin");
+        let content: Vec<u8> = (0..CHECKSUM_BUFFER_SIZE * 2 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        std::fs::write(&src, &content).unwrap();
+
+        for algorithm in [
+            ChecksumAlgorithm::Md5,
+            ChecksumAlgorithm::Xxh3,
+            ChecksumAlgorithm::Blake3,
+        ] {
+            let copied = copy_stream(&src, &dest, Some(algorithm), None)
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(copied.starts_with(&format!("{}:", algorithm)));
+            assert_eq!(std::fs::read(&dest).unwrap(), content);
+            assert_eq!(checksum_file(&dest, algorithm).await.unwrap(), copied);
+        }
+
+        std::fs::remove_file(&dest).unwrap();
+        let limiter = RateLimiter::new(u64::MAX / 4, Vec::new());
+        let copied = copy_stream(&src, &dest, None, Some(&limiter))
+            .await
+            .unwrap();
+        assert_eq!(copied, None);
+        assert_eq!(std::fs::read(&dest).unwrap(), content);
+    }
+}
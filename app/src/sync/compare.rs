@@ -0,0 +1,399 @@
+use crate::scan::StorageEntity;
+use crate::sync::conflict::ConflictPolicy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, BufReader};
+
+/// 内容比较时每次读取的字节数
+const COMPARE_BUFFER_SIZE: usize = 256 * 1024;
+
+/// 单个文件的同步决策
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    /// 目标不存在，需要复制
+    Create,
+    /// 目标存在但与源不一致，或配置了强制覆盖
+    Update,
+    /// 目标与源一致，跳过
+    Skip,
+    /// 内容一致，仅更新权限、修改时间等元数据
+    Metadata,
+    /// 目标与源不一致，按冲突策略保留目标
+    Keep,
+    /// 目标与源不一致，重命名已存在的目标后复制
+    Rename,
+    /// 目标与源不一致，按冲突策略视为失败
+    Reject,
+}
+
+impl SyncAction {
+    /// 是否为目标已存在且需要按冲突策略处理的决策
+    pub fn is_conflict(self) -> bool {
+        matches!(
+            self,
+            SyncAction::Update | SyncAction::Keep | SyncAction::Rename | SyncAction::Reject
+        )
+    }
+}
+
+/// 比较选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompareOptions {
+    /// 目标已存在且与源不一致时的处理策略
+    pub policy: ConflictPolicy,
+    /// 大小一致时比较文件内容，而不是修改时间
+    pub checksum: bool,
+}
+
+/// 同步结果计数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncSummary {
+    pub copied: u64,
+    pub updated: u64,
+    pub skipped: u64,
+    pub failed: u64,
+    /// 仅更新元数据的条目数
+    pub metadata: u64,
+    /// 镜像同步时删除的目标条目数
+    pub deleted: u64,
+    /// 重命名已存在的目标后复制的文件数
+    pub renamed: u64,
+    /// 按冲突策略处理的已存在目标数
+    pub conflicts: BTreeMap<ConflictPolicy, u64>,
+    /// 复制和覆盖写入的字节数
+    pub bytes: u64,
+}
+
+impl SyncSummary {
+    /// 记录一次成功的同步操作
+    pub fn record(&mut self, action: SyncAction, size: u64) {
+        match action {
+            SyncAction::Create => {
+                self.copied += 1;
+                self.bytes += size;
+            }
+            SyncAction::Update => {
+                self.updated += 1;
+                self.bytes += size;
+            }
+            SyncAction::Rename => {
+                self.renamed += 1;
+                self.bytes += size;
+            }
+            SyncAction::Skip | SyncAction::Keep => self.skipped += 1,
+            SyncAction::Metadata => self.metadata += 1,
+            SyncAction::Reject => self.failed += 1,
+        }
+    }
+
+    /// 记录一次按冲突策略处理的已存在目标
+    pub fn record_conflict(&mut self, policy: ConflictPolicy) {
+        *self.conflicts.entry(policy).or_default() += 1;
+    }
+}
+
+impl fmt::Display for SyncSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} copied, {} updated, {} skipped, {} failed",
+            self.copied, self.updated, self.skipped, self.failed
+        )?;
+        if self.metadata > 0 {
+            write!(f, ", {} metadata updated", self.metadata)?;
+        }
+        if self.renamed > 0 {
+            write!(f, ", {} renamed", self.renamed)?;
+        }
+        if self.deleted > 0 {
+            write!(f, ", {} deleted", self.deleted)?;
+        }
+        for (policy, count) in &self.conflicts {
+            write!(f, ", {} conflicts ({})", count, policy)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按秒比较修改时间，兼容时间精度较低的目标文件系统
+fn same_mtime(a: SystemTime, b: SystemTime) -> bool {
+    let secs = |t: SystemTime| t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).ok();
+    secs(a) == secs(b)
+}
+
+/// 逐块比较两个文件的内容
+async fn same_content(src: &Path, dest: &Path) -> std::io::Result<bool> {
+    let mut src = BufReader::new(File::open(src).await?);
+    let mut dest = BufReader::new(File::open(dest).await?);
+    let mut src_buf = vec![0u8; COMPARE_BUFFER_SIZE];
+    let mut dest_buf = vec![0u8; COMPARE_BUFFER_SIZE];
+
+    loop {
+        let read = src.read(&mut src_buf).await?;
+        if read == 0 {
+            return Ok(dest.read(&mut dest_buf[..1]).await? == 0);
+        }
+        if dest.read_exact(&mut dest_buf[..read]).await.is_err() {
+            return Ok(false);
+        }
+        if src_buf[..read] != dest_buf[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// 源的修改时间（秒）是否晚于目标
+pub fn source_newer(source: SystemTime, dest: SystemTime) -> bool {
+    let secs = |t: SystemTime| {
+        t.duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    };
+    secs(source) > secs(dest)
+}
+
+/// 未保留符号链接时复制的是链接指向的文件，用其大小和时间替换链接自身的值
+/// 比较、复制和恢复修改时间针对同一个对象，否则链接每次同步都会被重新复制
+pub async fn follow_symlink(entity: &mut StorageEntity) -> std::io::Result<()> {
+    let metadata = tokio::fs::metadata(&entity.file_path).await?;
+    entity.size = metadata.len();
+    entity.mtime = metadata.modified()?;
+    entity.atime = metadata.accessed()?;
+    Ok(())
+}
+
+/// 根据目标文件的状态决定复制、跳过，或按冲突策略处理已存在的目标
+/// 大小和修改时间（秒）都一致时视为未变化；启用checksum时以内容是否一致为准
+pub async fn compare(
+    entity: &StorageEntity, dest: &Path, options: &CompareOptions,
+) -> std::io::Result<SyncAction> {
+    let metadata = match tokio::fs::metadata(dest).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(SyncAction::Create),
+        Err(e) => return Err(e),
+    };
+
+    if options.policy == ConflictPolicy::Overwrite {
+        return Ok(SyncAction::Update);
+    }
+
+    let unchanged = if !metadata.is_file() || metadata.len() != entity.size {
+        false
+    } else if options.checksum {
+        same_content(Path::new(&entity.file_path), dest).await?
+    } else {
+        same_mtime(metadata.modified()?, entity.mtime)
+    };
+    Ok(if unchanged {
+        SyncAction::Skip
+    } else {
+        options
+            .policy
+            .resolve(source_newer(entity.mtime, metadata.modified()?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn set_mtime(path: &Path, time: SystemTime) {
+        filetime::set_file_mtime(path, filetime::FileTime::from_system_time(time)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compare_size_and_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        std::fs::write(&src, "hello").unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        set_mtime(&src, mtime);
+        let entity = StorageEntity::test_from_path(&src, "src");
+        let options = CompareOptions::default();
+
+        assert_eq!(
+            compare(&entity, &dest, &options).await.unwrap(),
+            SyncAction::Create
+        );
+
+        std::fs::write(&dest, "hello").unwrap();
+        set_mtime(&dest, mtime);
+        assert_eq!(
+            compare(&entity, &dest, &options).await.unwrap(),
+            SyncAction::Skip
+        );
+
+        // 亚秒级差异视为相同
+        set_mtime(&dest, mtime + Duration::from_millis(300));
+        assert_eq!(
+            compare(&entity, &dest, &options).await.unwrap(),
+            SyncAction::Skip
+        );
+
+        set_mtime(&dest, mtime + Duration::from_secs(5));
+        assert_eq!(
+            compare(&entity, &dest, &options).await.unwrap(),
+            SyncAction::Update
+        );
+
+        std::fs::write(&dest, "hello world").unwrap();
+        set_mtime(&dest, mtime);
+        assert_eq!(
+            compare(&entity, &dest, &options).await.unwrap(),
+            SyncAction::Update
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_overwrite_and_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dest = dir.path().join("dest.bin");
+        let content: Vec<u8> = (0..COMPARE_BUFFER_SIZE * 2 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        std::fs::write(&src, &content).unwrap();
+        std::fs::write(&dest, &content).unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        set_mtime(&src, mtime);
+        set_mtime(&dest, mtime);
+        let entity = StorageEntity::test_from_path(&src, "src");
+
+        let overwrite = CompareOptions {
+            policy: ConflictPolicy::Overwrite,
+            checksum: false,
+        };
+        assert_eq!(
+            compare(&entity, &dest, &overwrite).await.unwrap(),
+            SyncAction::Update
+        );
+
+        let checksum = CompareOptions {
+            policy: ConflictPolicy::UpdateIfDifferent,
+            checksum: true,
+        };
+        // 内容一致时即使修改时间不同也跳过
+        set_mtime(&dest, mtime + Duration::from_secs(60));
+        assert_eq!(
+            compare(&entity, &dest, &checksum).await.unwrap(),
+            SyncAction::Skip
+        );
+
+        // 大小和修改时间一致但内容不同
+        let mut changed = content.clone();
+        changed[COMPARE_BUFFER_SIZE + 1] ^= 0xff;
+        std::fs::write(&dest, &changed).unwrap();
+        set_mtime(&dest, mtime);
+        assert_eq!(
+            compare(&entity, &dest, &CompareOptions::default())
+                .await
+                .unwrap(),
+            SyncAction::Skip
+        );
+        assert_eq!(
+            compare(&entity, &dest, &checksum).await.unwrap(),
+            SyncAction::Update
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compare_conflict_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dest = dir.path().join("dest.txt");
+        std::fs::write(&src, "new content").unwrap();
+        std::fs::write(&dest, "old").unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        set_mtime(&src, mtime);
+        set_mtime(&dest, mtime - Duration::from_secs(60));
+        let entity = StorageEntity::test_from_path(&src, "src");
+        let options = |policy| CompareOptions {
+            policy,
+            checksum: false,
+        };
+
+        assert_eq!(
+            compare(&entity, &dest, &options(ConflictPolicy::Skip))
+                .await
+                .unwrap(),
+            SyncAction::Keep
+        );
+        assert_eq!(
+            compare(&entity, &dest, &options(ConflictPolicy::UpdateIfNewer))
+                .await
+                .unwrap(),
+            SyncAction::Update
+        );
+        assert_eq!(
+            compare(&entity, &dest, &options(ConflictPolicy::RenameExisting))
+                .await
+                .unwrap(),
+            SyncAction::Rename
+        );
+        assert_eq!(
+            compare(&entity, &dest, &options(ConflictPolicy::Fail))
+                .await
+                .unwrap(),
+            SyncAction::Reject
+        );
+
+        // 目标更新时保留目标
+        set_mtime(&dest, mtime + Duration::from_secs(60));
+        assert_eq!(
+            compare(&entity, &dest, &options(ConflictPolicy::UpdateIfNewer))
+                .await
+                .unwrap(),
+            SyncAction::Keep
+        );
+
+        // 一致的文件不视为冲突
+        std::fs::write(&dest, "new content").unwrap();
+        set_mtime(&dest, mtime);
+        assert_eq!(
+            compare(&entity, &dest, &options(ConflictPolicy::Fail))
+                .await
+                .unwrap(),
+            SyncAction::Skip
+        );
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let mut summary = SyncSummary::default();
+        summary.record(SyncAction::Create, 10);
+        summary.record(SyncAction::Update, 5);
+        summary.record(SyncAction::Skip, 100);
+        summary.failed += 1;
+
+        assert_eq!(summary.bytes, 15);
+        assert_eq!(
+            summary.to_string(),
+            "1 copied, 1 updated, 1 skipped, 1 failed"
+        );
+
+        summary.record(SyncAction::Metadata, 7);
+        summary.deleted = 2;
+        assert_eq!(summary.bytes, 15);
+        assert_eq!(
+            summary.to_string(),
+            "1 copied, 1 updated, 1 skipped, 1 failed, 1 metadata updated, 2 deleted"
+        );
+
+        summary.record(SyncAction::Rename, 3);
+        summary.record(SyncAction::Reject, 3);
+        summary.record_conflict(ConflictPolicy::RenameExisting);
+        summary.record_conflict(ConflictPolicy::RenameExisting);
+        assert_eq!(summary.bytes, 18);
+        assert_eq!(
+            summary.to_string(),
+            "1 copied, 1 updated, 1 skipped, 2 failed, 1 metadata updated, 1 renamed, 2 deleted, 2 conflicts (rename-existing)"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::sync::compare::SyncAction;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use utils::error::{Error, Result};
+
+/// 目标文件已存在且与源不一致时的处理策略，按作业选择
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// 保留已存在的目标文件
+    Skip,
+    /// 总是覆盖，即使内容一致
+    Overwrite,
+    /// 源文件修改时间（秒）更新时覆盖
+    UpdateIfNewer,
+    /// 大小、修改时间或内容不一致时覆盖
+    #[default]
+    UpdateIfDifferent,
+    /// 将已存在的目标文件加时间戳后缀重命名后再复制
+    RenameExisting,
+    /// 该文件同步失败，保留目标文件
+    Fail,
+}
+
+impl ConflictPolicy {
+    /// 目标与源不一致时的同步决策，`source_newer` 表示源文件的修改时间更新
+    pub fn resolve(self, source_newer: bool) -> SyncAction {
+        match self {
+            ConflictPolicy::Skip => SyncAction::Keep,
+            ConflictPolicy::Overwrite | ConflictPolicy::UpdateIfDifferent => SyncAction::Update,
+            ConflictPolicy::UpdateIfNewer if source_newer => SyncAction::Update,
+            ConflictPolicy::UpdateIfNewer => SyncAction::Keep,
+            ConflictPolicy::RenameExisting => SyncAction::Rename,
+            ConflictPolicy::Fail => SyncAction::Reject,
+        }
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "update-if-newer" => Ok(ConflictPolicy::UpdateIfNewer),
+            "update-if-different" => Ok(ConflictPolicy::UpdateIfDifferent),
+            "rename-existing" => Ok(ConflictPolicy::RenameExisting),
+            "fail" => Ok(ConflictPolicy::Fail),
+            _ => Err(Error::new(&format!("Unsupported conflict policy: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictPolicy::Skip => write!(f, "skip"),
+            ConflictPolicy::Overwrite => write!(f, "overwrite"),
+            ConflictPolicy::UpdateIfNewer => write!(f, "update-if-newer"),
+            ConflictPolicy::UpdateIfDifferent => write!(f, "update-if-different"),
+            ConflictPolicy::RenameExisting => write!(f, "rename-existing"),
+            ConflictPolicy::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// 重命名备份后缀中的时间戳格式
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// 将已存在的目标重命名为 `名称.时间戳`，同名时追加序号，返回新的路径
+pub async fn rename_existing(dest: &Path) -> std::io::Result<PathBuf> {
+    let file_name = dest
+        .file_name()
+        .ok_or_else(|| std::io::Error::from(ErrorKind::InvalidInput))?
+        .to_string_lossy()
+        .to_string();
+    let timestamp = chrono::Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+
+    let mut renamed = dest.with_file_name(format!("{}.{}", file_name, timestamp));
+    let mut sequence = 1;
+    while tokio::fs::symlink_metadata(&renamed).await.is_ok() {
+        renamed = dest.with_file_name(format!("{}.{}-{}", file_name, timestamp, sequence));
+        sequence += 1;
+    }
+
+    tokio::fs::rename(dest, &renamed).await?;
+    Ok(renamed)
+}
+
+/// 重命名备份对应的原文件名，不是 `名称.时间戳[-序号]` 形式时返回None
+pub fn backup_target_name(file_name: &str) -> Option<&str> {
+    let (name, suffix) = file_name.rsplit_once('.')?;
+    let (timestamp, sequence) = suffix.split_once('-').unwrap_or((suffix, "1"));
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let valid = !name.is_empty()
+        && digits(sequence)
+        && chrono::NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).is_ok();
+    valid.then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_policies() {
+        let policies = [
+            ("skip", SyncAction::Keep, SyncAction::Keep),
+            ("overwrite", SyncAction::Update, SyncAction::Update),
+            ("update-if-newer", SyncAction::Update, SyncAction::Keep),
+            (
+                "update-if-different",
+                SyncAction::Update,
+                SyncAction::Update,
+            ),
+            ("rename-existing", SyncAction::Rename, SyncAction::Rename),
+            ("fail", SyncAction::Reject, SyncAction::Reject),
+        ];
+        for (name, newer, older) in policies {
+            let policy: ConflictPolicy = name.parse().unwrap();
+            assert_eq!(policy.to_string(), name);
+            assert_eq!(policy.resolve(true), newer);
+            assert_eq!(policy.resolve(false), older);
+        }
+        assert!("newest".parse::<ConflictPolicy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rename_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("report.txt");
+        std::fs::write(&dest, "first").unwrap();
+        let first = rename_existing(&dest).await.unwrap();
+
+        // 同一秒内再次重命名时追加序号
+        std::fs::write(&dest, "second").unwrap();
+        let second = rename_existing(&dest).await.unwrap();
+
+        assert!(!dest.exists());
+        assert_ne!(first, second);
+        assert!(
+            first
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("report.txt.")
+        );
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "second");
+
+        for renamed in [&first, &second] {
+            let name = renamed.file_name().unwrap().to_string_lossy();
+            assert_eq!(backup_target_name(&name), Some("report.txt"));
+        }
+    }
+
+    #[test]
+    fn test_backup_target_name() {
+        assert_eq!(
+            backup_target_name("a.tar.gz.20260102030405"),
+            Some("a.tar.gz")
+        );
+        assert_eq!(backup_target_name("a.20260102030405-12"), Some("a"));
+        assert_eq!(backup_target_name("a.txt"), None);
+        assert_eq!(backup_target_name("a.2026010203"), None);
+        assert_eq!(backup_target_name("a.20261399999999"), None);
+        assert_eq!(backup_target_name("a.20260102030405-"), None);
+        assert_eq!(backup_target_name(".20260102030405"), None);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::scan::ScanConfig;
+use crate::scan::scan::is_filtered_out;
+use crate::sync::atomic::target_name;
+use crate::sync::conflict::backup_target_name;
+use crate::throttle::Throttle;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use storage::common::StorageEntry;
+use storage::{Storage, StorageType};
+
+/// 镜像同步时删除目标中多余条目的选项，对应rsync的 `--delete`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeleteOptions {
+    /// 复制完成后删除源中不存在的目标条目
+    pub enabled: bool,
+    /// 同时删除目标中被匹配/排除表达式过滤掉的条目，默认这些条目受保护
+    pub delete_excluded: bool,
+    /// 多余条目超过该数量时不删除任何条目
+    pub max_delete: Option<u64>,
+}
+
+/// 删除阶段的结果计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeleteSummary {
+    pub deleted: u64,
+    pub failed: u64,
+}
+
+/// 遍历目标存储，找出源中不存在的条目，按从深到浅排列以便先删除子项
+/// `source_paths` 为过滤后源条目的相对路径；被过滤掉的目标条目仅在 `delete_excluded` 时返回
+/// 同步临时文件始终保留；`keep_backups` 时保留源中仍存在的文件的重命名备份
+pub async fn extraneous_entries(
+    dest: &StorageType, source_paths: &HashSet<String>, config: &ScanConfig, delete_excluded: bool,
+    keep_backups: bool,
+) -> Vec<StorageEntry> {
+    let depth = (config.params.depth > 0).then_some(config.params.depth as usize);
+    let mut rx = dest.walkdir(None, depth).await;
+
+    let mut entries = Vec::new();
+    while let Some(entry) = rx.recv().await {
+        if entry.relative_path.is_empty() || source_paths.contains(&entry.relative_path) {
+            continue;
+        }
+        // 临时文件由下次运行清理，带断点的临时文件需要保留以便继续传输
+        if !entry.is_dir && target_name(&entry.name).is_some() {
+            continue;
+        }
+        // rename-existing保留的旧版本不在源中，删除会丢失本应保留的数据
+        if keep_backups && !entry.is_dir && is_backup_of_source(&entry, source_paths) {
+            log::debug!("Keeping renamed backup: {}", entry.relative_path);
+            continue;
+        }
+        if !delete_excluded && is_filtered_out(config, &entry) {
+            log::debug!("Keeping excluded entry: {}", entry.relative_path);
+            continue;
+        }
+        entries.push(entry);
+    }
+
+    sort_deepest_first(&mut entries);
+    entries
+}
+
+/// 条目是否为源中仍存在的文件的重命名备份
+fn is_backup_of_source(entry: &StorageEntry, source_paths: &HashSet<String>) -> bool {
+    let Some(target) = backup_target_name(&entry.name) else {
+        return false;
+    };
+    match entry.relative_path.rsplit_once('/') {
+        Some((dir, _)) => source_paths.contains(&format!("{}/{}", dir, target)),
+        None => source_paths.contains(target),
+    }
+}
+
+/// 遍历目标存储，取出计划中要删除且仍存在的条目，按从深到浅排列
+pub async fn planned_entries(dest: &StorageType, paths: &HashSet<String>) -> Vec<StorageEntry> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rx = dest.walkdir(None, None).await;
+    let mut entries = Vec::new();
+    while let Some(entry) = rx.recv().await {
+        if paths.contains(&entry.relative_path) {
+            entries.push(entry);
+        }
+    }
+
+    sort_deepest_first(&mut entries);
+    entries
+}
+
+fn sort_deepest_first(entries: &mut [StorageEntry]) {
+    entries.sort_by_key(|entry| std::cmp::Reverse(Path::new(&entry.path).components().count()));
+}
+
+/// 逐个删除条目并输出每个被删除的路径
+/// 目录中仍有受保护的条目时保留该目录
+pub async fn delete_entries(entries: &[StorageEntry], throttle: &Throttle) -> DeleteSummary {
+    let mut summary = DeleteSummary::default();
+
+    for entry in entries {
+        throttle.acquire_ops(1).await;
+        let result = if entry.is_dir {
+            tokio::fs::remove_dir(&entry.path).await
+        } else {
+            tokio::fs::remove_file(&entry.path).await
+        };
+
+        match result {
+            Ok(()) => {
+                log::info!("Deleted {}", entry.path);
+                println!("Deleted {}", entry.relative_path);
+                summary.deleted += 1;
+            }
+            Err(e) if entry.is_dir && e.kind() == std::io::ErrorKind::DirectoryNotEmpty => {
+                log::info!("Kept non-empty directory {}", entry.path);
+            }
+            Err(e) => {
+                log::error!("Failed to delete {}: {}", entry.path, e);
+                eprintln!("Failed to delete {}: {}", entry.relative_path, e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::{ScanParams, parse_expressions};
+
+    fn scan_config(exclude: &[&str]) -> ScanConfig {
+        let exclude: Vec<String> = exclude.iter().map(|e| e.to_string()).collect();
+        ScanConfig {
+            params: ScanParams {
+                depth: 0,
+                ..ScanParams::default()
+            },
+            expressions: Vec::new(),
+            exclude_expressions: parse_expressions(&exclude).unwrap(),
+        }
+    }
+
+    fn sorted_paths(entries: Vec<StorageEntry>) -> Vec<String> {
+        let mut paths: Vec<String> = entries.into_iter().map(|e| e.relative_path).collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_delete_extraneous_entries() {
+        let dest = tempfile::tempdir().unwrap();
+        let root = dest.path();
+        std::fs::create_dir_all(root.join("keep")).unwrap();
+        std::fs::create_dir_all(root.join("stale/nested")).unwrap();
+        std::fs::write(root.join("keep/a.txt"), "a").unwrap();
+        std::fs::write(root.join("keep/old.txt"), "old").unwrap();
+        std::fs::write(root.join("stale/nested/b.txt"), "b").unwrap();
+        std::fs::write(root.join("stale/build.log"), "log").unwrap();
+
+        let source_paths: HashSet<String> = ["keep", "keep/a.txt"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let storage = storage::create_storage(&root.to_string_lossy()).unwrap();
+        let config = scan_config(&[r#"extension=="log""#]);
+
+        let entries = extraneous_entries(&storage, &source_paths, &config, false, false).await;
+        let mut paths: Vec<&str> = entries.iter().map(|e| e.relative_path.as_str()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "keep/old.txt",
+                "stale",
+                "stale/nested",
+                "stale/nested/b.txt"
+            ]
+        );
+        assert_eq!(entries.last().unwrap().relative_path, "stale");
+
+        let summary = delete_entries(&entries, &Throttle::default()).await;
+        // stale目录中仍有被排除的日志文件，目录保留
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 3,
+                failed: 0
+            }
+        );
+        assert!(root.join("keep/a.txt").exists());
+        assert!(!root.join("keep/old.txt").exists());
+        assert!(root.join("stale/build.log").exists());
+
+        let entries = extraneous_entries(&storage, &source_paths, &config, true, false).await;
+        assert_eq!(
+            delete_entries(&entries, &Throttle::default()).await.deleted,
+            2
+        );
+        assert!(!root.join("stale").exists());
+    }
+
+    /// 测试只有rename-existing策略下源中仍存在的文件的备份会被保留
+    #[tokio::test]
+    async fn test_delete_renamed_backups() {
+        let dest = tempfile::tempdir().unwrap();
+        let root = dest.path();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        for name in [
+            "docs/report.txt",
+            "docs/report.txt.20240101120000",
+            "docs/removed.txt.20240101120000",
+            "old.20240101120000",
+        ] {
+            std::fs::write(root.join(name), "data").unwrap();
+        }
+
+        let source_paths: HashSet<String> = ["docs", "docs/report.txt"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let storage = storage::create_storage(&root.to_string_lossy()).unwrap();
+        let config = scan_config(&[]);
+
+        // 默认策略下带时间戳后缀的文件与其他多余条目一样被删除
+        let entries = extraneous_entries(&storage, &source_paths, &config, false, false).await;
+        assert_eq!(
+            sorted_paths(entries),
+            vec![
+                "docs/removed.txt.20240101120000",
+                "docs/report.txt.20240101120000",
+                "old.20240101120000"
+            ]
+        );
+        // 源中已不存在的文件的备份不再保留
+        let entries = extraneous_entries(&storage, &source_paths, &config, false, true).await;
+        assert_eq!(
+            sorted_paths(entries),
+            vec!["docs/removed.txt.20240101120000", "old.20240101120000"]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use crate::scan::StorageEntity;
+use crate::sync::atomic::{discard, temp_path};
+use crate::sync::compare::{SyncAction, source_newer};
+use crate::sync::conflict::ConflictPolicy;
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 同步时保留的元数据，对应rsync的 `-l -p -o -g -U`
+/// 文件和目录的修改时间始终保留，跳过未变化文件依赖修改时间比较
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreserveOptions {
+    /// 将符号链接重建为链接，而不是复制其指向的内容
+    pub links: bool,
+    /// 保留权限位，包括setuid/setgid/sticky
+    pub perms: bool,
+    /// 保留属主，仅在有权限（通常为root）时生效
+    pub owner: bool,
+    /// 保留属组，仅在有权限时生效
+    pub group: bool,
+    /// 保留访问时间
+    pub atimes: bool,
+}
+
+impl PreserveOptions {
+    /// 归档模式，对应rsync的 `-a`：保留链接、权限、属主和属组
+    pub fn archive() -> Self {
+        Self {
+            links: true,
+            perms: true,
+            owner: true,
+            group: true,
+            atimes: false,
+        }
+    }
+}
+
+/// 按保留选项将源实体的属主、权限和时间应用到目标路径
+/// 依次设置属主、权限和时间：修改属主会清除setuid位，设置权限会影响目录的修改时间
+pub async fn apply_metadata(
+    entity: &StorageEntity, dest: &Path, options: PreserveOptions,
+) -> std::io::Result<()> {
+    let src = entity.file_path.clone();
+    let dest = dest.to_path_buf();
+    let is_link = entity.is_symlink && options.links;
+    // 未保留链接时目标是链接指向内容的副本，权限已随复制保留，不能使用链接自身的权限
+    let mode = entity.mode.filter(|_| !entity.is_symlink);
+    let atime = FileTime::from_system_time(entity.atime);
+    let mtime = FileTime::from_system_time(entity.mtime);
+
+    tokio::task::spawn_blocking(move || {
+        if options.owner || options.group {
+            set_owner(Path::new(&src), &dest, options, is_link)?;
+        }
+        if options.perms
+            && let Some(mode) = mode
+        {
+            set_mode(&dest, mode)?;
+        }
+
+        let atime = if options.atimes {
+            atime
+        } else {
+            // 不保留访问时间时沿用目标当前的访问时间
+            let metadata = std::fs::symlink_metadata(&dest)?;
+            FileTime::from_last_access_time(&metadata)
+        };
+        if is_link {
+            filetime::set_symlink_file_times(&dest, atime, mtime)
+        } else {
+            filetime::set_file_times(&dest, atime, mtime)
+        }
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// 判断目标的修改时间或权限是否与源不一致，修改时间按秒比较
+/// 属主和属组在无特权时无法设置，不参与比较，否则普通用户每次同步都会得到相同的差异
+pub async fn metadata_differs(
+    entity: &StorageEntity, dest: &Path, options: PreserveOptions,
+) -> std::io::Result<bool> {
+    let metadata = if entity.is_symlink && options.links {
+        tokio::fs::symlink_metadata(dest).await?
+    } else {
+        tokio::fs::metadata(dest).await?
+    };
+
+    let secs = |t: SystemTime| t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).ok();
+    if secs(metadata.modified()?) != secs(entity.mtime) {
+        return Ok(true);
+    }
+    if options.perms
+        && !entity.is_symlink
+        && let Some(mode) = entity.mode
+    {
+        return Ok(mode_differs(&metadata, mode));
+    }
+    Ok(false)
+}
+
+#[cfg(unix)]
+fn mode_differs(metadata: &std::fs::Metadata, mode: u32) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o7777 != mode & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode_differs(metadata: &std::fs::Metadata, mode: u32) -> bool {
+    metadata.permissions().readonly() != (mode & 0o222 == 0)
+}
+
+/// 设置属主和属组，无权限时忽略，与rsync以普通用户运行时的行为一致
+/// `is_link` 为false时源实体若为链接则使用其指向内容的属主
+#[cfg(unix)]
+fn set_owner(
+    src: &Path, dest: &Path, options: PreserveOptions, is_link: bool,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = if is_link {
+        std::fs::symlink_metadata(src)?
+    } else {
+        std::fs::metadata(src)?
+    };
+    let uid = options.owner.then_some(metadata.uid());
+    let gid = options.group.then_some(metadata.gid());
+    match std::os::unix::fs::lchown(dest, uid, gid) {
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            log::debug!(
+                "Insufficient privileges to preserve ownership of {}: {}",
+                dest.display(),
+                e
+            );
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(not(unix))]
+fn set_owner(
+    _src: &Path, _dest: &Path, _options: PreserveOptions, _is_link: bool,
+) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// 设置权限位，忽略文件类型位
+#[cfg(unix)]
+fn set_mode(dest: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(dest, std::fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn set_mode(dest: &Path, mode: u32) -> std::io::Result<()> {
+    let mut permissions = std::fs::metadata(dest)?.permissions();
+    permissions.set_readonly(mode & 0o222 == 0);
+    std::fs::set_permissions(dest, permissions)
+}
+
+/// 根据链接目标决定重建或跳过符号链接，目标不一致时按冲突策略处理
+pub async fn compare_symlink(
+    entity: &StorageEntity, dest: &Path, policy: ConflictPolicy,
+) -> std::io::Result<SyncAction> {
+    let metadata = match tokio::fs::symlink_metadata(dest).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(SyncAction::Create),
+        Err(e) => return Err(e),
+    };
+
+    if policy == ConflictPolicy::Overwrite {
+        return Ok(SyncAction::Update);
+    }
+    if metadata.file_type().is_symlink() {
+        let target = tokio::fs::read_link(&entity.file_path).await?;
+        if tokio::fs::read_link(dest).await? == target {
+            return Ok(SyncAction::Skip);
+        }
+    }
+    Ok(policy.resolve(source_newer(entity.mtime, metadata.modified()?)))
+}
+
+/// 在目标位置重建符号链接，先在临时路径创建再重命名，原子地替换已存在的文件或链接
+pub async fn copy_symlink(entity: &StorageEntity, dest: &Path) -> std::io::Result<()> {
+    let target = tokio::fs::read_link(&entity.file_path).await?;
+
+    match tokio::fs::symlink_metadata(dest).await {
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(std::io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} is a directory", dest.display()),
+            ));
+        }
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let temp = temp_path(dest);
+    discard(&temp).await;
+    create_symlink(&target, &temp).await?;
+    if let Err(e) = tokio::fs::rename(&temp, dest).await {
+        discard(&temp).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn create_symlink(target: &Path, dest: &Path) -> std::io::Result<()> {
+    tokio::fs::symlink(target, dest).await
+}
+
+#[cfg(windows)]
+async fn create_symlink(target: &Path, dest: &Path) -> std::io::Result<()> {
+    tokio::fs::symlink_file(target, dest).await
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn entity(path: &Path) -> StorageEntity {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let metadata = std::fs::symlink_metadata(path).unwrap();
+        StorageEntity {
+            atime: UNIX_EPOCH + Duration::from_secs(1_600_000_000),
+            mtime: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            mode: Some(metadata.permissions().mode()),
+            ..StorageEntity::test_from_path(path, &name)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.sh");
+        let dest = dir.path().join("dest.sh");
+        std::fs::write(&src, "#!/bin/sh").unwrap();
+        std::fs::write(&dest, "#!/bin/sh").unwrap();
+        std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o750)).unwrap();
+        let entity = entity(&src);
+
+        apply_metadata(&entity, &dest, PreserveOptions::default())
+            .await
+            .unwrap();
+        let metadata = std::fs::metadata(&dest).unwrap();
+        assert_eq!(metadata.modified().unwrap(), entity.mtime);
+        assert_ne!(metadata.permissions().mode() & 0o7777, 0o750);
+
+        // 属主和属组与当前用户相同，无需特权即可设置
+        let options = PreserveOptions {
+            atimes: true,
+            ..PreserveOptions::archive()
+        };
+        apply_metadata(&entity, &dest, options).await.unwrap();
+        let metadata = std::fs::metadata(&dest).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o750);
+        assert_eq!(metadata.accessed().unwrap(), entity.atime);
+        assert!(!metadata_differs(&entity, &dest, options).await.unwrap());
+
+        std::fs::set_permissions(&dest, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(metadata_differs(&entity, &dest, options).await.unwrap());
+        // 不保留权限时只比较修改时间
+        assert!(
+            !metadata_differs(&entity, &dest, PreserveOptions::default())
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_symlink_recreated() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("link");
+        let dest = dir.path().join("copy");
+        std::os::unix::fs::symlink("target.txt", &src).unwrap();
+        let entity = entity(&src);
+
+        assert_eq!(
+            compare_symlink(&entity, &dest, ConflictPolicy::default())
+                .await
+                .unwrap(),
+            SyncAction::Create
+        );
+        copy_symlink(&entity, &dest).await.unwrap();
+        assert_eq!(std::fs::read_link(&dest).unwrap(), Path::new("target.txt"));
+        assert_eq!(
+            compare_symlink(&entity, &dest, ConflictPolicy::default())
+                .await
+                .unwrap(),
+            SyncAction::Skip
+        );
+
+        // 目标为普通文件时替换为链接
+        std::fs::remove_file(&dest).unwrap();
+        std::fs::write(&dest, "data").unwrap();
+        assert_eq!(
+            compare_symlink(&entity, &dest, ConflictPolicy::default())
+                .await
+                .unwrap(),
+            SyncAction::Update
+        );
+        copy_symlink(&entity, &dest).await.unwrap();
+        assert!(
+            std::fs::symlink_metadata(&dest)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+
+        // 悬空链接也能设置自身的时间
+        apply_metadata(&entity, &dest, PreserveOptions::archive())
+            .await
+            .unwrap();
+        let metadata = std::fs::symlink_metadata(&dest).unwrap();
+        assert_eq!(metadata.modified().unwrap(), entity.mtime);
+    }
+}
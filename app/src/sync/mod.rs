@@ -1,3 +1,21 @@
+pub mod atomic;
+pub mod checksum;
+pub mod compare;
+pub mod conflict;
+pub mod delete;
+pub mod metadata;
+pub mod plan;
+pub mod pool;
+pub mod section;
+#[allow(clippy::module_inception)]
 pub mod sync;
 
+pub use checksum::ChecksumAlgorithm;
+pub use compare::{CompareOptions, SyncAction, SyncSummary};
+pub use conflict::ConflictPolicy;
+pub use delete::DeleteOptions;
+pub use metadata::PreserveOptions;
+pub use plan::{PlanAction, SyncPlan};
+pub use pool::{CopyOptions, CopyPool};
+pub use section::SectionOptions;
 pub use sync::{SyncConfig, SyncParams, sync};
@@ -0,0 +1,374 @@
+use crate::scan::{ScanConfig, ScanMessage, ScanParams, StorageEntity, parse_expressions, walkdir};
+use crate::sync::compare::{CompareOptions, SyncAction, compare, follow_symlink};
+use crate::sync::conflict::ConflictPolicy;
+use crate::sync::metadata::{PreserveOptions, compare_symlink, metadata_differs};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use storage::throttle::RateLimiter;
+use tokio::sync::mpsc;
+use utils::error::{Error, Result};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// 演练模式写入作业目录的变更计划文件名
+pub const PLAN_FILE_NAME: &str = "plan.json";
+
+/// 计划中的变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Create,
+    Update,
+    Delete,
+    /// 内容一致，仅更新元数据
+    Metadata,
+    /// 重命名已存在的目标后复制
+    Rename,
+    /// 冲突策略为 `fail` 时将失败的文件
+    Conflict,
+}
+
+/// 计划中的单个变更
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanEntry {
+    pub action: PlanAction,
+    pub relative_path: String,
+    pub is_dir: bool,
+    /// 文件大小，删除条目为目标文件的大小
+    pub size: u64,
+}
+
+/// 按变更类型汇总的计数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSummary {
+    pub create: u64,
+    pub update: u64,
+    pub delete: u64,
+    pub metadata: u64,
+    #[serde(default)]
+    pub rename: u64,
+    #[serde(default)]
+    pub conflict: u64,
+    /// 需要复制的字节数
+    pub bytes: u64,
+    /// 将被删除的字节数
+    pub deleted_bytes: u64,
+}
+
+impl fmt::Display for PlanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} to create, {} to update, {} metadata-only, {} to delete",
+            self.create, self.update, self.metadata, self.delete
+        )?;
+        if self.rename > 0 {
+            write!(f, ", {} to rename", self.rename)?;
+        }
+        if self.conflict > 0 {
+            write!(f, ", {} conflicts", self.conflict)?;
+        }
+        write!(
+            f,
+            " ({} bytes to copy, {} bytes to delete)",
+            self.bytes, self.deleted_bytes
+        )
+    }
+}
+
+/// 源条目的指纹，与遍历顺序无关，应用计划前用于检测源是否变化
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceDigest {
+    pub entries: u64,
+    pub hash: u64,
+}
+
+impl SourceDigest {
+    pub fn add(&mut self, entity: &StorageEntity) {
+        // 目录的修改时间随子项变化，子项本身已计入指纹
+        let (size, mtime) = if entity.is_dir {
+            (0, 0)
+        } else {
+            (entity.size, unix_secs(entity.mtime))
+        };
+        let key = format!(
+            "{}\0{}\0{}\0{}",
+            entity.relative_path, entity.is_dir, size, mtime
+        );
+        self.entries += 1;
+        self.hash = self.hash.wrapping_add(xxh3_64(key.as_bytes()));
+    }
+}
+
+/// 同步变更计划，由 `sync --dry-run` 生成，`sync --apply-plan` 执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPlan {
+    pub src_path: String,
+    pub dest_path: String,
+    pub created_at: String,
+    /// 生成计划时的扫描参数，应用计划时按相同的过滤条件遍历源
+    pub scan_params: ScanParams,
+    pub preserve: PreserveOptions,
+    /// 生成计划时的冲突策略，应用计划时沿用
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+    pub source: SourceDigest,
+    pub summary: PlanSummary,
+    pub entries: Vec<PlanEntry>,
+}
+
+impl SyncPlan {
+    pub fn new(
+        src_path: &str, dest_path: &str, scan_params: ScanParams, preserve: PreserveOptions,
+        conflict: ConflictPolicy,
+    ) -> Self {
+        Self {
+            src_path: src_path.to_string(),
+            dest_path: dest_path.to_string(),
+            created_at: chrono::Local::now().to_rfc3339(),
+            scan_params,
+            preserve,
+            conflict,
+            source: SourceDigest::default(),
+            summary: PlanSummary::default(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// 添加变更并更新汇总计数
+    pub fn push(&mut self, entry: PlanEntry) {
+        match entry.action {
+            PlanAction::Create => {
+                self.summary.create += 1;
+                self.summary.bytes += entry.size;
+            }
+            PlanAction::Update => {
+                self.summary.update += 1;
+                self.summary.bytes += entry.size;
+            }
+            PlanAction::Delete => {
+                self.summary.delete += 1;
+                self.summary.deleted_bytes += entry.size;
+            }
+            PlanAction::Metadata => self.summary.metadata += 1,
+            PlanAction::Rename => {
+                self.summary.rename += 1;
+                self.summary.bytes += entry.size;
+            }
+            PlanAction::Conflict => self.summary.conflict += 1,
+        }
+        self.entries.push(entry);
+    }
+
+    /// 指定变更类型的所有相对路径
+    pub fn paths(&self, actions: &[PlanAction]) -> HashSet<String> {
+        self.entries
+            .iter()
+            .filter(|entry| actions.contains(&entry.action))
+            .map(|entry| entry.relative_path.clone())
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read(path)?;
+        serde_json::from_slice(&content).map_err(|e| {
+            Error::with_source(
+                &format!("Failed to parse sync plan {}", path.display()),
+                Box::new(e),
+            )
+        })
+    }
+
+    /// 按计划中的扫描参数重新遍历源，指纹不一致时拒绝执行计划
+    pub async fn verify_source(&self, ops_limiter: Option<Arc<RateLimiter>>) -> Result<()> {
+        let config = ScanConfig {
+            params: self.scan_params.clone(),
+            expressions: parse_expressions(&self.scan_params.match_expressions)?,
+            exclude_expressions: parse_expressions(&self.scan_params.exclude_expressions)?,
+        };
+        let (tx, mut rx) = mpsc::channel::<ScanMessage>(1000);
+        let handle = tokio::spawn(async move { walkdir(config, tx, ops_limiter).await });
+
+        let mut digest = SourceDigest::default();
+        while let Some(message) = rx.recv().await {
+            if let ScanMessage::Result(entity) = message {
+                digest.add(&entity);
+            }
+        }
+        handle
+            .await
+            .map_err(|e| Error::with_source("Walkdir task failed", Box::new(e)))??;
+
+        if digest != self.source {
+            return Err(Error::new(&format!(
+                "Source {} has changed since the plan was created at {} ({} entries planned, {} found), refusing to apply",
+                self.src_path, self.created_at, self.source.entries, digest.entries
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 比较单个源实体与目标，返回需要执行的变更，无需变更时返回None
+/// 与实际同步使用相同的比较规则，但不写入目标
+pub async fn plan_entry(
+    entity: &StorageEntity, dest: &Path, compare_options: &CompareOptions,
+    preserve: PreserveOptions,
+) -> std::io::Result<Option<PlanEntry>> {
+    let followed;
+    let entity = if entity.is_symlink && !preserve.links {
+        let mut target = entity.clone();
+        follow_symlink(&mut target).await?;
+        followed = target;
+        &followed
+    } else {
+        entity
+    };
+
+    let action = if entity.is_dir {
+        match tokio::fs::metadata(dest).await {
+            Ok(metadata) if metadata.is_dir() => SyncAction::Skip,
+            Ok(_) => SyncAction::Update,
+            Err(e) if e.kind() == ErrorKind::NotFound => SyncAction::Create,
+            Err(e) => return Err(e),
+        }
+    } else if entity.is_symlink && preserve.links {
+        compare_symlink(entity, dest, compare_options.policy).await?
+    } else {
+        compare(entity, dest, compare_options).await?
+    };
+
+    let action = match action {
+        SyncAction::Create => PlanAction::Create,
+        SyncAction::Update => PlanAction::Update,
+        SyncAction::Rename => PlanAction::Rename,
+        SyncAction::Reject => PlanAction::Conflict,
+        SyncAction::Keep => return Ok(None),
+        SyncAction::Skip | SyncAction::Metadata => {
+            if !metadata_differs(entity, dest, preserve).await? {
+                return Ok(None);
+            }
+            PlanAction::Metadata
+        }
+    };
+
+    Ok(Some(PlanEntry {
+        action,
+        relative_path: entity.relative_path.clone(),
+        is_dir: entity.is_dir,
+        size: if entity.is_dir { 0 } else { entity.size },
+    }))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_plan_entries() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let mtime = filetime::FileTime::from_unix_time(1_700_000_000, 0);
+        for name in ["new.txt", "changed.txt", "same.txt", "touched.txt"] {
+            std::fs::write(src.path().join(name), "source").unwrap();
+            filetime::set_file_mtime(src.path().join(name), mtime).unwrap();
+        }
+        std::fs::create_dir(src.path().join("dir")).unwrap();
+        std::fs::write(dest.path().join("changed.txt"), "old").unwrap();
+        std::fs::write(dest.path().join("same.txt"), "source").unwrap();
+        std::fs::write(dest.path().join("touched.txt"), "source").unwrap();
+        filetime::set_file_mtime(dest.path().join("same.txt"), mtime).unwrap();
+
+        let options = CompareOptions {
+            checksum: true,
+            ..CompareOptions::default()
+        };
+        let mut plan = SyncPlan::new(
+            &src.path().to_string_lossy(),
+            &dest.path().to_string_lossy(),
+            ScanParams::default(),
+            PreserveOptions::default(),
+            ConflictPolicy::default(),
+        );
+        for name in ["new.txt", "changed.txt", "same.txt", "touched.txt", "dir"] {
+            let entity = StorageEntity::test_from_path(&src.path().join(name), name);
+            let entry = plan_entry(
+                &entity,
+                &dest.path().join(name),
+                &options,
+                PreserveOptions::default(),
+            )
+            .await
+            .unwrap();
+            if let Some(entry) = entry {
+                plan.push(entry);
+            }
+        }
+
+        let actions: Vec<(&str, PlanAction)> = plan
+            .entries
+            .iter()
+            .map(|entry| (entry.relative_path.as_str(), entry.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("new.txt", PlanAction::Create),
+                ("changed.txt", PlanAction::Update),
+                ("touched.txt", PlanAction::Metadata),
+                ("dir", PlanAction::Create),
+            ]
+        );
+        assert_eq!(plan.summary.bytes, 12);
+        // 演练不写入目标
+        assert!(!dest.path().join("new.txt").exists());
+
+        let path = dest.path().join(PLAN_FILE_NAME);
+        plan.save(&path).unwrap();
+        let loaded = SyncPlan::load(&path).unwrap();
+        assert_eq!(loaded.entries, plan.entries);
+        assert_eq!(loaded.summary, plan.summary);
+    }
+
+    #[test]
+    fn test_source_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "b").unwrap();
+        let a = StorageEntity::test_from_path(&dir.path().join("a.txt"), "a.txt");
+        let b = StorageEntity::test_from_path(&dir.path().join("b.txt"), "b.txt");
+
+        let mut forward = SourceDigest::default();
+        forward.add(&a);
+        forward.add(&b);
+        let mut backward = SourceDigest::default();
+        backward.add(&b);
+        backward.add(&a);
+        assert_eq!(forward, backward);
+
+        let mut modified = SourceDigest::default();
+        modified.add(&a);
+        modified.add(&StorageEntity {
+            mtime: b.mtime + Duration::from_secs(1),
+            ..b
+        });
+        assert_ne!(forward, modified);
+    }
+}
@@ -0,0 +1,635 @@
+use crate::consumer::format_bytes;
+use crate::scan::{ScanMessage, StorageEntity};
+use crate::sync::atomic::{commit, discard, sync_temp, temp_path};
+use crate::sync::checksum::{ChecksumAlgorithm, checksum_file, copy_stream};
+use crate::sync::compare::{CompareOptions, SyncAction, SyncSummary, compare, follow_symlink};
+use crate::sync::conflict::rename_existing;
+use crate::sync::metadata::{
+    PreserveOptions, apply_metadata, compare_symlink, copy_symlink, metadata_differs,
+};
+use crate::sync::section::{SectionOptions, copy_sections};
+use crate::throttle::Throttle;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, broadcast, mpsc};
+use tokio::task::JoinHandle;
+use utils::error::{Error, Result};
+
+/// 在途字节额度以KiB为单位换算为信号量许可
+const PERMIT_UNIT: u64 = 1024;
+
+/// 所有复制任务共用的选项
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    pub compare: CompareOptions,
+    /// 启用校验时使用的哈希算法
+    pub verify: Option<ChecksumAlgorithm>,
+    pub preserve: PreserveOptions,
+    /// 大文件分段传输的选项，为空时整个文件一次复制
+    pub sections: Option<SectionOptions>,
+    /// 作业级限速，所有worker共享
+    pub throttle: Throttle,
+    /// 重命名到目标路径前将临时文件落盘
+    pub fsync: bool,
+}
+
+/// 单个文件的复制任务，许可在任务处理完成后释放，归还在途字节额度
+struct CopyTask {
+    entity: StorageEntity,
+    dest_path: PathBuf,
+    _permit: OwnedSemaphorePermit,
+}
+
+/// 单个worker的进度计数
+#[derive(Debug, Default)]
+pub struct WorkerProgress {
+    /// 已处理的文件数，包含跳过的文件
+    pub files: AtomicU64,
+    /// 已复制的字节数
+    pub bytes: AtomicU64,
+    pub failed: AtomicU64,
+}
+
+/// 复制worker池：固定数量的worker从共享队列领取文件复制任务
+/// 提交任务前按文件大小申请在途字节额度，大量小文件和少量大文件都能占满带宽而不会无限制地占用内存和句柄
+pub struct CopyPool {
+    sender: mpsc::Sender<CopyTask>,
+    workers: Vec<JoinHandle<()>>,
+    progress: Vec<Arc<WorkerProgress>>,
+    summary: Arc<Mutex<SyncSummary>>,
+    inflight: Arc<Semaphore>,
+    /// 在途字节额度对应的许可总数
+    inflight_permits: u32,
+}
+
+impl CopyPool {
+    /// 启动 `concurrency` 个worker，处理完成的实体由worker广播给消费者
+    pub fn new(
+        concurrency: u32, max_inflight_bytes: u64, options: CopyOptions,
+        broadcaster: broadcast::Sender<ScanMessage>,
+    ) -> Self {
+        let concurrency = concurrency.max(1) as usize;
+        let inflight_permits = max_inflight_bytes
+            .div_ceil(PERMIT_UNIT)
+            .clamp(1, u32::MAX as u64) as u32;
+        let (sender, receiver) = mpsc::channel(concurrency * 2);
+        let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+        let summary = Arc::new(Mutex::new(SyncSummary::default()));
+
+        let mut workers = Vec::with_capacity(concurrency);
+        let mut progress = Vec::with_capacity(concurrency);
+        for _ in 0..concurrency {
+            let worker_progress = Arc::new(WorkerProgress::default());
+            workers.push(tokio::spawn(run_worker(
+                Arc::clone(&receiver),
+                options.clone(),
+                broadcaster.clone(),
+                Arc::clone(&summary),
+                Arc::clone(&worker_progress),
+            )));
+            progress.push(worker_progress);
+        }
+
+        Self {
+            sender,
+            workers,
+            progress,
+            summary,
+            inflight: Arc::new(Semaphore::new(inflight_permits as usize)),
+            inflight_permits,
+        }
+    }
+
+    /// 提交文件复制任务，在途字节达到上限或队列已满时等待
+    /// 超过上限的单个文件占用全部额度，等待其他任务完成后单独复制
+    pub async fn submit(&self, entity: StorageEntity, dest_path: PathBuf) -> Result<()> {
+        let permits = entity
+            .size
+            .div_ceil(PERMIT_UNIT)
+            .clamp(1, self.inflight_permits as u64) as u32;
+        let permit = Arc::clone(&self.inflight)
+            .acquire_many_owned(permits)
+            .await
+            .map_err(|e| Error::with_source("Copy pool is closed", Box::new(e)))?;
+
+        self.sender
+            .send(CopyTask {
+                entity,
+                dest_path,
+                _permit: permit,
+            })
+            .await
+            .map_err(|_| Error::new("All copy workers have stopped"))
+    }
+
+    /// 当前的汇总计数
+    pub fn summary(&self) -> SyncSummary {
+        self.summary.lock().unwrap().clone()
+    }
+
+    /// 各worker的进度，例如 `#0 12 files 3.4 MiB, #1 9 files 1.2 GiB 1 failed`
+    pub fn worker_progress(&self) -> String {
+        self.progress
+            .iter()
+            .enumerate()
+            .map(|(index, progress)| {
+                let mut line = format!(
+                    "#{} {} files {}",
+                    index,
+                    progress.files.load(Ordering::Relaxed),
+                    format_bytes(progress.bytes.load(Ordering::Relaxed) as f64)
+                );
+                let failed = progress.failed.load(Ordering::Relaxed);
+                if failed > 0 {
+                    line.push_str(&format!(" {} failed", failed));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// 关闭任务队列，等待所有worker处理完已提交的任务后返回汇总计数
+    pub async fn finish(self) -> SyncSummary {
+        let Self {
+            sender,
+            workers,
+            summary,
+            ..
+        } = self;
+        drop(sender);
+
+        for worker in workers {
+            if let Err(e) = worker.await {
+                log::error!("Copy worker failed: {}", e);
+            }
+        }
+
+        summary.lock().unwrap().clone()
+    }
+}
+
+/// worker主循环：比较、复制并广播实体，直到任务队列关闭
+async fn run_worker(
+    receiver: Arc<tokio::sync::Mutex<mpsc::Receiver<CopyTask>>>, options: CopyOptions,
+    broadcaster: broadcast::Sender<ScanMessage>, summary: Arc<Mutex<SyncSummary>>,
+    progress: Arc<WorkerProgress>,
+) {
+    loop {
+        let task = receiver.lock().await.recv().await;
+        let Some(mut task) = task else {
+            break;
+        };
+
+        let entity = &mut task.entity;
+        let action = if entity.is_symlink && options.preserve.links {
+            sync_symlink(entity, &task.dest_path, &options).await
+        } else {
+            sync_file(entity, &task.dest_path, &options).await
+        };
+
+        progress.files.fetch_add(1, Ordering::Relaxed);
+        match action {
+            Some(action) => {
+                match action {
+                    SyncAction::Create | SyncAction::Update | SyncAction::Rename => {
+                        progress.bytes.fetch_add(entity.size, Ordering::Relaxed);
+                    }
+                    SyncAction::Reject => {
+                        progress.failed.fetch_add(1, Ordering::Relaxed);
+                    }
+                    _ => {}
+                }
+                let mut summary = summary.lock().unwrap();
+                summary.record(action, entity.size);
+                if action.is_conflict() {
+                    summary.record_conflict(options.compare.policy);
+                }
+            }
+            None => {
+                progress.failed.fetch_add(1, Ordering::Relaxed);
+                summary.lock().unwrap().failed += 1;
+            }
+        }
+
+        // 复制完成后再广播，使消费者收到的记录带有文件哈希
+        if let Err(e) = broadcaster.send(ScanMessage::Result(task.entity)) {
+            log::error!("Failed to broadcast scan result: {}", e);
+        }
+    }
+}
+
+/// 比较并按需复制文件，未保留的符号链接按其指向的文件处理，失败时输出错误并返回None
+async fn sync_file(
+    entity: &mut StorageEntity, dest_path: &Path, options: &CopyOptions,
+) -> Option<SyncAction> {
+    options.throttle.acquire_ops(1).await;
+    if entity.is_symlink
+        && let Err(e) = follow_symlink(entity).await
+    {
+        eprintln!("Failed to read link target {}: {}", entity.file_path, e);
+        return None;
+    }
+    let action = match compare(entity, dest_path, &options.compare).await {
+        Ok(action) => action,
+        Err(e) => {
+            eprintln!("Failed to compare {}: {}", dest_path.display(), e);
+            return None;
+        }
+    };
+    match action {
+        SyncAction::Skip => refresh_metadata(entity, dest_path, options).await,
+        SyncAction::Keep | SyncAction::Reject => Some(resolve_kept(entity, dest_path, action)),
+        _ => copy_file(entity, dest_path, options, action)
+            .await
+            .then_some(action),
+    }
+}
+
+/// 比较并按需重建符号链接，失败时输出错误并返回None
+async fn sync_symlink(
+    entity: &StorageEntity, dest_path: &Path, options: &CopyOptions,
+) -> Option<SyncAction> {
+    options.throttle.acquire_ops(1).await;
+    let action = match compare_symlink(entity, dest_path, options.compare.policy).await {
+        Ok(action) => action,
+        Err(e) => {
+            eprintln!("Failed to compare {}: {}", dest_path.display(), e);
+            return None;
+        }
+    };
+    match action {
+        SyncAction::Skip => return refresh_metadata(entity, dest_path, options).await,
+        SyncAction::Keep | SyncAction::Reject => {
+            return Some(resolve_kept(entity, dest_path, action));
+        }
+        SyncAction::Rename if !rename_dest(dest_path).await => return None,
+        _ => {}
+    }
+
+    if let Some(parent_dir) = dest_path.parent()
+        && let Err(e) = tokio::fs::create_dir_all(parent_dir).await
+    {
+        eprintln!("Failed to create directory: {}", e);
+        return None;
+    }
+    if let Err(e) = copy_symlink(entity, dest_path).await {
+        eprintln!("Failed to create symlink {}: {}", dest_path.display(), e);
+        return None;
+    }
+    if let Err(e) = apply_metadata(entity, dest_path, options.preserve).await {
+        eprintln!("Failed to set metadata of {}: {}", dest_path.display(), e);
+        return None;
+    }
+    Some(action)
+}
+
+/// 按冲突策略保留目标，策略为 `fail` 时输出错误
+fn resolve_kept(entity: &StorageEntity, dest_path: &Path, action: SyncAction) -> SyncAction {
+    if action == SyncAction::Reject {
+        eprintln!(
+            "Destination {} already exists and differs from the source",
+            dest_path.display()
+        );
+    } else {
+        log::debug!("Keeping existing destination: {}", entity.relative_path);
+    }
+    action
+}
+
+/// 重命名已存在的目标，失败时输出错误并返回false
+async fn rename_dest(dest_path: &Path) -> bool {
+    match rename_existing(dest_path).await {
+        Ok(renamed) => {
+            log::info!(
+                "Renamed existing {} to {}",
+                dest_path.display(),
+                renamed.display()
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to rename {}: {}", dest_path.display(), e);
+            false
+        }
+    }
+}
+
+/// 内容未变化时只在元数据不一致时恢复元数据，失败时输出错误并返回None
+async fn refresh_metadata(
+    entity: &StorageEntity, dest_path: &Path, options: &CopyOptions,
+) -> Option<SyncAction> {
+    match metadata_differs(entity, dest_path, options.preserve).await {
+        Ok(false) => {
+            log::debug!("Skipping unchanged entry: {}", entity.relative_path);
+            Some(SyncAction::Skip)
+        }
+        Ok(true) => match apply_metadata(entity, dest_path, options.preserve).await {
+            Ok(()) => Some(SyncAction::Metadata),
+            Err(e) => {
+                eprintln!("Failed to set metadata of {}: {}", dest_path.display(), e);
+                None
+            }
+        },
+        Err(e) => {
+            eprintln!("Failed to compare {}: {}", dest_path.display(), e);
+            None
+        }
+    }
+}
+
+/// 复制单个文件并按保留选项恢复元数据，失败时输出错误并返回false
+/// 先写入同目录下的隐藏临时文件，恢复元数据并校验后再重命名到目标路径，中断时不会留下不完整的目标文件
+/// 指定校验算法时将源文件哈希写入 `entity.checksum`，目标文件哈希不一致时视为失败
+async fn copy_file(
+    entity: &mut StorageEntity, dest_path: &Path, options: &CopyOptions, action: SyncAction,
+) -> bool {
+    let verify = options.verify;
+    if let Some(parent_dir) = dest_path.parent()
+        && let Err(e) = tokio::fs::create_dir_all(parent_dir).await
+    {
+        eprintln!("Failed to create directory: {}", e);
+        return false;
+    }
+
+    // 创建目标文件计为一次元数据操作
+    options.throttle.acquire_ops(1).await;
+    let src_path = Path::new(&entity.file_path);
+    let temp_path = temp_path(dest_path);
+    let bandwidth = &options.throttle.bytes;
+    let sectioned = options
+        .sections
+        .as_ref()
+        .filter(|sections| sections.applies_to(entity.size));
+    let copied = match sectioned {
+        // 分段传输无法在复制过程中按顺序计算哈希，完成后再读取源文件计算
+        Some(sections) => {
+            match copy_sections(entity, &temp_path, sections, bandwidth.clone()).await {
+                Ok(()) => match verify {
+                    Some(algorithm) => checksum_file(src_path, algorithm).await.map(Some),
+                    None => Ok(None),
+                },
+                Err(e) => Err(e),
+            }
+        }
+        // 不校验也不限速时使用系统复制
+        None if verify.is_none() && bandwidth.is_none() => {
+            tokio::fs::copy(src_path, &temp_path).await.map(|_| None)
+        }
+        None => copy_stream(src_path, &temp_path, verify, bandwidth.as_deref()).await,
+    };
+    match copied {
+        Ok(checksum) => entity.checksum = checksum,
+        Err(e) => {
+            eprintln!("Failed to copy file: {}", e);
+            // 分段传输的临时文件与断点一起保留，重新运行时继续传输
+            if sectioned.is_none() {
+                discard(&temp_path).await;
+            }
+            return false;
+        }
+    }
+
+    let finished = finish_temp(entity, &temp_path, dest_path, options, action).await;
+    if !finished {
+        discard(&temp_path).await;
+    }
+    finished
+}
+
+/// 将写入完成的临时文件落盘、恢复元数据并校验，然后重命名到目标路径
+async fn finish_temp(
+    entity: &StorageEntity, temp_path: &Path, dest_path: &Path, options: &CopyOptions,
+    action: SyncAction,
+) -> bool {
+    if options.fsync
+        && let Err(e) = sync_temp(temp_path).await
+    {
+        eprintln!("Failed to flush {}: {}", temp_path.display(), e);
+        return false;
+    }
+
+    if let Err(e) = apply_metadata(entity, temp_path, options.preserve).await {
+        eprintln!("Failed to set metadata of {}: {}", dest_path.display(), e);
+        return false;
+    }
+
+    if let (Some(algorithm), Some(expected)) = (options.verify, &entity.checksum) {
+        match checksum_file(temp_path, algorithm).await {
+            Ok(actual) if actual == *expected => {}
+            Ok(actual) => {
+                eprintln!(
+                    "Checksum mismatch for {}: source {}, destination {}",
+                    dest_path.display(),
+                    expected,
+                    actual
+                );
+                return false;
+            }
+            Err(e) => {
+                eprintln!("Failed to verify {}: {}", dest_path.display(), e);
+                return false;
+            }
+        }
+    }
+
+    // 新内容已完整写入后再移走已存在的目标
+    if action == SyncAction::Rename && !rename_dest(dest_path).await {
+        return false;
+    }
+    if let Err(e) = commit(temp_path, dest_path, options.fsync).await {
+        eprintln!("Failed to rename into {}: {}", dest_path.display(), e);
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::conflict::ConflictPolicy;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn source_entity(root: &Path, relative_path: &str, content: &[u8]) -> StorageEntity {
+        let path = root.join(relative_path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        StorageEntity {
+            ctime: mtime,
+            mtime,
+            ..StorageEntity::test_from_path(&path, relative_path)
+        }
+    }
+
+    fn options(policy: ConflictPolicy) -> CopyOptions {
+        CopyOptions {
+            compare: CompareOptions {
+                policy,
+                checksum: false,
+            },
+            verify: None,
+            preserve: PreserveOptions::default(),
+            sections: None,
+            throttle: Throttle::default(),
+            fsync: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_copies_and_broadcasts() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let job = tempfile::tempdir().unwrap();
+        let (broadcaster, mut receiver) = broadcast::channel(64);
+        let options = CopyOptions {
+            verify: Some(ChecksumAlgorithm::Xxh3),
+            // 大文件分段复制，小文件直接复制
+            sections: Some(SectionOptions {
+                threshold: 32 * 1024,
+                section_size: 16 * 1024,
+                concurrency: 2,
+                checkpoint_dir: job.path().to_path_buf(),
+            }),
+            fsync: true,
+            ..options(ConflictPolicy::default())
+        };
+        // 在途额度小于单个文件时仍能逐个完成复制
+        let pool = CopyPool::new(3, 4 * 1024, options, broadcaster);
+
+        let large = vec![7u8; 64 * 1024];
+        for index in 0..10 {
+            let relative_path = format!("dir{}/file{}.bin", index % 2, index);
+            let content: &[u8] = if index == 0 { &large } else { b"small" };
+            let entity = source_entity(src.path(), &relative_path, content);
+            pool.submit(entity, dest.path().join(&relative_path))
+                .await
+                .unwrap();
+        }
+        assert_eq!(pool.worker_progress().matches('#').count(), 3);
+
+        let summary = pool.finish().await;
+        assert_eq!(summary.copied, 10);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.bytes, 64 * 1024 + 9 * 5);
+        assert_eq!(
+            std::fs::read(dest.path().join("dir0/file0.bin")).unwrap(),
+            large
+        );
+        let mtime = std::fs::metadata(dest.path().join("dir1/file9.bin"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(mtime, UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+        let mut broadcast = 0;
+        while let Ok(ScanMessage::Result(entity)) = receiver.try_recv() {
+            assert!(entity.checksum.unwrap().starts_with("xxh3:"));
+            broadcast += 1;
+        }
+        assert_eq!(broadcast, 10);
+    }
+
+    #[tokio::test]
+    async fn test_pool_counts_failures() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let (broadcaster, _receiver) = broadcast::channel(16);
+        let pool = CopyPool::new(
+            2,
+            1024 * 1024,
+            options(ConflictPolicy::default()),
+            broadcaster,
+        );
+
+        let mut missing = source_entity(src.path(), "missing.txt", b"gone");
+        missing.file_path = src.path().join("absent.txt").to_string_lossy().to_string();
+        pool.submit(missing, dest.path().join("missing.txt"))
+            .await
+            .unwrap();
+        let entity = source_entity(src.path(), "ok.txt", b"ok");
+        pool.submit(entity, dest.path().join("ok.txt"))
+            .await
+            .unwrap();
+
+        let summary = pool.finish().await;
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_pool_conflict_policy() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let (broadcaster, _receiver) = broadcast::channel(16);
+        let options = options(ConflictPolicy::RenameExisting);
+        let pool = CopyPool::new(1, 1024 * 1024, options, broadcaster);
+
+        std::fs::write(dest.path().join("changed.txt"), "old").unwrap();
+        let entity = source_entity(src.path(), "changed.txt", b"new");
+        pool.submit(entity, dest.path().join("changed.txt"))
+            .await
+            .unwrap();
+        let entity = source_entity(src.path(), "new.txt", b"new");
+        pool.submit(entity, dest.path().join("new.txt"))
+            .await
+            .unwrap();
+
+        let summary = pool.finish().await;
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.renamed, 1);
+        assert_eq!(
+            summary.conflicts.get(&ConflictPolicy::RenameExisting),
+            Some(&1)
+        );
+        // 已存在的目标保留在带时间戳后缀的文件中
+        let mut names: Vec<String> = std::fs::read_dir(dest.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 3);
+        assert!(names[1].starts_with("changed.txt."));
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join(&names[1])).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join("changed.txt")).unwrap(),
+            "new"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pool_skips_followed_symlink_on_resync() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        let target = source_entity(src.path(), "target.txt", b"linked content");
+        let link_path = src.path().join("link.txt");
+        std::os::unix::fs::symlink(&target.file_path, &link_path).unwrap();
+
+        // walkdir不跟随链接，实体的大小和时间来自链接自身
+        let link = StorageEntity::test_from_path(&link_path, "link.txt");
+
+        let mut summaries = Vec::new();
+        for _ in 0..2 {
+            let (broadcaster, _receiver) = broadcast::channel(16);
+            let options = options(ConflictPolicy::default());
+            let pool = CopyPool::new(1, 1024 * 1024, options, broadcaster);
+            pool.submit(link.clone(), dest.path().join("link.txt"))
+                .await
+                .unwrap();
+            summaries.push(pool.finish().await);
+        }
+
+        assert_eq!(summaries[0].copied, 1);
+        assert_eq!(summaries[1].copied + summaries[1].updated, 0);
+        assert_eq!(summaries[1].skipped, 1);
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join("link.txt")).unwrap(),
+            "linked content"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use crate::scan::StorageEntity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use storage::file::{AsyncSectionReader, AsyncSectionWriter};
+use storage::throttle::RateLimiter;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use xxhash_rust::xxh3::{Xxh3, xxh3_64};
+
+/// 作业目录中存放断点文件的子目录
+pub const CHECKPOINT_DIR_NAME: &str = "checkpoints";
+
+/// 分段读写时每次读取的字节数
+const SECTION_BUFFER_SIZE: usize = 256 * 1024;
+
+/// 大文件分段传输的选项
+#[derive(Debug, Clone)]
+pub struct SectionOptions {
+    /// 不小于该大小的文件分段传输
+    pub threshold: u64,
+    pub section_size: u64,
+    /// 单个文件同时传输的分段数
+    pub concurrency: usize,
+    /// 断点文件所在目录
+    pub checkpoint_dir: PathBuf,
+}
+
+impl SectionOptions {
+    /// 文件是否需要分段传输，不足两段的文件直接复制
+    pub fn applies_to(&self, size: u64) -> bool {
+        size >= self.threshold && size > self.section_size
+    }
+}
+
+/// 单个文件的传输断点，记录已完成分段写入内容的XXH3哈希
+/// 源文件的大小、修改时间或分段大小变化后断点失效
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    relative_path: String,
+    size: u64,
+    mtime: u64,
+    section_size: u64,
+    sections: BTreeMap<u64, u64>,
+}
+
+impl Checkpoint {
+    fn new(entity: &StorageEntity, section_size: u64) -> Self {
+        Self {
+            relative_path: entity.relative_path.clone(),
+            size: entity.size,
+            mtime: entity
+                .mtime
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            section_size,
+            sections: BTreeMap::new(),
+        }
+    }
+
+    /// 写入临时文件后重命名，中断时不会留下不完整的断点
+    async fn save(&self, path: &Path) -> std::io::Result<()> {
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(self)?).await?;
+        tokio::fs::rename(&tmp_path, path).await
+    }
+}
+
+/// 分段并行复制文件，每完成一段就更新断点
+/// 重新运行时校验断点中已完成的分段，只复制缺失或校验失败的分段
+pub async fn copy_sections(
+    entity: &StorageEntity, dest: &Path, options: &SectionOptions,
+    limiter: Option<Arc<RateLimiter>>,
+) -> std::io::Result<()> {
+    let src = PathBuf::from(&entity.file_path);
+    let checkpoint_path = checkpoint_path(&options.checkpoint_dir, &entity.relative_path);
+    let expected = Checkpoint::new(entity, options.section_size);
+    let mut checkpoint = match load_checkpoint(&checkpoint_path, &expected, dest).await {
+        Some(checkpoint) => checkpoint,
+        None => {
+            // 没有可用的断点时重新创建目标文件
+            let file = tokio::fs::File::create(dest).await?;
+            file.set_len(entity.size).await?;
+            expected
+        }
+    };
+
+    let total = entity.size.div_ceil(options.section_size);
+    if !checkpoint.sections.is_empty() {
+        let mut valid = BTreeMap::new();
+        for (&index, &hash) in &checkpoint.sections {
+            let (offset, len) = section_range(index, options.section_size, entity.size);
+            if hash_section(dest, offset, len).await? == hash {
+                valid.insert(index, hash);
+            } else {
+                log::warn!(
+                    "Section {} of {} failed validation, copying it again",
+                    index,
+                    dest.display()
+                );
+            }
+        }
+        checkpoint.sections = valid;
+        log::info!(
+            "Resuming {} with {}/{} sections already copied",
+            entity.relative_path,
+            checkpoint.sections.len(),
+            total
+        );
+    }
+
+    tokio::fs::create_dir_all(&options.checkpoint_dir).await?;
+    let mut pending = (0..total)
+        .filter(|index| !checkpoint.sections.contains_key(index))
+        .collect::<Vec<_>>()
+        .into_iter();
+    let checkpoint = Arc::new(Mutex::new(checkpoint));
+
+    let mut tasks = JoinSet::new();
+    let mut result = Ok(());
+    loop {
+        // 出错后不再启动新的分段，等待进行中的分段完成并记录断点
+        while result.is_ok()
+            && tasks.len() < options.concurrency.max(1)
+            && let Some(index) = pending.next()
+        {
+            let (offset, len) = section_range(index, options.section_size, entity.size);
+            tasks.spawn(copy_section(
+                src.clone(),
+                dest.to_path_buf(),
+                index,
+                offset,
+                len,
+                Arc::clone(&checkpoint),
+                checkpoint_path.clone(),
+                limiter.clone(),
+            ));
+        }
+
+        match tasks.join_next().await {
+            None => break,
+            Some(Ok(Ok(()))) => {}
+            Some(Ok(Err(e))) => {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+            Some(Err(e)) => {
+                if result.is_ok() {
+                    result = Err(std::io::Error::other(e));
+                }
+            }
+        }
+    }
+    result?;
+
+    let permissions = tokio::fs::metadata(&src).await?.permissions();
+    tokio::fs::set_permissions(dest, permissions).await?;
+    match tokio::fs::remove_file(&checkpoint_path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// 相对路径对应的断点文件
+pub fn checkpoint_path(checkpoint_dir: &Path, relative_path: &str) -> PathBuf {
+    checkpoint_dir.join(format!("{:016x}.json", xxh3_64(relative_path.as_bytes())))
+}
+
+/// 读取与当前源文件匹配的断点，目标文件大小不符时断点无效
+async fn load_checkpoint(path: &Path, expected: &Checkpoint, dest: &Path) -> Option<Checkpoint> {
+    let content = tokio::fs::read(path).await.ok()?;
+    let checkpoint: Checkpoint = match serde_json::from_slice(&content) {
+        Ok(checkpoint) => checkpoint,
+        Err(e) => {
+            log::warn!("Ignoring unreadable checkpoint {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    let matches = checkpoint.relative_path == expected.relative_path
+        && checkpoint.size == expected.size
+        && checkpoint.mtime == expected.mtime
+        && checkpoint.section_size == expected.section_size;
+    let dest_len = tokio::fs::metadata(dest).await.ok().map(|m| m.len());
+    if !matches || dest_len != Some(expected.size) {
+        log::info!("Discarding stale checkpoint for {}", expected.relative_path);
+        return None;
+    }
+    Some(checkpoint)
+}
+
+/// 分段的起始偏移和长度，最后一段可能较短
+fn section_range(index: u64, section_size: u64, size: u64) -> (u64, u64) {
+    let offset = index * section_size;
+    (offset, section_size.min(size - offset))
+}
+
+/// 复制单个分段，落盘后记录到断点
+#[allow(clippy::too_many_arguments)]
+async fn copy_section(
+    src: PathBuf, dest: PathBuf, index: u64, offset: u64, len: u64,
+    checkpoint: Arc<Mutex<Checkpoint>>, checkpoint_path: PathBuf,
+    limiter: Option<Arc<RateLimiter>>,
+) -> std::io::Result<()> {
+    let mut reader = AsyncSectionReader::new(src, offset, len).await?;
+    let mut writer = AsyncSectionWriter::new(dest, offset, len).await?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0u8; SECTION_BUFFER_SIZE];
+    let mut copied = 0u64;
+
+    loop {
+        let read = reader.read_chunk(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        if let Some(limiter) = &limiter {
+            limiter.acquire(read as u64).await;
+        }
+        hasher.update(&buf[..read]);
+        let mut written = 0;
+        while written < read {
+            let n = writer.write_chunk(&buf[written..read]).await?;
+            if n == 0 {
+                return Err(std::io::Error::from(ErrorKind::WriteZero));
+            }
+            written += n;
+        }
+        copied += read as u64;
+    }
+    if copied != len {
+        return Err(std::io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "source ended {} bytes early in section {}",
+                len - copied,
+                index
+            ),
+        ));
+    }
+    writer.sync_data().await?;
+
+    let mut checkpoint = checkpoint.lock().await;
+    checkpoint.sections.insert(index, hasher.digest());
+    checkpoint.save(&checkpoint_path).await
+}
+
+/// 计算目标文件中一个分段的哈希
+async fn hash_section(path: &Path, offset: u64, len: u64) -> std::io::Result<u64> {
+    let mut reader = AsyncSectionReader::new(path.to_path_buf(), offset, len).await?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0u8; SECTION_BUFFER_SIZE];
+
+    loop {
+        let read = reader.read_chunk(&mut buf).await?;
+        if read == 0 {
+            return Ok(hasher.digest());
+        }
+        hasher.update(&buf[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(checkpoint_dir: &Path) -> SectionOptions {
+        SectionOptions {
+            threshold: 0,
+            section_size: 1000,
+            concurrency: 3,
+            checkpoint_dir: checkpoint_dir.to_path_buf(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dest = dir.path().join("dest.bin");
+        let content: Vec<u8> = (0..10_500).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&src, &content).unwrap();
+        let options = options(&dir.path().join(CHECKPOINT_DIR_NAME));
+
+        let entity = StorageEntity::test_from_path(&src, "src.bin");
+        copy_sections(&entity, &dest, &options, None).await.unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), content);
+        // 复制完成后删除断点
+        assert_eq!(
+            std::fs::read_dir(&options.checkpoint_dir).unwrap().count(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dest = dir.path().join("dest.bin");
+        let content: Vec<u8> = (0..2500).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&src, &content).unwrap();
+        let entity = StorageEntity::test_from_path(&src, "src.bin");
+        let options = options(&dir.path().join(CHECKPOINT_DIR_NAME));
+
+        // 模拟中断：第0段已记录，第1段的记录与目标内容不一致
+        let mut partial = vec![0u8; 2500];
+        partial[..1000].fill(9);
+        std::fs::write(&dest, &partial).unwrap();
+        let mut checkpoint = Checkpoint::new(&entity, options.section_size);
+        checkpoint.sections.insert(0, xxh3_64(&partial[..1000]));
+        checkpoint.sections.insert(1, 42);
+        std::fs::create_dir_all(&options.checkpoint_dir).unwrap();
+        let checkpoint_path = checkpoint_path(&options.checkpoint_dir, "src.bin");
+        checkpoint.save(&checkpoint_path).await.unwrap();
+
+        copy_sections(&entity, &dest, &options, None).await.unwrap();
+        let copied = std::fs::read(&dest).unwrap();
+        // 校验通过的第0段不再复制，其余分段来自源文件
+        assert_eq!(&copied[..1000], &partial[..1000]);
+        assert_eq!(&copied[1000..], &content[1000..]);
+        assert!(!checkpoint_path.exists());
+    }
+}
@@ -1,19 +1,32 @@
 use crate::consumer::ConsumerManager;
+use crate::job::job_dir;
 use crate::scan::scan::ConsumerConfig;
 use crate::scan::{
-    FilterExpression, ScanConfig, ScanMessage, ScanParams, parse_expressions, walkdir,
+    FilterExpression, ScanConfig, ScanMessage, ScanParams, StorageEntity, parse_expressions,
+    walkdir,
 };
+use crate::sync::atomic::remove_orphans;
+use crate::sync::checksum::ChecksumAlgorithm;
+use crate::sync::compare::{CompareOptions, SyncSummary};
+use crate::sync::conflict::ConflictPolicy;
+use crate::sync::delete::{DeleteOptions, delete_entries, extraneous_entries, planned_entries};
+use crate::sync::metadata::{PreserveOptions, apply_metadata};
+use crate::sync::plan::{PLAN_FILE_NAME, PlanAction, PlanEntry, SyncPlan, plan_entry};
+use crate::sync::pool::{CopyOptions, CopyPool};
+use crate::sync::section::{CHECKPOINT_DIR_NAME, SectionOptions};
+use crate::throttle::Throttle;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::time::Instant;
 use storage::Storage;
-use storage::create_storage;
-use storage::systemtime_to_seconds_nanos;
+use storage::common::StorageEntry;
+use storage::{StorageType, create_storage};
 use tokio::sync::mpsc;
 use tokio::time;
-use utils::app_config::AppConfig;
-use utils::error::Result;
+use utils::app_config::{AppConfig, MigrateConfig};
+use utils::error::{Error, Result};
 
 /// 扫描参数结构体 - 来自CLI的输入参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +44,34 @@ pub struct SyncParams {
 
     /// 检查sum
     pub enable_md5: bool,
+
+    /// 启用校验时使用的哈希算法
+    #[serde(default)]
+    pub checksum_algorithm: ChecksumAlgorithm,
+
+    /// 大小一致时比较文件内容决定是否跳过，而不是比较修改时间
+    #[serde(default)]
+    pub checksum: bool,
+
+    /// 需要保留的元数据
+    #[serde(default)]
+    pub preserve: PreserveOptions,
+
+    /// 删除目标中源不存在的条目
+    #[serde(default)]
+    pub delete: DeleteOptions,
+
+    /// 目标已存在时的处理策略，为空时使用配置文件中的策略
+    #[serde(default)]
+    pub conflict: Option<ConflictPolicy>,
+
+    /// 只比较不写入，将变更计划写入作业目录
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// 执行演练模式生成的计划文件
+    #[serde(default)]
+    pub apply_plan: Option<String>,
 }
 
 impl Default for SyncParams {
@@ -40,6 +81,13 @@ impl Default for SyncParams {
             src_path: String::from("."),
             dest_path: String::from("."),
             enable_md5: false,
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            checksum: false,
+            preserve: PreserveOptions::default(),
+            delete: DeleteOptions::default(),
+            conflict: None,
+            dry_run: false,
+            apply_plan: None,
             scan_params: ScanParams::default(),
         }
     }
@@ -54,28 +102,63 @@ pub struct SyncConfig {
 }
 
 /// 主扫描函数 - 入口点
-pub async fn sync(params: SyncParams) -> Result<()> {
+pub async fn sync(mut params: SyncParams) -> Result<()> {
     log::info!("Starting sync with params: {:?}", params);
 
+    if params.dry_run {
+        return dry_run(params).await;
+    }
+
+    let app_config = AppConfig::fetch().map_err(|e| {
+        utils::error::Error::with_source("Failed to load application configuration", Box::new(e))
+    })?;
+    let throttle = Throttle::from_config(&app_config.throttle)?;
+
+    // 按计划执行时使用生成计划时的过滤条件和保留选项，删除的条目也以计划为准
+    let plan = match &params.apply_plan {
+        Some(path) => {
+            let plan = load_plan(Path::new(path), &params, &throttle).await?;
+            params.scan_params.match_expressions = plan.scan_params.match_expressions.clone();
+            params.scan_params.exclude_expressions = plan.scan_params.exclude_expressions.clone();
+            params.scan_params.depth = plan.scan_params.depth;
+            params.preserve = plan.preserve;
+            params.conflict = Some(plan.conflict);
+            params.delete = DeleteOptions::default();
+            Some(plan)
+        }
+        None => None,
+    };
+    let planned_files = plan.as_ref().map(|plan| {
+        plan.paths(&[
+            PlanAction::Create,
+            PlanAction::Update,
+            PlanAction::Metadata,
+            PlanAction::Rename,
+            PlanAction::Conflict,
+        ])
+    });
+
     let scan_config = ScanConfig {
         params: params.scan_params.clone(),
         expressions: parse_expressions(&params.scan_params.match_expressions)?,
         exclude_expressions: parse_expressions(&params.scan_params.exclude_expressions)?,
     };
 
-    let app_config = AppConfig::fetch().map_err(|e| {
-        utils::error::Error::with_source("Failed to load application configuration", Box::new(e))
-    })?;
-
+    let job_id = params.id.clone().unwrap_or_else(|| "unknown".to_string());
+    let sync_job_dir = job_dir("sync", &job_id);
     let consumer_config = ConsumerConfig {
         app_config: app_config.clone(),
         scan_config: scan_config.clone(),
-        job_id: params.id.clone().unwrap_or_else(|| "unknown".to_string()),
+        job_dir: sync_job_dir.clone(),
+        job_id,
     };
 
     // 创建消费者管理器（使用默认配置）
-    let mut consumer_manager =
-        ConsumerManager::new(app_config.database.enabled, app_config.kafka.enabled);
+    let mut consumer_manager = ConsumerManager::new(
+        app_config.database.enabled,
+        app_config.kafka.enabled,
+        app_config.webhook.enabled,
+    );
 
     // 启动所有消费者
     let consumer_handles = consumer_manager.start_consumers().await?;
@@ -95,91 +178,162 @@ pub async fn sync(params: SyncParams) -> Result<()> {
     time::sleep(Duration::from_secs(2)).await;
 
     // 启动walkdir任务（仅生成ScanResults）
-    let walkdir_handle = tokio::spawn(async move { walkdir(scan_config, tx).await });
+    let walkdir_config = scan_config.clone();
+    let walkdir_ops = throttle.ops.clone();
+    let mut walkdir_handle =
+        tokio::spawn(async move { walkdir(walkdir_config, tx, walkdir_ops).await });
 
     // 1 根据传入的src_path 创建storage
     let src_storage = create_storage(&params.src_path)?;
     // 2 根据传入的dest_path 创建storage
-    let dest_storage = create_storage(&params.dest_path)?;
+    let dest_storage = create_storage(&params.dest_path)?.with_ops_limiter(throttle.ops.clone());
 
     let mut last_progress_time = Instant::now();
 
-    let mut total_files = 0;
+    let compare_options = CompareOptions {
+        policy: conflict_policy(&params, &app_config.migrate)?,
+        checksum: params.checksum,
+    };
+    let copy_options = CopyOptions {
+        compare: compare_options,
+        // 启用校验时复制过程中计算源文件哈希，并回读目标文件验证
+        verify: params.enable_md5.then_some(params.checksum_algorithm),
+        preserve: params.preserve,
+        // 大文件的断点保存在作业目录中，使用相同的作业ID重新运行时继续传输
+        sections: Some(SectionOptions {
+            threshold: app_config.migrate.section_threshold,
+            section_size: app_config.migrate.section_size.max(1),
+            concurrency: app_config.migrate.section_concurrency as usize,
+            checkpoint_dir: sync_job_dir.join(CHECKPOINT_DIR_NAME),
+        }),
+        throttle: throttle.clone(),
+        fsync: app_config.migrate.fsync,
+    };
+    let pool = CopyPool::new(
+        app_config.migrate.concurrency,
+        app_config.migrate.max_inflight_bytes,
+        copy_options,
+        broadcaster.clone(),
+    );
+    let local = src_storage.is_local() && dest_storage.is_local();
+    // 在开始写入前清理上次运行中断留下的临时文件
+    if local {
+        let removed = remove_orphans(&dest_storage, &sync_job_dir.join(CHECKPOINT_DIR_NAME)).await;
+        if removed > 0 {
+            println!("Removed {} orphaned temporary files", removed);
+        }
+    }
+    // 已创建的目标目录，所有文件复制完成后统一恢复元数据
+    let mut dirs = Vec::new();
+    // 源中所有条目的相对路径，用于找出目标中多余的条目
+    let mut source_paths = HashSet::new();
+    // 只有完整遍历了源才能安全删除
+    let mut scan_complete = false;
 
     loop {
         match rx.recv().await {
             Some(ScanMessage::Result(entity)) => {
-                if let Err(e) = broadcaster.send(ScanMessage::Result(entity.clone())) {
-                    log::error!("Failed to broadcast scan result: {}", e);
+                // 按计划执行时只处理计划中的文件，目录仍全部处理以便最后恢复其元数据
+                if let Some(planned_files) = &planned_files
+                    && !entity.is_dir
+                    && !planned_files.contains(&entity.relative_path)
+                {
+                    continue;
+                }
+                if params.delete.enabled {
+                    source_paths.insert(entity.relative_path.clone());
                 }
+                if local && !entity.relative_path.is_empty() {
+                    let dest_path = format!("{}/{}", dest_storage.get_root(), entity.relative_path);
+                    let dest_path = PathBuf::from(dest_path);
 
-                if src_storage.is_local() && dest_storage.is_local() {
-                    if !entity.relative_path.is_empty() && !entity.is_dir {
-                        let dest_path =
-                            format!("{}/{}", dest_storage.get_root(), entity.relative_path);
-                        let dest_path = PathBuf::from(dest_path);
-                        if let Some(parent_dir) = dest_path.parent() {
-                            if let Err(e) = tokio::fs::create_dir_all(parent_dir).await {
-                                eprintln!("Failed to create directory: {}", e);
-                                continue;
+                    if entity.is_dir {
+                        // 按扫描顺序创建目录，保证worker写入文件前父目录已存在
+                        throttle.acquire_ops(1).await;
+                        match tokio::fs::create_dir_all(&dest_path).await {
+                            Ok(()) => dirs.push((dest_path, entity.clone())),
+                            Err(e) => {
+                                eprintln!(
+                                    "Failed to create directory {}: {}",
+                                    dest_path.display(),
+                                    e
+                                );
                             }
                         }
-
-                        if let Err(e) = tokio::fs::copy(&entity.file_path, &dest_path).await {
-                            eprintln!("Failed to copy file: {}", e);
+                    } else {
+                        // 文件交给worker复制，复制完成后由worker广播
+                        if let Err(e) = pool.submit(entity, dest_path).await {
+                            log::error!("Sync failed: {}", e);
+                            let _ = broadcaster.send(ScanMessage::Failed(e.to_string()));
+                            break;
                         }
-
-                        tokio::task::spawn_blocking(move || {
-                            // 使用标准库的 set_file_times 函数（注意：此函数在 std::fs 中实际并不存在，仅为示例逻辑）
-                            // 实际标准库中需要通过 File::set_times 或其他方式，但标准库当前（Rust 1.xx）并未提供直接设置 mtime 的函数。
-                            // 更常见的做法是使用第三方库，如 `filetime`。
-                            let (secs, nsecs) = systemtime_to_seconds_nanos(entity.mtime);
-                            filetime::set_file_mtime(
-                                &dest_path,
-                                filetime::FileTime::from_unix_time(secs, nsecs),
-                            )
-                            .expect("Failed to set mtime");
-                        })
-                        .await
-                        .expect("spawn_blocking task failed");
-                        total_files += 1;
-                    };
-                    // 每10秒打印一次进度
-                    if last_progress_time.elapsed().as_secs() >= 10 {
-                        let now = chrono::Local::now();
-                        println!(
-                            "[{}] Sync progress: {} total files",
-                            now.format("%Y-%m-%d %H:%M:%S"),
-                            total_files,
-                        );
-                        last_progress_time = Instant::now();
+                        print_progress(&pool, &mut last_progress_time);
+                        continue;
                     }
                 }
-                // 3. 从src_storage读取文件内容
-                // 4 写入dest_storage
-                // 5. 将_result写入CH数据库
-                // 6. broadcast _result 给消费者
 
-                // 检查是否都是本地文件存储
+                if let Err(e) = broadcaster.send(ScanMessage::Result(entity)) {
+                    log::error!("Failed to broadcast scan result: {}", e);
+                }
+                if local {
+                    print_progress(&pool, &mut last_progress_time);
+                }
             }
             Some(ScanMessage::Complete) => {
-                let _ = broadcaster.send(ScanMessage::Complete);
+                scan_complete = true;
                 break;
             }
-            Some(ScanMessage::Config(_)) => {
+            Some(ScanMessage::Config(_)) | Some(ScanMessage::Failed(_)) => {
                 // 忽略配置消息，已在前面的步骤处理
             }
             None => {
-                let _ = broadcaster.send(ScanMessage::Complete);
+                // 通道提前关闭说明遍历失败，先通知失败原因，完成消息在复制结束后广播
+                if let Ok(Err(e)) = (&mut walkdir_handle).await {
+                    log::error!("Sync failed: {}", e);
+                    let _ = broadcaster.send(ScanMessage::Failed(e.to_string()));
+                }
                 break;
             }
         }
     }
 
+    // 提前退出时关闭队列，使walkdir任务不再阻塞在发送上
+    drop(rx);
+
+    // 等待已提交的文件复制完成，再恢复目录元数据并通知消费者
+    let mut summary = pool.finish().await;
+    if let Some(plan) = &plan {
+        let entries = planned_entries(&dest_storage, &plan.paths(&[PlanAction::Delete])).await;
+        let deleted = delete_entries(&entries, &throttle).await;
+        summary.deleted += deleted.deleted;
+        summary.failed += deleted.failed;
+    } else if params.delete.enabled {
+        if !scan_complete {
+            println!("Source scan did not complete, skipping deletion");
+        } else if !local {
+            println!("Deletion is only supported between local storages, skipping");
+        } else {
+            delete_extraneous(
+                &dest_storage,
+                &source_paths,
+                &scan_config,
+                params.delete,
+                compare_options.policy,
+                &throttle,
+                &mut summary,
+            )
+            .await;
+        }
+    }
+    finalize_dirs(dirs, params.preserve).await;
+    let _ = broadcaster.send(ScanMessage::Complete);
+
     // 等待walkdir任务完成
-    let _ = walkdir_handle
-        .await
-        .map_err(|e| utils::error::Error::with_source("Walkdir task failed", Box::new(e)))?;
+    if !walkdir_handle.is_finished() {
+        let _ = walkdir_handle
+            .await
+            .map_err(|e| utils::error::Error::with_source("Walkdir task failed", Box::new(e)))?;
+    }
 
     // 等待所有消费者完成
     for handle in consumer_handles {
@@ -189,5 +343,217 @@ pub async fn sync(params: SyncParams) -> Result<()> {
     // 关闭消费者管理器
     consumer_manager.shutdown().await?;
 
+    log::info!("Sync completed: {}", summary);
+    println!("Sync completed: {}", summary);
+
+    Ok(())
+}
+
+/// 作业的冲突策略：命令行参数优先，其次是 `migrate.conflict_policy`，未配置时兼容 `migrate.overwrite`
+/// `overwrite = false` 使用默认的update-if-different，而不是跳过已存在的文件
+fn conflict_policy(params: &SyncParams, config: &MigrateConfig) -> Result<ConflictPolicy> {
+    if let Some(policy) = params.conflict {
+        return Ok(policy);
+    }
+    match &config.conflict_policy {
+        Some(policy) if !policy.is_empty() => policy.parse(),
+        _ if config.overwrite => Ok(ConflictPolicy::Overwrite),
+        _ => Ok(ConflictPolicy::default()),
+    }
+}
+
+/// 每10秒打印一次总进度和各worker的进度
+fn print_progress(pool: &CopyPool, last_progress_time: &mut Instant) {
+    if last_progress_time.elapsed().as_secs() < 10 {
+        return;
+    }
+    let now = chrono::Local::now();
+    println!(
+        "[{}] Sync progress: {} (workers: {})",
+        now.format("%Y-%m-%d %H:%M:%S"),
+        pool.summary(),
+        pool.worker_progress(),
+    );
+    *last_progress_time = Instant::now();
+}
+
+/// 删除目标中多余的条目，数量超过上限时不删除任何条目
+/// rename-existing策略下保留源中仍存在的文件的重命名备份
+async fn delete_extraneous(
+    dest: &StorageType, source_paths: &HashSet<String>, config: &ScanConfig,
+    options: DeleteOptions, policy: ConflictPolicy, throttle: &Throttle, summary: &mut SyncSummary,
+) {
+    let keep_backups = policy == ConflictPolicy::RenameExisting;
+    let entries = extraneous_entries(
+        dest,
+        source_paths,
+        config,
+        options.delete_excluded,
+        keep_backups,
+    )
+    .await;
+    if exceeds_max_delete(&entries, options) {
+        return;
+    }
+
+    let deleted = delete_entries(&entries, throttle).await;
+    summary.deleted += deleted.deleted;
+    summary.failed += deleted.failed;
+}
+
+/// 多余条目超过 `--max-delete` 时输出提示并返回true
+fn exceeds_max_delete(entries: &[StorageEntry], options: DeleteOptions) -> bool {
+    match options.max_delete {
+        Some(max_delete) if entries.len() as u64 > max_delete => {
+            log::warn!(
+                "{} extraneous entries exceed --max-delete {}, skipping deletion",
+                entries.len(),
+                max_delete
+            );
+            println!(
+                "Refusing to delete {} entries, more than --max-delete {}",
+                entries.len(),
+                max_delete
+            );
+            true
+        }
+        _ => false,
+    }
+}
+
+/// 演练模式：完整比较源和目标但不写入，将变更计划写入作业目录
+async fn dry_run(params: SyncParams) -> Result<()> {
+    let scan_config = ScanConfig {
+        params: params.scan_params.clone(),
+        expressions: parse_expressions(&params.scan_params.match_expressions)?,
+        exclude_expressions: parse_expressions(&params.scan_params.exclude_expressions)?,
+    };
+    let app_config = AppConfig::fetch()
+        .map_err(|e| Error::with_source("Failed to load application configuration", Box::new(e)))?;
+    let throttle = Throttle::from_config(&app_config.throttle)?;
+
+    let src_storage = create_storage(&params.src_path)?;
+    let dest_storage = create_storage(&params.dest_path)?.with_ops_limiter(throttle.ops.clone());
+    if !(src_storage.is_local() && dest_storage.is_local()) {
+        return Err(Error::new(
+            "Dry run is only supported between local storages",
+        ));
+    }
+
+    let compare_options = CompareOptions {
+        policy: conflict_policy(&params, &app_config.migrate)?,
+        checksum: params.checksum,
+    };
+    let mut plan = SyncPlan::new(
+        &params.src_path,
+        &params.dest_path,
+        params.scan_params.clone(),
+        params.preserve,
+        compare_options.policy,
+    );
+
+    let (tx, mut rx) = mpsc::channel::<ScanMessage>(1000);
+    let walkdir_config = scan_config.clone();
+    let walkdir_ops = throttle.ops.clone();
+    let walkdir_handle =
+        tokio::spawn(async move { walkdir(walkdir_config, tx, walkdir_ops).await });
+
+    let mut source_paths = HashSet::new();
+    while let Some(message) = rx.recv().await {
+        let ScanMessage::Result(entity) = message else {
+            continue;
+        };
+        plan.source.add(&entity);
+        if entity.relative_path.is_empty() {
+            continue;
+        }
+
+        let dest_path = PathBuf::from(format!(
+            "{}/{}",
+            dest_storage.get_root(),
+            entity.relative_path
+        ));
+        throttle.acquire_ops(1).await;
+        if let Some(entry) = plan_entry(&entity, &dest_path, &compare_options, params.preserve)
+            .await
+            .map_err(|e| {
+                Error::with_source(
+                    &format!("Failed to compare {}", dest_path.display()),
+                    Box::new(e),
+                )
+            })?
+        {
+            plan.push(entry);
+        }
+        if params.delete.enabled {
+            source_paths.insert(entity.relative_path);
+        }
+    }
+    // 遍历失败时计划不完整，不写入计划文件
+    walkdir_handle
+        .await
+        .map_err(|e| Error::with_source("Walkdir task failed", Box::new(e)))??;
+
+    if params.delete.enabled {
+        let entries = extraneous_entries(
+            &dest_storage,
+            &source_paths,
+            &scan_config,
+            params.delete.delete_excluded,
+            compare_options.policy == ConflictPolicy::RenameExisting,
+        )
+        .await;
+        if !exceeds_max_delete(&entries, params.delete) {
+            for entry in entries {
+                plan.push(PlanEntry {
+                    action: PlanAction::Delete,
+                    relative_path: entry.relative_path,
+                    is_dir: entry.is_dir,
+                    size: if entry.is_dir { 0 } else { entry.size },
+                });
+            }
+        }
+    }
+
+    let job_id = params.id.clone().unwrap_or_else(|| "unknown".to_string());
+    let plan_path = job_dir("sync", &job_id).join(PLAN_FILE_NAME);
+    plan.save(&plan_path)?;
+
+    log::info!("Sync plan: {}", plan.summary);
+    println!("Sync plan: {}", plan.summary);
+    println!("Plan written to {}", plan_path.display());
     Ok(())
 }
+
+/// 读取计划文件并确认源在生成计划后没有变化
+async fn load_plan(path: &Path, params: &SyncParams, throttle: &Throttle) -> Result<SyncPlan> {
+    let plan = SyncPlan::load(path)?;
+    if plan.src_path != params.src_path || plan.dest_path != params.dest_path {
+        return Err(Error::new(&format!(
+            "Plan {} was created for {} -> {}, not {} -> {}",
+            path.display(),
+            plan.src_path,
+            plan.dest_path,
+            params.src_path,
+            params.dest_path
+        )));
+    }
+
+    plan.verify_source(throttle.ops.clone()).await?;
+    println!(
+        "Source unchanged since {}, applying plan: {}",
+        plan.created_at, plan.summary
+    );
+    Ok(plan)
+}
+
+/// 从深到浅恢复目录元数据，避免子目录的写入再次改变父目录的修改时间
+/// 权限也在最后设置，只读目录不会阻止其中文件的写入
+async fn finalize_dirs(mut dirs: Vec<(PathBuf, StorageEntity)>, preserve: PreserveOptions) {
+    dirs.sort_by_key(|(path, _)| std::cmp::Reverse(path.components().count()));
+    for (path, entity) in dirs {
+        if let Err(e) = apply_metadata(&entity, &path, preserve).await {
+            log::warn!("Failed to set metadata of {}: {}", path.display(), e);
+        }
+    }
+}
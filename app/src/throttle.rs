@@ -0,0 +1,111 @@
+use chrono::{NaiveTime, Timelike};
+use std::sync::Arc;
+use storage::throttle::{RateLimiter, RateWindow};
+use utils::app_config::ThrottleConfig;
+use utils::error::{Error, Result};
+
+/// 作业级限速器，同一作业的扫描、复制和删除共享额度
+#[derive(Debug, Clone, Default)]
+pub struct Throttle {
+    /// 同步数据传输的字节限速
+    pub bytes: Option<Arc<RateLimiter>>,
+    /// 元数据操作限速
+    pub ops: Option<Arc<RateLimiter>>,
+}
+
+impl Throttle {
+    pub fn from_config(config: &ThrottleConfig) -> Result<Self> {
+        let mut bytes_windows = Vec::with_capacity(config.schedule.len());
+        let mut ops_windows = Vec::with_capacity(config.schedule.len());
+        for window in &config.schedule {
+            let start = parse_time(&window.start)?;
+            let end = parse_time(&window.end)?;
+            bytes_windows.push(RateWindow {
+                start,
+                end,
+                rate: window.bytes_per_sec.unwrap_or(config.bytes_per_sec),
+            });
+            ops_windows.push(RateWindow {
+                start,
+                end,
+                rate: window.ops_per_sec.unwrap_or(config.ops_per_sec),
+            });
+        }
+
+        Ok(Self {
+            bytes: limiter(config.bytes_per_sec, bytes_windows),
+            ops: limiter(config.ops_per_sec, ops_windows),
+        })
+    }
+
+    /// 等待 `amount` 个元数据操作的额度
+    pub async fn acquire_ops(&self, amount: u64) {
+        if let Some(limiter) = &self.ops {
+            limiter.acquire(amount).await;
+        }
+    }
+}
+
+/// 任何时间都不限速时不创建限速器
+fn limiter(rate: u64, windows: Vec<RateWindow>) -> Option<Arc<RateLimiter>> {
+    if rate == 0 && windows.iter().all(|window| window.rate == 0) {
+        return None;
+    }
+    Some(Arc::new(RateLimiter::new(rate, windows)))
+}
+
+/// 解析 `HH:MM` 格式的时间，返回当天零点起的秒数
+fn parse_time(value: &str) -> Result<u32> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map(|time| time.num_seconds_from_midnight())
+        .map_err(|e| {
+            Error::with_source(
+                &format!("Invalid throttle schedule time: {}", value),
+                Box::new(e),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::app_config::ThrottleWindow;
+
+    #[test]
+    fn test_throttle_from_config() {
+        assert!(
+            Throttle::from_config(&ThrottleConfig::default())
+                .unwrap()
+                .bytes
+                .is_none()
+        );
+
+        let config = ThrottleConfig {
+            bytes_per_sec: 0,
+            ops_per_sec: 200,
+            schedule: vec![ThrottleWindow {
+                start: "08:00".to_string(),
+                end: "18:30".to_string(),
+                bytes_per_sec: Some(1024),
+                ops_per_sec: None,
+            }],
+        };
+        let throttle = Throttle::from_config(&config).unwrap();
+        let bytes = throttle.bytes.unwrap();
+        assert_eq!(bytes.rate_at(18 * 3600 + 29 * 60), 1024);
+        assert_eq!(bytes.rate_at(18 * 3600 + 30 * 60), 0);
+        // 时间段未设置操作限速时沿用默认值
+        assert_eq!(throttle.ops.unwrap().rate_at(9 * 3600), 200);
+
+        let invalid = ThrottleConfig {
+            schedule: vec![ThrottleWindow {
+                start: "25:00".to_string(),
+                end: "06:00".to_string(),
+                bytes_per_sec: None,
+                ops_per_sec: None,
+            }],
+            ..config
+        };
+        assert!(Throttle::from_config(&invalid).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use app::scan::ScanParams;
+use app::sync::{ConflictPolicy, DeleteOptions, SyncParams, sync};
+use std::fs;
+use std::path::Path;
+use utils::app_config::AppConfig;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 目标目录中除指定文件外的其他文件名
+    fn other_files(dir: &Path, name: &str) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|file_name| file_name != name)
+            .collect()
+    }
+
+    /// 测试镜像同步不会删除rename-existing保留的旧版本
+    #[tokio::test]
+    async fn test_delete_keeps_renamed_backups() {
+        // 作业目录创建在当前目录下
+        let work = tempfile::tempdir().unwrap();
+        std::env::set_current_dir(work.path()).unwrap();
+        let config = include_str!("../../src/resources/default_config.toml").replace(
+            "enabled = true           # Enable Database integration",
+            "enabled = false",
+        );
+        AppConfig::init(Some(&config)).unwrap();
+
+        let src = work.path().join("src");
+        let dest = work.path().join("dest");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(src.join("report.txt"), "new content").unwrap();
+        fs::write(dest.join("report.txt"), "old").unwrap();
+        fs::write(dest.join("stale.txt"), "stale").unwrap();
+
+        let src_path = src.to_string_lossy().to_string();
+        let params = SyncParams {
+            id: Some("backup".to_string()),
+            scan_params: ScanParams {
+                id: Some("backup".to_string()),
+                path: src_path.clone(),
+                ..ScanParams::default()
+            },
+            src_path,
+            dest_path: dest.to_string_lossy().to_string(),
+            delete: DeleteOptions {
+                enabled: true,
+                ..DeleteOptions::default()
+            },
+            conflict: Some(ConflictPolicy::RenameExisting),
+            ..SyncParams::default()
+        };
+        sync(params.clone()).await.unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dest.join("report.txt")).unwrap(),
+            "new content"
+        );
+        let backups = other_files(&dest, "report.txt");
+        assert_eq!(backups.len(), 1, "unexpected files: {:?}", backups);
+        assert!(backups[0].starts_with("report.txt."));
+        assert_eq!(fs::read_to_string(dest.join(&backups[0])).unwrap(), "old");
+
+        // 再次同步时之前运行保留的备份同样不会被删除
+        sync(params).await.unwrap();
+        assert_eq!(other_files(&dest, "report.txt"), backups);
+    }
+}
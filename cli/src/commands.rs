@@ -1,6 +1,9 @@
 use crate::sanitize_job_id;
+use app::database::{migrate, replay};
+use app::job::{JOBS_DIR, job_dir};
+use app::report::{dir_tree, format_tree};
 use app::scan::{ScanParams, ScanType, scan};
-use app::sync::{SyncParams, sync};
+use app::sync::{DeleteOptions, PreserveOptions, SyncParams, sync};
 use chrono::Local;
 use log::info;
 use std::fs;
@@ -9,36 +12,36 @@ use std::path::Path;
 /// 准备job目录和ID
 fn prepare_job(job_type: &str, id: Option<String>) -> utils::error::Result<(String, bool)> {
     // 创建jobs目录（如果不存在）
-    let jobs_dir = "jobs";
-    if !Path::new(jobs_dir).exists() {
-        fs::create_dir_all(jobs_dir)?;
+    if !Path::new(JOBS_DIR).exists() {
+        fs::create_dir_all(JOBS_DIR)?;
     }
 
     // 生成或处理job ID
-    let job_id = id.unwrap_or_else(|| {
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-        timestamp
-    });
+    let job_id = id.unwrap_or_else(|| Local::now().format("%Y%m%d_%H%M%S").to_string());
     let job_id = sanitize_job_id(&job_id);
 
     // 构建job目录路径
-    let job_dir = format!("{}/{}_{}", jobs_dir, job_type, job_id);
-    let job_path_exists = Path::new(&job_dir).exists();
+    let job_dir = job_dir(job_type, &job_id);
+    let job_path_exists = job_dir.exists();
 
     // 如果是全量操作，创建目录
     if !job_path_exists {
         fs::create_dir_all(&job_dir)?;
         info!(
             "Created {} directory for full {}: {}",
-            job_type, job_type, job_dir
+            job_type,
+            job_type,
+            job_dir.display()
         );
     }
 
     Ok((job_id, job_path_exists))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn scan_cmd(
     id: Option<String>, depth: u32, path: String, r#match: Vec<String>, exclude: Vec<String>,
+    output: Option<String>, output_format: Option<String>, columns: Vec<String>,
 ) -> utils::error::Result<()> {
     let (job_id, job_path_exists) = prepare_job("scan", id)?;
 
@@ -56,14 +59,20 @@ pub async fn scan_cmd(
         path,
         match_expressions: r#match,
         exclude_expressions: exclude,
+        output,
+        output_format: output_format.map(|f| f.parse()).transpose()?,
+        columns,
     };
 
     scan(params).await?;
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn sync_cmd(
     id: Option<String>, src_path: String, dest_path: String, enable_md5: bool,
+    checksum_algorithm: Option<String>, checksum: bool, preserve: PreserveOptions,
+    delete: DeleteOptions, conflict: Option<String>, dry_run: bool, apply_plan: Option<String>,
     r#match: Vec<String>, exclude: Vec<String>,
 ) -> utils::error::Result<()> {
     let (job_id, job_path_exists) = prepare_job("sync", id)?;
@@ -84,12 +93,103 @@ pub async fn sync_cmd(
             path: src_path.clone(),
             match_expressions: r#match,
             exclude_expressions: exclude,
+            ..ScanParams::default()
         },
         src_path,
         dest_path,
         enable_md5,
+        checksum_algorithm: checksum_algorithm
+            .map(|a| a.parse())
+            .transpose()?
+            .unwrap_or_default(),
+        checksum,
+        preserve,
+        delete,
+        conflict: conflict.map(|c| c.parse()).transpose()?,
+        dry_run,
+        apply_plan,
     };
 
     sync(params).await?;
     Ok(())
 }
+
+pub async fn db_migrate_cmd(apply: bool) -> utils::error::Result<()> {
+    let statuses = migrate(apply).await?;
+
+    if statuses.is_empty() {
+        println!("No job tables found");
+        return Ok(());
+    }
+
+    for status in &statuses {
+        let pending = if status.pending.is_empty() {
+            "up to date".to_string()
+        } else {
+            format!(
+                "pending: {}",
+                status
+                    .pending
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        println!(
+            "{}  version {}  {}",
+            status.table_name, status.current_version, pending
+        );
+    }
+
+    let pending_tables = statuses.iter().filter(|s| !s.pending.is_empty()).count();
+    if pending_tables > 0 {
+        println!(
+            "{} table(s) have pending migrations, run `db migrate --apply` to apply them",
+            pending_tables
+        );
+    }
+    Ok(())
+}
+
+pub async fn db_replay_cmd(id: Option<String>) -> utils::error::Result<()> {
+    let id = id.map(|id| sanitize_job_id(&id));
+    let reports = replay(id.as_deref()).await?;
+
+    if reports.is_empty() {
+        println!("No spooled records found");
+        return Ok(());
+    }
+
+    for report in &reports {
+        println!(
+            "{}  replayed {}  remaining {}  ({})",
+            report.job_id,
+            report.replayed,
+            report.remaining,
+            report.spool_path.display()
+        );
+    }
+
+    let remaining: usize = reports.iter().map(|r| r.remaining).sum();
+    if remaining > 0 {
+        return Err(utils::error::Error::new(&format!(
+            "{} records could not be replayed and remain spooled",
+            remaining
+        )));
+    }
+    Ok(())
+}
+
+pub async fn report_tree_cmd(id: String, depth: Option<u32>) -> utils::error::Result<()> {
+    let id = sanitize_job_id(&id);
+    let rollups = dir_tree(&id, depth).await?;
+
+    if rollups.is_empty() {
+        println!("No directory rollups found for job {}", id);
+        return Ok(());
+    }
+
+    print!("{}", format_tree(&rollups));
+    Ok(())
+}
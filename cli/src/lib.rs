@@ -1,3 +1,4 @@
+use app::sync::{DeleteOptions, PreserveOptions};
 use clap::{Parser, Subcommand};
 
 mod commands;
@@ -32,6 +33,62 @@ pub enum Commands {
         #[arg(long, default_value_t = false)]
         enable_md5: bool,
 
+        /// Hash algorithm used by --enable-md5 (default: md5)
+        #[arg(long, value_name = "ALGORITHM", value_parser = ["md5", "xxh3", "blake3"], requires = "enable_md5")]
+        checksum_algorithm: Option<String>,
+
+        /// Skip files whose content matches the destination instead of comparing size and mtime
+        #[arg(short, long, default_value_t = false)]
+        checksum: bool,
+
+        /// Archive mode, same as --links --perms --owner --group (modification times are always kept)
+        #[arg(short, long, default_value_t = false)]
+        archive: bool,
+
+        /// Recreate symlinks as symlinks instead of copying their targets
+        #[arg(long, default_value_t = false)]
+        links: bool,
+
+        /// Preserve permission bits
+        #[arg(long, default_value_t = false)]
+        perms: bool,
+
+        /// Preserve the file owner (requires root privileges)
+        #[arg(long, default_value_t = false)]
+        owner: bool,
+
+        /// Preserve the file group
+        #[arg(long, default_value_t = false)]
+        group: bool,
+
+        /// Preserve access times
+        #[arg(long, default_value_t = false)]
+        atimes: bool,
+
+        /// Delete destination entries that do not exist in the source
+        #[arg(long, default_value_t = false)]
+        delete: bool,
+
+        /// Also delete destination entries filtered out by --match/--exclude (implies --delete)
+        #[arg(long, default_value_t = false)]
+        delete_excluded: bool,
+
+        /// Do not delete anything if more than N entries would be deleted
+        #[arg(long, value_name = "N")]
+        max_delete: Option<u64>,
+
+        /// How to handle destination files that already exist and differ (default: migrate.conflict_policy)
+        #[arg(long, value_name = "POLICY", value_parser = ["skip", "overwrite", "update-if-newer", "update-if-different", "rename-existing", "fail"])]
+        conflict: Option<String>,
+
+        /// Compare source and destination without writing and save a change plan to the job directory
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Execute a plan produced by --dry-run, refusing it if the source changed since
+        #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+        apply_plan: Option<String>,
+
         /// Filter expression to match files/directories
         /// Examples: 'modified<0.5 and "ntap" in name and type==file'
         #[arg(short, long, value_name = "EXPRESSION")]
@@ -65,18 +122,74 @@ pub enum Commands {
         /// Examples: 'name=="target" or name==".git"'
         #[arg(short, long, value_name = "EXPRESSION")]
         exclude: Vec<String>,
+
+        /// Export scan results to this file
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+
+        /// Export format, inferred from the output file extension when omitted
+        #[arg(long, value_name = "FORMAT", value_parser = ["csv", "ndjson", "parquet"], requires = "output")]
+        output_format: Option<String>,
+
+        /// Columns to export, comma separated (default: all columns)
+        /// Examples: 'path,size,mtime'
+        #[arg(
+            long,
+            value_name = "COLUMNS",
+            value_delimiter = ',',
+            requires = "output"
+        )]
+        columns: Vec<String>,
+    },
+
+    /// Manage the scan result database
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+
+    /// Report on stored scan results
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommands {
+    /// Report pending schema migrations for job tables
+    Migrate {
+        /// Apply pending migrations instead of only reporting them
+        #[arg(long, default_value_t = false)]
+        apply: bool,
+    },
+
+    /// Re-insert records spooled after failed database writes
+    Replay {
+        /// Only replay the spool of this job
+        #[arg(short, long)]
+        id: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommands {
+    /// Print per-directory size and file counts (du-style) of a scan
+    Tree {
+        /// Scan ID to report on
+        #[arg(short, long)]
+        id: String,
+
+        /// Maximum directory depth below the scan root (default: all)
+        #[arg(short, long)]
+        depth: Option<u32>,
     },
 }
 
 /// 将作业ID转换为文件系统安全的标识符
 /// 将特殊字符转换为下划线，确保可用于目录和文件名
 pub fn sanitize_job_id(job_id: &str) -> String {
-    job_id
-        .replace('-', "_")
-        .replace('.', "_")
-        .replace(' ', "_")
-        .replace('/', "_")
-        .replace('\\', "_")
+    job_id.replace(['-', '.', ' ', '/', '\\'], "_")
 }
 
 pub async fn cli_match() -> utils::error::Result<()> {
@@ -90,6 +203,9 @@ pub async fn cli_match() -> utils::error::Result<()> {
             path,
             r#match,
             exclude,
+            output,
+            output_format,
+            columns,
         } => {
             commands::scan_cmd(
                 id.clone(),
@@ -97,6 +213,9 @@ pub async fn cli_match() -> utils::error::Result<()> {
                 path.clone(),
                 r#match.clone(),
                 exclude.clone(),
+                output.clone(),
+                output_format.clone(),
+                columns.clone(),
             )
             .await?
         }
@@ -105,19 +224,66 @@ pub async fn cli_match() -> utils::error::Result<()> {
             src_path,
             dest_path,
             enable_md5,
+            checksum_algorithm,
+            checksum,
+            archive,
+            links,
+            perms,
+            owner,
+            group,
+            atimes,
+            delete,
+            delete_excluded,
+            max_delete,
+            conflict,
+            dry_run,
+            apply_plan,
             r#match,
             exclude,
         } => {
+            let archive = if *archive {
+                PreserveOptions::archive()
+            } else {
+                PreserveOptions::default()
+            };
+            let preserve = PreserveOptions {
+                links: archive.links || *links,
+                perms: archive.perms || *perms,
+                owner: archive.owner || *owner,
+                group: archive.group || *group,
+                atimes: archive.atimes || *atimes,
+            };
+            let delete = DeleteOptions {
+                enabled: *delete || *delete_excluded,
+                delete_excluded: *delete_excluded,
+                max_delete: *max_delete,
+            };
             commands::sync_cmd(
                 id.clone(),
                 src_path.clone(),
                 dest_path.clone(),
-                enable_md5.clone(),
+                *enable_md5,
+                checksum_algorithm.clone(),
+                *checksum,
+                preserve,
+                delete,
+                conflict.clone(),
+                *dry_run,
+                apply_plan.clone(),
                 r#match.clone(),
                 exclude.clone(),
             )
             .await?
         }
+        Commands::Db { command } => match command {
+            DbCommands::Migrate { apply } => commands::db_migrate_cmd(*apply).await?,
+            DbCommands::Replay { id } => commands::db_replay_cmd(id.clone()).await?,
+        },
+        Commands::Report { command } => match command {
+            ReportCommands::Tree { id, depth } => {
+                commands::report_tree_cmd(id.clone(), *depth).await?
+            }
+        },
     }
     Ok(())
 }
@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use slog_scope::debug;
+use std::fmt;
+
+use crate::dialect::Dialect;
+use crate::error::{DatabaseError, Result};
+use crate::traits::Database;
+
+/// 作业目录表：每个作业一行，记录最近一次运行
+pub const JOBS_TABLE: &str = "terrasync_jobs";
+
+/// 运行历史表：每次运行一行
+pub const RUNS_TABLE: &str = "terrasync_runs";
+
+/// 运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunStatus::Running => write!(f, "running"),
+            RunStatus::Completed => write!(f, "completed"),
+            RunStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// 单次作业运行记录，时间均为Unix毫秒时间戳，未结束时finished_at和duration_ms为0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub run_id: String,
+    pub job_id: String,
+    /// 作业类型：scan 或 sync
+    pub job_type: String,
+    /// 运行参数的JSON文本
+    pub params: String,
+    pub status: RunStatus,
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub duration_ms: i64,
+    pub total_files: u64,
+    pub total_dirs: u64,
+    pub total_bytes: u64,
+    pub errors: u64,
+    pub error_message: String,
+}
+
+impl JobRun {
+    /// 创建一条运行中的记录
+    pub fn start(job_id: &str, job_type: &str, params: String) -> Self {
+        Self {
+            run_id: uuid::Uuid::new_v4().to_string(),
+            job_id: job_id.to_string(),
+            job_type: job_type.to_string(),
+            params,
+            status: RunStatus::Running,
+            started_at: now_millis(),
+            finished_at: 0,
+            duration_ms: 0,
+            total_files: 0,
+            total_dirs: 0,
+            total_bytes: 0,
+            errors: 0,
+            error_message: String::new(),
+        }
+    }
+
+    /// 标记运行结束，有错误时状态为失败
+    pub fn finish(&mut self) {
+        self.finished_at = now_millis();
+        self.duration_ms = (self.finished_at - self.started_at).max(0);
+        self.status = if self.errors > 0 {
+            RunStatus::Failed
+        } else {
+            RunStatus::Completed
+        };
+    }
+}
+
+/// 当前Unix毫秒时间戳
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+const RUN_COLUMNS: [&str; 13] = [
+    "run_id",
+    "job_id",
+    "job_type",
+    "params",
+    "status",
+    "started_at",
+    "finished_at",
+    "duration_ms",
+    "total_files",
+    "total_dirs",
+    "total_bytes",
+    "errors",
+    "error_message",
+];
+
+/// 创建作业目录表和运行历史表
+pub async fn ensure_catalog_tables(db: &dyn Database) -> Result<()> {
+    let dialect = Dialect::of(db)?;
+    let statements = match dialect {
+        Dialect::ClickHouse => vec![
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (job_id String, job_type String, params String, \
+                 last_run_id String, last_status String, last_started_at Int64, last_finished_at Int64, \
+                 updated_at Int64) ENGINE = ReplacingMergeTree(updated_at) ORDER BY (job_id)",
+                JOBS_TABLE
+            ),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (run_id String, job_id String, job_type String, params String, \
+                 status String, started_at Int64, finished_at Int64, duration_ms Int64, total_files UInt64, \
+                 total_dirs UInt64, total_bytes UInt64, errors UInt64, error_message String, updated_at Int64) \
+                 ENGINE = ReplacingMergeTree(updated_at) ORDER BY (job_id, run_id)",
+                RUNS_TABLE
+            ),
+        ],
+        Dialect::Sqlite | Dialect::Postgres => {
+            let text = "TEXT NOT NULL";
+            let int = if dialect == Dialect::Postgres {
+                "BIGINT NOT NULL"
+            } else {
+                "INTEGER NOT NULL"
+            };
+            vec![
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {0} (job_id TEXT PRIMARY KEY, job_type {1}, params {1}, \
+                     last_run_id {1}, last_status {1}, last_started_at {2}, last_finished_at {2}, updated_at {2})",
+                    JOBS_TABLE, text, int
+                ),
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {0} (run_id TEXT PRIMARY KEY, job_id {1}, job_type {1}, params {1}, \
+                     status {1}, started_at {2}, finished_at {2}, duration_ms {2}, total_files {2}, total_dirs {2}, \
+                     total_bytes {2}, errors {2}, error_message {1}, updated_at {2})",
+                    RUNS_TABLE, text, int
+                ),
+                format!(
+                    "CREATE INDEX IF NOT EXISTS {0}_job_idx ON {0} (job_id, started_at)",
+                    RUNS_TABLE
+                ),
+            ]
+        }
+    };
+
+    for sql in statements {
+        db.execute(&sql, &[]).await?;
+    }
+    Ok(())
+}
+
+/// 写入完整行，主键冲突时更新其余列
+/// ClickHouse由ReplacingMergeTree按updated_at保留最新行
+fn upsert_sql(dialect: Dialect, table: &str, columns: &[&str], key: &str) -> String {
+    let placeholders = (1..=columns.len())
+        .map(|i| dialect.placeholder(i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let insert = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table,
+        columns.join(", "),
+        placeholders
+    );
+    if dialect == Dialect::ClickHouse {
+        return insert;
+    }
+
+    let updates = columns
+        .iter()
+        .filter(|c| **c != key)
+        .map(|c| format!("{0} = excluded.{0}", c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{} ON CONFLICT ({}) DO UPDATE SET {}", insert, key, updates)
+}
+
+/// 写入运行记录并同步更新作业目录中的最近运行信息
+pub async fn record_run(db: &dyn Database, run: &JobRun) -> Result<()> {
+    let dialect = Dialect::of(db)?;
+    let updated_at = now_millis();
+
+    let mut run_columns = RUN_COLUMNS.to_vec();
+    run_columns.push("updated_at");
+    let run_params = [
+        json!(run.run_id),
+        json!(run.job_id),
+        json!(run.job_type),
+        json!(run.params),
+        json!(run.status.to_string()),
+        json!(run.started_at),
+        json!(run.finished_at),
+        json!(run.duration_ms),
+        json!(run.total_files),
+        json!(run.total_dirs),
+        json!(run.total_bytes),
+        json!(run.errors),
+        json!(run.error_message),
+        json!(updated_at),
+    ];
+    db.execute(
+        &upsert_sql(dialect, RUNS_TABLE, &run_columns, "run_id"),
+        &run_params,
+    )
+    .await?;
+
+    let job_columns = [
+        "job_id",
+        "job_type",
+        "params",
+        "last_run_id",
+        "last_status",
+        "last_started_at",
+        "last_finished_at",
+        "updated_at",
+    ];
+    let job_params = [
+        json!(run.job_id),
+        json!(run.job_type),
+        json!(run.params),
+        json!(run.run_id),
+        json!(run.status.to_string()),
+        json!(run.started_at),
+        json!(run.finished_at),
+        json!(updated_at),
+    ];
+    db.execute(
+        &upsert_sql(dialect, JOBS_TABLE, &job_columns, "job_id"),
+        &job_params,
+    )
+    .await?;
+
+    debug!(
+        "Recorded run {} of job {} as {}",
+        run.run_id, run.job_id, run.status
+    );
+    Ok(())
+}
+
+/// 查询运行历史，按开始时间倒序，可按作业过滤
+pub async fn list_runs(db: &dyn Database, job_id: Option<&str>, limit: u64) -> Result<Vec<JobRun>> {
+    let dialect = Dialect::of(db)?;
+    let final_clause = if dialect == Dialect::ClickHouse {
+        " FINAL"
+    } else {
+        ""
+    };
+    let (where_clause, params) = match job_id {
+        Some(job_id) => (
+            format!(" WHERE job_id = {}", dialect.placeholder(1)),
+            vec![json!(job_id)],
+        ),
+        None => (String::new(), Vec::new()),
+    };
+    let sql = format!(
+        "SELECT {} FROM {}{}{} ORDER BY started_at DESC LIMIT {}",
+        RUN_COLUMNS.join(", "),
+        RUNS_TABLE,
+        final_clause,
+        where_clause,
+        limit
+    );
+
+    db.execute(&sql, &params)
+        .await?
+        .rows
+        .into_iter()
+        .map(|row| {
+            serde_json::from_value(row)
+                .map_err(|e| DatabaseError::QueryError(format!("Invalid run record: {}", e)))
+        })
+        .collect()
+}
@@ -1,20 +1,314 @@
 use async_trait::async_trait;
-use clickhouse::Client;
+use clickhouse::insert::Insert;
+use clickhouse::inserter::{Inserter, Quantities};
+use clickhouse::query::Query;
+use clickhouse::{Client, Compression};
+use futures::stream;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::Client as HyperClient;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ClientConfig, RootCertStore};
+use serde::Serialize;
 use serde_json::Value;
 use slog_scope::debug;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 
 use crate::config::ClickHouseConfig;
 use crate::error::{DatabaseError, Result};
+use crate::migration;
+use crate::query::{FILE_SCAN_COLUMNS, RecordStream, ScanQuery};
+use crate::rollup::{self, DirRollup};
 use crate::traits::FileScanRecord;
 use crate::traits::{Database, QueryResult};
-use crate::{SCAN_BASE_TABLE_BASE_NAME, SCAN_STATE_TABLE_BASE_NAME};
-use crate::{generate_scan_temp_table_name, get_scan_base_table_name, get_scan_state_table_name};
+use crate::{SCAN_BASE_TABLE_BASE_NAME, SCAN_DIRS_TABLE_BASE_NAME, SCAN_STATE_TABLE_BASE_NAME};
+use crate::{
+    generate_scan_temp_table_name, get_scan_base_table_name, get_scan_dirs_table_name,
+    get_scan_state_table_name,
+};
 
 pub struct ClickHouseDatabase {
     sync_client: Client,
     async_client: Client,
     job_id: String,
+    /// 单次请求的超时时间，流式查询除外
+    read_timeout: Option<Duration>,
     scan_temp_table_name: Option<String>,
+    /// 流式写入的提交阈值，任一达到即提交
+    inserter_max_rows: u64,
+    inserter_max_bytes: u64,
+    inserter_period: Option<Duration>,
+    /// 进行中的流式写入，出错时丢弃并在下次写入时重建
+    base_inserter: Mutex<Option<BaseInserter>>,
+    layout: TableLayout,
+    /// 共享表布局下保留的最近代次数，0表示全部保留
+    retain_generations: u32,
+    /// 共享表布局下当前写入的代次，首次使用时从表中解析
+    generation: Mutex<Option<u32>>,
+}
+
+/// 所有作业共用的扫描结果表，按 (job_id, generation) 分区
+pub const SHARED_SCAN_BASE_TABLE: &str = "terrasync_scan_base";
+
+/// 所有作业共用的扫描状态表
+pub const SHARED_SCAN_STATE_TABLE: &str = "terrasync_scan_state";
+
+/// 共享扫描状态表中记录作业已提交代次的行id
+const COMMITTED_GENERATION_ID: u8 = 2;
+
+/// 扫描结果的表布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableLayout {
+    /// 每个作业一张scan_base表
+    PerJob,
+    /// 所有作业写入同一张分区表，每次扫描写入新的代次
+    Shared,
+}
+
+impl TableLayout {
+    fn parse(layout: &str) -> Result<Self> {
+        match layout {
+            "per_job" => Ok(TableLayout::PerJob),
+            "shared" => Ok(TableLayout::Shared),
+            other => Err(DatabaseError::ConfigError(format!(
+                "Unknown ClickHouse table layout: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// 共享表中的一行：扫描记录附加作业、代次和用于去重的版本号
+#[derive(Debug, Serialize, clickhouse::Row)]
+struct SharedScanRecord {
+    job_id: String,
+    generation: u32,
+    version: u64,
+    path: String,
+    size: u64,
+    ext: Option<String>,
+    ctime: i64,
+    mtime: i64,
+    atime: i64,
+    perm: Option<String>,
+    is_symlink: bool,
+    is_dir: bool,
+    is_regular_file: bool,
+    hard_links: u8,
+    current_state: u8,
+    checksum: Option<String>,
+}
+
+impl SharedScanRecord {
+    fn new(job_id: &str, generation: u32, version: u64, record: &FileScanRecord) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            generation,
+            version,
+            path: record.path.clone(),
+            size: record.size,
+            ext: record.ext.clone(),
+            ctime: record.ctime,
+            mtime: record.mtime,
+            atime: record.atime,
+            perm: record.perm.clone(),
+            is_symlink: record.is_symlink,
+            is_dir: record.is_dir,
+            is_regular_file: record.is_regular_file,
+            hard_links: record.hard_links,
+            current_state: record.current_state,
+            checksum: record.checksum.clone(),
+        }
+    }
+}
+
+/// 写入base表的INSERT，按表布局写入对应的行结构
+enum BaseInsert {
+    PerJob(Insert<FileScanRecord>),
+    Shared {
+        insert: Insert<SharedScanRecord>,
+        job_id: String,
+        generation: u32,
+        version: u64,
+    },
+}
+
+impl BaseInsert {
+    async fn write(&mut self, record: &FileScanRecord) -> clickhouse::error::Result<()> {
+        match self {
+            BaseInsert::PerJob(insert) => insert.write(record).await,
+            BaseInsert::Shared {
+                insert,
+                job_id,
+                generation,
+                version,
+            } => {
+                let row = SharedScanRecord::new(job_id, *generation, *version, record);
+                insert.write(&row).await
+            }
+        }
+    }
+
+    async fn end(self) -> clickhouse::error::Result<()> {
+        match self {
+            BaseInsert::PerJob(insert) => insert.end().await,
+            BaseInsert::Shared { insert, .. } => insert.end().await,
+        }
+    }
+}
+
+/// base表的流式写入，由Inserter按行数、字节数和时间阈值分多次INSERT提交
+enum BaseInserter {
+    PerJob(Inserter<FileScanRecord>),
+    Shared {
+        inserter: Inserter<SharedScanRecord>,
+        job_id: String,
+        generation: u32,
+        version: u64,
+    },
+}
+
+impl BaseInserter {
+    fn write(&mut self, record: &FileScanRecord) -> clickhouse::error::Result<()> {
+        match self {
+            BaseInserter::PerJob(inserter) => inserter.write(record),
+            BaseInserter::Shared {
+                inserter,
+                job_id,
+                generation,
+                version,
+            } => {
+                let row = SharedScanRecord::new(job_id, *generation, *version, record);
+                inserter.write(&row)
+            }
+        }
+    }
+
+    /// 达到阈值时提交，`force` 为true时无条件提交
+    async fn commit(&mut self, force: bool) -> clickhouse::error::Result<Quantities> {
+        match self {
+            BaseInserter::PerJob(inserter) if force => inserter.force_commit().await,
+            BaseInserter::PerJob(inserter) => inserter.commit().await,
+            BaseInserter::Shared { inserter, .. } if force => inserter.force_commit().await,
+            BaseInserter::Shared { inserter, .. } => inserter.commit().await,
+        }
+    }
+
+    /// 尚未提交的行数
+    fn pending_rows(&self) -> u64 {
+        match self {
+            BaseInserter::PerJob(inserter) => inserter.pending().rows,
+            BaseInserter::Shared { inserter, .. } => inserter.pending().rows,
+        }
+    }
+}
+
+/// 共享表行的版本号，同一代次中同一路径的多次写入保留版本号最大的一行
+fn insert_version() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// 与clickhouse crate默认客户端一致的TCP保活和连接池空闲时间
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 读取密码：依次尝试密码文件、环境变量和明文密码
+fn resolve_password(config: &ClickHouseConfig) -> Result<Option<String>> {
+    if let Some(path) = &config.password_file {
+        let password = std::fs::read_to_string(path).map_err(|e| {
+            DatabaseError::ConfigError(format!("Failed to read password file {}: {}", path, e))
+        })?;
+        return Ok(Some(password.trim_end_matches(['\r', '\n']).to_string()));
+    }
+    if let Some(name) = &config.password_env {
+        let password = std::env::var(name).map_err(|e| {
+            DatabaseError::ConfigError(format!("Failed to read password from ${}: {}", name, e))
+        })?;
+        return Ok(Some(password));
+    }
+    Ok(config.password.clone())
+}
+
+/// 构建TLS配置：信任内置根证书和自定义CA，可选客户端证书认证
+fn tls_config(config: &ClickHouseConfig) -> Result<ClientConfig> {
+    let tls_error = |e: &dyn std::fmt::Display| DatabaseError::ConfigError(format!("TLS: {}", e));
+
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(path) = &config.ca_cert {
+        for cert in CertificateDer::pem_file_iter(path).map_err(|e| tls_error(&e))? {
+            roots
+                .add(cert.map_err(|e| tls_error(&e))?)
+                .map_err(|e| tls_error(&e))?;
+        }
+    }
+
+    let builder =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| tls_error(&e))?
+            .with_root_certificates(roots);
+
+    match (&config.client_cert, &config.client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let certs = CertificateDer::pem_file_iter(cert_path)
+                .map_err(|e| tls_error(&e))?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| tls_error(&e))?;
+            let key = PrivateKeyDer::from_pem_file(key_path).map_err(|e| tls_error(&e))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| tls_error(&e))
+        }
+        (None, None) => Ok(builder.with_no_client_auth()),
+        _ => Err(DatabaseError::ConfigError(
+            "client_cert and client_key must be configured together".to_string(),
+        )),
+    }
+}
+
+/// 构建客户端：底层HTTP(S)连接使用 `dial_timeout` 作为连接超时，并附加认证、压缩和会话设置
+fn build_client(config: &ClickHouseConfig) -> Result<Client> {
+    let mut connector = HttpConnector::new();
+    connector.set_keepalive(Some(TCP_KEEPALIVE));
+    connector.enforce_http(false);
+    if config.dial_timeout > 0 {
+        connector.set_connect_timeout(Some(Duration::from_secs(config.dial_timeout as u64)));
+    }
+    let connector: HttpsConnector<HttpConnector> = HttpsConnectorBuilder::new()
+        .with_tls_config(tls_config(config)?)
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(connector);
+    let http_client = HyperClient::builder(TokioExecutor::new())
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .build(connector);
+
+    let compression = if config.compression {
+        Compression::Lz4
+    } else {
+        Compression::None
+    };
+    let mut client = Client::with_http_client(http_client)
+        .with_url(&config.dsn)
+        .with_database(config.database.clone())
+        .with_user(config.username.clone())
+        .with_compression(compression);
+    if let Some(password) = resolve_password(config)? {
+        client = client.with_password(password);
+    }
+    for (name, value) in &config.settings {
+        client = client.with_option(name, value);
+    }
+    Ok(client)
 }
 
 /// 文件扫描记录的标准列定义
@@ -33,48 +327,286 @@ const FILE_SCAN_COLUMNS_DEFINITION: &str = r#"
     current_state UInt8
 "#;
 
+/// 判断语句是否返回结果行
+fn returns_rows(sql: &str) -> bool {
+    let keyword = sql
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+    matches!(keyword.as_str(), "SELECT" | "WITH")
+}
+
+/// 按顺序绑定JSON参数
+fn bind_params(mut query: Query, params: &[Value]) -> Query {
+    for param in params {
+        query = match param {
+            Value::String(s) => query.bind(s.as_str()),
+            Value::Bool(b) => query.bind(*b as u8),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind(i),
+                None => query.bind(n.as_f64().unwrap_or_default()),
+            },
+            other => query.bind(other.to_string()),
+        };
+    }
+    query
+}
+
+/// 未查询列的默认值表达式，类型与表结构一致
+fn default_column_value(column: &str) -> &'static str {
+    match column {
+        "path" => "''",
+        "size" => "toUInt64(0)",
+        "ext" | "perm" | "checksum" => "CAST(NULL, 'Nullable(String)')",
+        "ctime" | "mtime" | "atime" => "toDateTime64(0, 3)",
+        _ => "toUInt8(0)",
+    }
+}
+
 impl ClickHouseDatabase {
-    pub fn new(config: ClickHouseConfig, job_id: String) -> Self {
-        // 创建同步客户端
-        let mut sync_client = Client::default()
-            .with_url(&config.dsn)
-            .with_database(config.database.clone())
-            .with_user(config.username.clone());
-        let mut async_client = Client::default()
-            .with_url(&config.dsn)
-            .with_database(config.database.clone())
-            .with_user(config.username.clone())
+    pub fn new(config: ClickHouseConfig, job_id: String) -> Result<Self> {
+        // 同步客户端与异步插入客户端共享底层连接池
+        let sync_client = build_client(&config)?;
+        let async_client = sync_client
+            .clone()
             .with_option("async_insert", "1")
             .with_option("wait_for_async_insert", "1");
 
-        // 可选的密码配置
-        if let Some(password) = &config.password {
-            sync_client = sync_client.with_password(password);
-            async_client = async_client.with_password(password);
-        }
-
-        Self {
+        Ok(Self {
             sync_client,
             async_client,
             job_id,
+            read_timeout: (config.read_timeout > 0)
+                .then(|| Duration::from_secs(config.read_timeout as u64)),
             scan_temp_table_name: None,
+            inserter_max_rows: config.inserter_max_rows.max(1),
+            inserter_max_bytes: config.inserter_max_bytes.max(1),
+            inserter_period: (config.inserter_period > 0)
+                .then(|| Duration::from_secs(config.inserter_period)),
+            base_inserter: Mutex::new(None),
+            layout: TableLayout::parse(&config.table_layout)?,
+            retain_generations: config.retain_generations,
+            generation: Mutex::new(None),
+        })
+    }
+
+    /// 当前作业的base表名
+    fn base_table_name(&self) -> String {
+        match self.layout {
+            TableLayout::PerJob => get_scan_base_table_name(&self.job_id),
+            TableLayout::Shared => SHARED_SCAN_BASE_TABLE.to_string(),
+        }
+    }
+
+    /// 当前作业的scan_state表名
+    fn state_table_name(&self) -> String {
+        match self.layout {
+            TableLayout::PerJob => get_scan_state_table_name(&self.job_id),
+            TableLayout::Shared => SHARED_SCAN_STATE_TABLE.to_string(),
+        }
+    }
+
+    /// 共享表中作业已有数据的最新代次，没有数据时为0
+    async fn latest_generation(&self) -> Result<u32> {
+        let query = format!(
+            "SELECT max(generation) FROM {} WHERE job_id = ?",
+            SHARED_SCAN_BASE_TABLE
+        );
+        self.with_timeout(
+            self.sync_client
+                .query(&query)
+                .bind(&self.job_id)
+                .fetch_one::<u32>(),
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryError(e.to_string()))
+    }
+
+    /// 写入使用的代次：未开始新代次时写入最新代次
+    async fn write_generation(&self) -> Result<u32> {
+        let mut generation = self.generation.lock().await;
+        if let Some(generation) = *generation {
+            return Ok(generation);
+        }
+        let latest = self.latest_generation().await?.max(1);
+        *generation = Some(latest);
+        Ok(latest)
+    }
+
+    /// 查询使用的代次：最近一次提交的代次，尚未提交过时使用最新代次
+    /// 扫描进行中写入的新代次在提交前对查询不可见
+    async fn current_generation(&self) -> Result<u32> {
+        if self.table_exists(SHARED_SCAN_STATE_TABLE).await? {
+            let query = format!(
+                "SELECT generation FROM {} FINAL WHERE job_id = ? AND id = ?",
+                SHARED_SCAN_STATE_TABLE
+            );
+            let committed = self
+                .with_timeout(
+                    self.sync_client
+                        .query(&query)
+                        .bind(&self.job_id)
+                        .bind(COMMITTED_GENERATION_ID)
+                        .fetch_optional::<u32>(),
+                )
+                .await
+                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+            if let Some(generation) = committed {
+                return Ok(generation);
+            }
+        }
+        Ok(self.latest_generation().await?.max(1))
+    }
+
+    /// 删除共享表中作业不晚于 `up_to` 的代次分区，未指定时删除作业的全部分区
+    pub async fn drop_generations(&self, up_to: Option<u32>) -> Result<Vec<u32>> {
+        if self.layout == TableLayout::PerJob || !self.table_exists(SHARED_SCAN_BASE_TABLE).await? {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "SELECT DISTINCT generation FROM {} WHERE job_id = ? AND generation <= ? ORDER BY generation",
+            SHARED_SCAN_BASE_TABLE
+        );
+        let generations = self
+            .with_timeout(
+                self.sync_client
+                    .query(&query)
+                    .bind(&self.job_id)
+                    .bind(up_to.unwrap_or(u32::MAX))
+                    .fetch_all::<u32>(),
+            )
+            .await
+            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+
+        let drop_sql = format!(
+            "ALTER TABLE {} DROP PARTITION (?, ?)",
+            SHARED_SCAN_BASE_TABLE
+        );
+        for generation in &generations {
+            self.with_timeout(
+                self.sync_client
+                    .query(&drop_sql)
+                    .bind(&self.job_id)
+                    .bind(*generation)
+                    .execute(),
+            )
+            .await
+            .map_err(DatabaseError::ClickHouseError)?;
+        }
+
+        debug!(
+            "Dropped generations {:?} of job {} from {}",
+            generations, self.job_id, SHARED_SCAN_BASE_TABLE
+        );
+        Ok(generations)
+    }
+
+    /// 创建base表的INSERT，共享表布局下写入当前代次
+    async fn base_insert(&self, client: &Client) -> Result<BaseInsert> {
+        let table_name = self.base_table_name();
+        match self.layout {
+            TableLayout::PerJob => Ok(BaseInsert::PerJob(self.insert(client, &table_name)?)),
+            TableLayout::Shared => {
+                let insert = self.insert(client, &table_name)?;
+                Ok(BaseInsert::Shared {
+                    insert,
+                    job_id: self.job_id.clone(),
+                    generation: self.write_generation().await?,
+                    version: insert_version(),
+                })
+            }
+        }
+    }
+
+    /// 创建base表的流式写入，共享表布局下写入当前代次
+    async fn base_inserter(&self) -> Result<BaseInserter> {
+        let table_name = self.base_table_name();
+        match self.layout {
+            TableLayout::PerJob => Ok(BaseInserter::PerJob(self.inserter(&table_name)?)),
+            TableLayout::Shared => Ok(BaseInserter::Shared {
+                inserter: self.inserter(&table_name)?,
+                job_id: self.job_id.clone(),
+                generation: self.write_generation().await?,
+                version: insert_version(),
+            }),
+        }
+    }
+
+    /// 将记录写入base表，`client` 决定是否使用异步插入
+    async fn insert_base_records(&self, client: &Client, records: &[FileScanRecord]) -> Result<()> {
+        let mut insert = self.base_insert(client).await?;
+        for record in records {
+            insert
+                .write(record)
+                .await
+                .map_err(DatabaseError::ClickHouseError)?;
+        }
+        insert.end().await.map_err(DatabaseError::ClickHouseError)
+    }
+
+    /// 为请求应用 `read_timeout`
+    async fn with_timeout<T>(
+        &self, request: impl Future<Output = clickhouse::error::Result<T>>,
+    ) -> clickhouse::error::Result<T> {
+        match self.read_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, request)
+                .await
+                .unwrap_or(Err(clickhouse::error::Error::TimedOut)),
+            None => request.await,
         }
     }
 
+    /// 创建INSERT，发送和结束时均应用 `read_timeout`
+    fn insert<T: clickhouse::Row>(&self, client: &Client, table_name: &str) -> Result<Insert<T>> {
+        Ok(client
+            .insert(table_name)
+            .map_err(DatabaseError::ClickHouseError)?
+            .with_timeouts(self.read_timeout, self.read_timeout))
+    }
+
+    /// 创建按配置阈值提交的Inserter，发送和提交时均应用 `read_timeout`
+    fn inserter<T: clickhouse::Row>(&self, table_name: &str) -> Result<Inserter<T>> {
+        Ok(self
+            .sync_client
+            .inserter(table_name)
+            .map_err(DatabaseError::ClickHouseError)?
+            .with_timeouts(self.read_timeout, self.read_timeout)
+            .with_max_rows(self.inserter_max_rows)
+            .with_max_bytes(self.inserter_max_bytes)
+            .with_period(self.inserter_period))
+    }
+
     /// 创建主扫描表
     /// 创建包含完整文件信息字段的主表，用于存储扫描结果
     /// 表结构包含：路径、大小、扩展名、创建时间、修改时间、访问时间、权限、符号链接标志、目录标志、普通文件标志、目录句柄、当前状态
     /// 使用ReplacingMergeTree引擎，基于path字段排序，自动处理重复数据
+    /// 共享表布局下所有作业共用一张表，按 (job_id, generation) 分区，按version去重
     pub async fn create_scan_base_table(&self) -> Result<()> {
-        let table_name = get_scan_base_table_name(&self.job_id);
-        let create_table_sql = format!(
-            "CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = ReplacingMergeTree() ORDER BY (path)",
-            table_name, FILE_SCAN_COLUMNS_DEFINITION
-        );
+        let table_name = self.base_table_name();
+        let existed = self.table_exists(&table_name).await?;
+        let columns = migration::columns_definition(FILE_SCAN_COLUMNS_DEFINITION, "clickhouse");
+        let create_table_sql = match self.layout {
+            TableLayout::PerJob => format!(
+                "CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = ReplacingMergeTree() ORDER BY (path)",
+                table_name, columns
+            ),
+            TableLayout::Shared => format!(
+                "CREATE TABLE IF NOT EXISTS {} (job_id String, generation UInt32, version UInt64, {}) \
+                 ENGINE = ReplacingMergeTree(version) PARTITION BY (job_id, generation) \
+                 ORDER BY (job_id, generation, path)",
+                table_name,
+                columns.trim_start()
+            ),
+        };
 
         debug!("Creating ClickHouse scan base table: {}", table_name);
         self.execute(&create_table_sql, &[]).await?;
 
+        migration::prepare_scan_base_table(self, &table_name, existed).await?;
+
         Ok(())
     }
 
@@ -82,11 +614,18 @@ impl ClickHouseDatabase {
     /// 创建用于存储扫描状态信息的表，包含id和origin_state字段
     /// 使用ReplacingMergeTree引擎，基于id字段排序，确保状态数据唯一性
     pub async fn create_scan_state_table(&self) -> Result<()> {
-        let table_name = get_scan_state_table_name(&self.job_id);
-        let create_table_sql = format!(
-            "CREATE TABLE IF NOT EXISTS {} (id UInt8, origin_state UInt8) ENGINE = ReplacingMergeTree() ORDER BY id",
-            table_name
-        );
+        let table_name = self.state_table_name();
+        let create_table_sql = match self.layout {
+            TableLayout::PerJob => format!(
+                "CREATE TABLE IF NOT EXISTS {} (id UInt8, origin_state UInt8) ENGINE = ReplacingMergeTree() ORDER BY id",
+                table_name
+            ),
+            TableLayout::Shared => format!(
+                "CREATE TABLE IF NOT EXISTS {} (job_id String, id UInt8, origin_state UInt8, \
+                 generation UInt32 DEFAULT 0) ENGINE = ReplacingMergeTree() ORDER BY (job_id, id)",
+                table_name
+            ),
+        };
 
         debug!("Creating ClickHouse scan state table: {}", table_name);
         self.execute(&create_table_sql, &[]).await?;
@@ -113,9 +652,7 @@ impl ClickHouseDatabase {
         );
 
         let table_names: Vec<String> = self
-            .sync_client
-            .query(&query)
-            .fetch_all::<String>()
+            .with_timeout(self.sync_client.query(&query).fetch_all::<String>())
             .await
             .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
 
@@ -138,9 +675,7 @@ impl ClickHouseDatabase {
 impl Database for ClickHouseDatabase {
     async fn ping(&self) -> Result<()> {
         // 测试连接
-        self.sync_client
-            .query("SELECT 1")
-            .fetch_one::<u8>()
+        self.with_timeout(self.sync_client.query("SELECT 1").fetch_one::<u8>())
             .await
             .map_err(|e| DatabaseError::ConnectionError(e.to_string()))?;
 
@@ -153,6 +688,9 @@ impl Database for ClickHouseDatabase {
         match table_name {
             SCAN_BASE_TABLE_BASE_NAME => self.create_scan_base_table().await,
             SCAN_STATE_TABLE_BASE_NAME => self.create_scan_state_table().await,
+            SCAN_DIRS_TABLE_BASE_NAME => {
+                rollup::create_dirs_table(self, &get_scan_dirs_table_name(&self.job_id)).await
+            }
             _ => {
                 // 通用表创建 - 对于未知表名，直接返回错误
                 Err(DatabaseError::UnsupportedType(format!(
@@ -165,15 +703,34 @@ impl Database for ClickHouseDatabase {
 
     async fn drop_table(&self, table_name: &str) -> Result<()> {
         // 根据表名调用相应的删除方法
-        match table_name {
-            SCAN_BASE_TABLE_BASE_NAME => {
+        // 共享表布局下只删除当前作业的数据
+        match (table_name, self.layout) {
+            (SCAN_BASE_TABLE_BASE_NAME, TableLayout::Shared) => {
+                self.drop_generations(None).await.map(|_| ())
+            }
+            (SCAN_STATE_TABLE_BASE_NAME, TableLayout::Shared) => {
+                if self.table_exists(SHARED_SCAN_STATE_TABLE).await? {
+                    let sql = format!(
+                        "ALTER TABLE {} DELETE WHERE job_id = ?",
+                        SHARED_SCAN_STATE_TABLE
+                    );
+                    self.execute(&sql, &[Value::from(self.job_id.as_str())])
+                        .await?;
+                }
+                Ok(())
+            }
+            (SCAN_BASE_TABLE_BASE_NAME, TableLayout::PerJob) => {
                 self.drop_table_by_name(&get_scan_base_table_name(&self.job_id))
                     .await
             }
-            SCAN_STATE_TABLE_BASE_NAME => {
+            (SCAN_STATE_TABLE_BASE_NAME, TableLayout::PerJob) => {
                 self.drop_table_by_name(&get_scan_state_table_name(&self.job_id))
                     .await
             }
+            (SCAN_DIRS_TABLE_BASE_NAME, _) => {
+                self.drop_table_by_name(&get_scan_dirs_table_name(&self.job_id))
+                    .await
+            }
             _ => {
                 // 通用表删除 - 对于未知表名，直接删除指定表名
                 self.drop_table_by_name(table_name).await
@@ -184,25 +741,33 @@ impl Database for ClickHouseDatabase {
     async fn execute(&self, sql: &str, params: &[Value]) -> Result<QueryResult> {
         debug!("Executing ClickHouse statement: {}", sql);
 
-        let mut query = self.sync_client.query(sql);
-
-        // 绑定参数
-        for param in params {
-            if let Some(s) = param.as_str() {
-                query = query.bind(s);
-            } else if let Some(n) = param.as_i64() {
-                query = query.bind(n);
-            } else if let Some(b) = param.as_bool() {
-                query = query.bind(b);
-            } else {
-                query = query.bind(param.to_string());
-            }
+        // 查询语句逐行格式化为JSON返回，其他语句只执行
+        if returns_rows(sql) {
+            let wrapped = format!(
+                "SELECT formatRowNoNewline('JSONEachRow', *) FROM ({})",
+                sql.trim().trim_end_matches(';')
+            );
+            let query = bind_params(self.sync_client.query(&wrapped), params)
+                .with_option("output_format_json_quote_64bit_integers", "0");
+            let rows = self
+                .with_timeout(query.fetch_all::<String>())
+                .await
+                .map_err(|e| DatabaseError::QueryError(e.to_string()))?
+                .iter()
+                .map(|row| {
+                    serde_json::from_str(row).map_err(|e| DatabaseError::QueryError(e.to_string()))
+                })
+                .collect::<Result<Vec<Value>>>()?;
+            return Ok(QueryResult {
+                rows,
+                affected_rows: 0,
+                last_insert_id: None,
+            });
         }
 
-        query
-            .execute()
+        self.with_timeout(bind_params(self.sync_client.query(sql), params).execute())
             .await
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+            .map_err(DatabaseError::ClickHouseError)?;
 
         Ok(QueryResult {
             rows: Vec::new(),
@@ -220,9 +785,7 @@ impl Database for ClickHouseDatabase {
         );
 
         let count: u64 = self
-            .sync_client
-            .query(&query)
-            .fetch_one()
+            .with_timeout(self.sync_client.query(&query).fetch_one())
             .await
             .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
 
@@ -242,7 +805,8 @@ impl Database for ClickHouseDatabase {
         let temp_table_name = generate_scan_temp_table_name();
         let create_table_sql = format!(
             "CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = MergeTree() ORDER BY (path)",
-            temp_table_name, FILE_SCAN_COLUMNS_DEFINITION
+            temp_table_name,
+            migration::columns_definition(FILE_SCAN_COLUMNS_DEFINITION, "clickhouse")
         );
 
         debug!(
@@ -291,24 +855,18 @@ impl Database for ClickHouseDatabase {
         let record_count = records.len();
 
         // 使用标准insert方法进行批量插入
-        let mut insert = self
-            .sync_client
-            .insert(temp_table_name)
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+        let mut insert = self.insert(&self.sync_client, temp_table_name)?;
 
         // 批量写入所有记录
         for record in &records {
             insert
                 .write(record)
                 .await
-                .map_err(|e| DatabaseError::ClickHouseError(e))?;
+                .map_err(DatabaseError::ClickHouseError)?;
         }
 
         // 确保最终完成
-        insert
-            .end()
-            .await
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+        insert.end().await.map_err(DatabaseError::ClickHouseError)?;
 
         debug!(
             "Successfully inserted {} events to temporary table",
@@ -323,34 +881,14 @@ impl Database for ClickHouseDatabase {
     }
 
     async fn batch_insert_base_record_sync(&self, records: Vec<FileScanRecord>) -> Result<()> {
-        let base_table_name = get_scan_base_table_name(&self.job_id);
-
         if records.is_empty() {
             debug!("No events to insert");
             return Ok(());
         }
 
         let record_count = records.len();
-
-        // 使用标准insert方法进行批量插入
-        let mut insert = self
-            .sync_client
-            .insert(&base_table_name)
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
-
-        // 批量写入所有记录
-        for record in &records {
-            insert
-                .write(record)
-                .await
-                .map_err(|e| DatabaseError::ClickHouseError(e))?;
-        }
-
-        // 确保最终完成
-        insert
-            .end()
-            .await
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+        self.insert_base_records(&self.sync_client, &records)
+            .await?;
 
         debug!(
             "Successfully inserted {} events to base table",
@@ -360,83 +898,213 @@ impl Database for ClickHouseDatabase {
     }
 
     async fn batch_insert_base_record_async(&self, records: Vec<FileScanRecord>) -> Result<()> {
-        let base_table_name = get_scan_base_table_name(&self.job_id);
-
         if records.is_empty() {
             debug!("No events to insert");
             return Ok(());
         }
 
         let record_count = records.len();
+        self.insert_base_records(&self.async_client, &records)
+            .await?;
 
-        // 使用标准insert方法进行批量插入
-        let mut insert = self
-            .async_client
-            .insert(&base_table_name)
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+        debug!(
+            "Successfully inserted {} events to base table",
+            record_count
+        );
+        Ok(())
+    }
 
-        // 批量写入所有记录
-        for record in &records {
-            insert
+    fn supports_streaming_insert(&self) -> bool {
+        true
+    }
+
+    /// 写入进行中的INSERT，达到行数、字节数或时间阈值时提交
+    /// 不带记录调用时只检查阈值，使写入缓慢时也能按时间提交
+    async fn stream_insert_base_records(&self, records: &[FileScanRecord]) -> Result<bool> {
+        let mut guard = self.base_inserter.lock().await;
+        // 出错时丢弃Inserter及其未提交的数据
+        let mut inserter = match guard.take() {
+            Some(inserter) => inserter,
+            None => self.base_inserter().await?,
+        };
+
+        for record in records {
+            inserter
                 .write(record)
+                .map_err(DatabaseError::ClickHouseError)?;
+        }
+        let committed = inserter
+            .commit(false)
+            .await
+            .map_err(DatabaseError::ClickHouseError)?;
+        if committed.rows > 0 {
+            debug!(
+                "Committed {} streamed rows ({} bytes) to base table",
+                committed.rows, committed.bytes
+            );
+        }
+
+        let all_committed = inserter.pending_rows() == 0;
+        *guard = Some(inserter);
+        Ok(all_committed)
+    }
+
+    async fn flush_base_records(&self) -> Result<()> {
+        let mut guard = self.base_inserter.lock().await;
+        if let Some(mut inserter) = guard.take() {
+            let committed = inserter
+                .commit(true)
                 .await
-                .map_err(|e| DatabaseError::ClickHouseError(e))?;
+                .map_err(DatabaseError::ClickHouseError)?;
+            debug!(
+                "Committed {} streamed rows ({} bytes) to base table",
+                committed.rows, committed.bytes
+            );
+            *guard = Some(inserter);
         }
+        Ok(())
+    }
 
-        // 确保最终完成
-        insert
-            .end()
-            .await
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+    /// 开始新的代次，提交前查询仍读取上次提交的代次，按作业分表时无需处理
+    async fn start_scan_generation(&self) -> Result<()> {
+        if self.layout == TableLayout::PerJob {
+            return Ok(());
+        }
+
+        let generation = self.latest_generation().await? + 1;
+        *self.generation.lock().await = Some(generation);
 
         debug!(
-            "Successfully inserted {} events to base table",
-            record_count
+            "Started scan generation {} for job {}",
+            generation, self.job_id
         );
         Ok(())
     }
 
-    /// 查询scan_base表，支持指定列查询，使用FINAL关键字
-    async fn query_scan_base_table(&self, columns: &[&str]) -> Result<Vec<FileScanRecord>> {
-        let table_name = get_scan_base_table_name(&self.job_id);
-        let select_columns = if columns.is_empty() {
-            "*".to_string()
-        } else {
-            columns.join(", ")
-        };
+    /// 在状态表中记录提交的代次使查询切换到该代次，再按保留数删除旧代次分区
+    async fn commit_scan_generation(&self) -> Result<()> {
+        if self.layout == TableLayout::PerJob {
+            return Ok(());
+        }
 
-        let query = format!("SELECT {} FROM {} FINAL", select_columns, table_name);
+        let generation = self.write_generation().await?;
+        let sql = format!(
+            "INSERT INTO {} (job_id, id, origin_state, generation) VALUES (?, ?, 0, ?)",
+            SHARED_SCAN_STATE_TABLE
+        );
+        self.with_timeout(
+            self.sync_client
+                .query(&sql)
+                .bind(&self.job_id)
+                .bind(COMMITTED_GENERATION_ID)
+                .bind(generation)
+                .execute(),
+        )
+        .await
+        .map_err(DatabaseError::ClickHouseError)?;
+
+        if self.retain_generations > 0 && generation > self.retain_generations {
+            self.drop_generations(Some(generation - self.retain_generations))
+                .await?;
+        }
 
-        let rows = self
-            .sync_client
-            .query(&query)
-            .fetch_all::<FileScanRecord>()
-            .await
+        debug!(
+            "Committed scan generation {} for job {}",
+            generation, self.job_id
+        );
+        Ok(())
+    }
+
+    /// 使用FINAL关键字去重，通过RowBinary游标逐行读取HTTP响应
+    /// 共享表布局下只读取作业当前代次的分区，FINAL不跨分区合并
+    async fn query_stream(&self, query: &ScanQuery) -> Result<RecordStream> {
+        let table_name = self.base_table_name();
+        let columns = query.select_columns()?;
+        // RowBinary按字段顺序解码，未选择的列以默认值补齐
+        let select_list = FILE_SCAN_COLUMNS
+            .iter()
+            .map(|column| {
+                if columns.contains(column) {
+                    column.to_string()
+                } else {
+                    format!("{} AS {}", default_column_value(column), column)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (mut where_clause, mut params) = query.where_clause(|column, _| match column {
+            "ctime" | "mtime" | "atime" => "fromUnixTimestamp64Milli(toInt64(?))".to_string(),
+            _ => "?".to_string(),
+        })?;
+        if self.layout == TableLayout::Shared {
+            let filters = where_clause.trim_start().trim_start_matches("WHERE ");
+            where_clause = if filters.is_empty() {
+                " WHERE job_id = ? AND generation = ?".to_string()
+            } else {
+                format!(" WHERE job_id = ? AND generation = ? AND {}", filters)
+            };
+            let generation = self.current_generation().await?;
+            params.splice(
+                0..0,
+                [Value::from(self.job_id.as_str()), Value::from(generation)],
+            );
+        }
+        let sql = format!(
+            "SELECT {} FROM {} FINAL{}{}",
+            select_list,
+            table_name,
+            where_clause,
+            query.order_limit_clause(None)?
+        );
+        debug!("Streaming ClickHouse query: {}", sql);
+
+        let cursor = bind_params(self.sync_client.query(&sql), &params)
+            .with_option("do_not_merge_across_partitions_select_final", "1")
+            .fetch::<FileScanRecord>()
             .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
 
-        Ok(rows)
+        Ok(Box::pin(stream::try_unfold(
+            cursor,
+            |mut cursor| async move {
+                let row = cursor
+                    .next()
+                    .await
+                    .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+                Ok(row.map(|row| (row, cursor)))
+            },
+        )))
     }
 
     /// 查询scan_state表，返回id=1的origin_state值
     /// 当记录不存在时返回错误
     async fn query_scan_state_table(&self) -> Result<u8> {
-        let table_name = get_scan_state_table_name(&self.job_id);
-        let query = format!("SELECT origin_state FROM {} FINAL WHERE id = 1", table_name);
-
-        let origin_state = self
-            .sync_client
-            .query(&query)
-            .fetch_one::<u8>()
-            .await
-            .map_err(|e| match e {
-                clickhouse::error::Error::RowNotFound => {
-                    DatabaseError::QueryError("No scan state record found for id=1".to_string())
-                }
-                _ => DatabaseError::QueryError(format!(
-                    "Failed to query scan_state table: {}",
-                    e.to_string()
-                )),
-            })?;
+        let table_name = self.state_table_name();
+        let origin_state = match self.layout {
+            TableLayout::PerJob => {
+                let query = format!("SELECT origin_state FROM {} FINAL WHERE id = 1", table_name);
+                self.with_timeout(self.sync_client.query(&query).fetch_one::<u8>())
+                    .await
+            }
+            TableLayout::Shared => {
+                let query = format!(
+                    "SELECT origin_state FROM {} FINAL WHERE job_id = ? AND id = 1",
+                    table_name
+                );
+                self.with_timeout(
+                    self.sync_client
+                        .query(&query)
+                        .bind(&self.job_id)
+                        .fetch_one::<u8>(),
+                )
+                .await
+            }
+        }
+        .map_err(|e| match e {
+            clickhouse::error::Error::RowNotFound => {
+                DatabaseError::QueryError("No scan state record found for id=1".to_string())
+            }
+            _ => DatabaseError::QueryError(format!("Failed to query scan_state table: {}", e)),
+        })?;
 
         Ok(origin_state)
     }
@@ -459,21 +1127,26 @@ impl Database for ClickHouseDatabase {
 
     /// 同步插入scan_state表，id固定为1
     async fn insert_scan_state_sync(&self, origin_state: u8) -> Result<()> {
-        let table_name = get_scan_state_table_name(&self.job_id);
-        let insert_sql = format!(
-            "INSERT INTO {} (id, origin_state) VALUES (?, ?)",
-            table_name
-        );
+        let table_name = self.state_table_name();
+        let query = match self.layout {
+            TableLayout::PerJob => self.sync_client.query(&format!(
+                "INSERT INTO {} (id, origin_state) VALUES (?, ?)",
+                table_name
+            )),
+            TableLayout::Shared => self
+                .sync_client
+                .query(&format!(
+                    "INSERT INTO {} (job_id, id, origin_state) VALUES (?, ?, ?)",
+                    table_name
+                ))
+                .bind(&self.job_id),
+        };
 
         debug!("Inserting scan state: id=1, origin_state={}", origin_state);
 
-        self.sync_client
-            .query(&insert_sql)
-            .bind(1u8)
-            .bind(origin_state)
-            .execute()
+        self.with_timeout(query.bind(1u8).bind(origin_state).execute())
             .await
-            .map_err(|e| DatabaseError::ClickHouseError(e))?;
+            .map_err(DatabaseError::ClickHouseError)?;
 
         debug!(
             "Inserted scan state record: id=1, origin_state={}",
@@ -481,4 +1154,12 @@ impl Database for ClickHouseDatabase {
         );
         Ok(())
     }
+
+    async fn replace_dir_rollups(&self, rollups: &[DirRollup]) -> Result<()> {
+        rollup::replace_dir_rollups(self, &get_scan_dirs_table_name(&self.job_id), rollups).await
+    }
+
+    async fn query_dir_rollups(&self, max_depth: Option<u32>) -> Result<Vec<DirRollup>> {
+        rollup::query_dir_rollups(self, &get_scan_dirs_table_name(&self.job_id), max_depth).await
+    }
 }
@@ -1,9 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum DatabaseType {
     #[serde(rename = "clickhouse")]
     ClickHouse,
+    #[serde(rename = "sqlite")]
+    Sqlite,
+    #[serde(rename = "sqlite-memory")]
+    SqliteMemory,
+    #[serde(rename = "postgres")]
+    Postgres,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +19,8 @@ pub struct DatabaseConfig {
     pub db_type: String,
     pub batch_size: u32,
     pub clickhouse: Option<ClickHouseConfig>,
+    pub sqlite: Option<SqliteConfig>,
+    pub postgres: Option<PostgresConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +31,46 @@ pub struct ClickHouseConfig {
     pub database: String,
     pub username: String,
     pub password: Option<String>,
+    /// 流式写入累计达到该行数时提交
+    pub inserter_max_rows: u64,
+    /// 流式写入累计达到该未压缩字节数时提交
+    pub inserter_max_bytes: u64,
+    /// 距上次提交超过该秒数时提交，0表示不按时间提交
+    pub inserter_period: u64,
+    /// 是否使用LZ4压缩传输数据
+    pub compression: bool,
+    /// 从该环境变量读取密码，优先于 `password`
+    pub password_env: Option<String>,
+    /// 从该文件读取密码，优先于 `password_env` 和 `password`
+    pub password_file: Option<String>,
+    /// 额外信任的CA证书PEM文件，用于校验HTTPS服务端证书
+    pub ca_cert: Option<String>,
+    /// 客户端证书PEM文件，需与 `client_key` 同时配置
+    pub client_cert: Option<String>,
+    /// 客户端私钥PEM文件
+    pub client_key: Option<String>,
+    /// 透传给ClickHouse的会话设置
+    pub settings: BTreeMap<String, String>,
+    /// 扫描结果表布局："per_job" 每个作业一张表，"shared" 所有作业共用按作业和代次分区的表
+    pub table_layout: String,
+    /// "shared" 布局下每个作业保留的最近代次数，0表示全部保留
+    pub retain_generations: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqliteConfig {
+    /// 数据库文件路径，所有作业共用一个文件，按job_id区分表
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostgresConfig {
+    /// 连接串，支持 `postgres://host:port` 或 `host=... port=...` 格式
+    pub dsn: String,
+    pub dial_timeout: u32,
+    pub database: String,
+    pub username: String,
+    pub password: Option<String>,
 }
 
 impl Default for DatabaseConfig {
@@ -31,6 +80,8 @@ impl Default for DatabaseConfig {
             db_type: "clickhouse".to_string(),
             batch_size: 200000,
             clickhouse: Some(ClickHouseConfig::default()),
+            sqlite: None,
+            postgres: None,
         }
     }
 }
@@ -44,6 +95,38 @@ impl Default for ClickHouseConfig {
             database: "default".to_string(),
             username: "default".to_string(),
             password: None,
+            inserter_max_rows: 100_000,
+            inserter_max_bytes: 64 * 1024 * 1024,
+            inserter_period: 10,
+            compression: true,
+            password_env: None,
+            password_file: None,
+            ca_cert: None,
+            client_cert: None,
+            client_key: None,
+            settings: BTreeMap::new(),
+            table_layout: "per_job".to_string(),
+            retain_generations: 2,
+        }
+    }
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        Self {
+            path: "jobs/terrasync.db".to_string(),
+        }
+    }
+}
+
+impl Default for PostgresConfig {
+    fn default() -> Self {
+        Self {
+            dsn: "postgres://localhost:5432".to_string(),
+            dial_timeout: 10,
+            database: "postgres".to_string(),
+            username: "postgres".to_string(),
+            password: None,
         }
     }
 }
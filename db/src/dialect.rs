@@ -0,0 +1,41 @@
+use crate::error::{DatabaseError, Result};
+use crate::traits::Database;
+
+/// 元数据表在各后端上的SQL方言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    ClickHouse,
+    Sqlite,
+    Postgres,
+}
+
+impl Dialect {
+    pub(crate) fn of(db: &dyn Database) -> Result<Self> {
+        match db.database_type() {
+            "clickhouse" => Ok(Dialect::ClickHouse),
+            "sqlite" | "sqlite-memory" => Ok(Dialect::Sqlite),
+            "postgres" => Ok(Dialect::Postgres),
+            other => Err(DatabaseError::UnsupportedType(format!(
+                "Metadata tables are not supported for {}",
+                other
+            ))),
+        }
+    }
+
+    /// 根据后端类型名确定方言，未知类型按SQLite处理
+    pub(crate) fn from_type(database_type: &str) -> Self {
+        match database_type {
+            "clickhouse" => Dialect::ClickHouse,
+            "postgres" => Dialect::Postgres,
+            _ => Dialect::Sqlite,
+        }
+    }
+
+    /// 第 `index` 个（从1开始）参数的占位符
+    pub(crate) fn placeholder(&self, index: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${}", index),
+            _ => "?".to_string(),
+        }
+    }
+}
@@ -5,6 +5,12 @@ pub enum DatabaseError {
     #[error("ClickHouse error: {0}")]
     ClickHouseError(#[from] clickhouse::error::Error),
     
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+    
+    #[error("PostgreSQL error: {0}")]
+    PostgresError(#[from] tokio_postgres::Error),
+    
     #[error("Configuration error: {0}")]
     ConfigError(String),
     
@@ -6,6 +6,8 @@ use crate::error::{DatabaseError, Result};
 use crate::traits::Database;
 
 use crate::clickhouse::ClickHouseDatabase;
+use crate::postgres::PostgresDatabase;
+use crate::sqlite::SqliteDatabase;
 use std::sync::Arc;
 
 pub type DatabaseCreator = fn(config: &DatabaseConfig, job_id: String) -> Result<Arc<dyn Database>>;
@@ -32,18 +34,17 @@ impl DatabaseFactory {
             return Err(DatabaseError::ConfigError("Database is disabled".to_string()));
         }
 
-        if config.db_type != "clickhouse" {
-            return Err(DatabaseError::UnsupportedType(format!(
-                "Database type '{}' is not supported",
-                config.db_type
-            )));
-        }
-
-        let clickhouse_config = config.clickhouse.as_ref()
-            .ok_or_else(|| DatabaseError::ConfigError("ClickHouse configuration missing".to_string()))?;
+        let creator = DATABASE_REGISTRY
+            .get(config.db_type.as_str())
+            .map(|entry| *entry.value())
+            .ok_or_else(|| {
+                DatabaseError::UnsupportedType(format!(
+                    "Database type '{}' is not supported",
+                    config.db_type
+                ))
+            })?;
 
-        let db = ClickHouseDatabase::new(clickhouse_config.clone(), job_id);
-        Ok(Arc::new(db) as Arc<dyn Database>)
+        creator(config, job_id)
     }
 }
 
@@ -59,7 +60,29 @@ fn register_builtin_types(registry: &DashMap<String, DatabaseCreator>) -> Result
         let clickhouse_config = config.clickhouse.as_ref()
             .ok_or_else(|| DatabaseError::ConfigError("ClickHouse configuration missing".to_string()))?;
         
-        let db = ClickHouseDatabase::new(clickhouse_config.clone(), job_id);
+        let db = ClickHouseDatabase::new(clickhouse_config.clone(), job_id)?;
+        Ok(Arc::new(db) as Arc<dyn Database>)
+    });
+
+    // Register SQLite, using the default file path when not configured
+    registry.insert("sqlite".to_string(), |config, job_id| {
+        let sqlite_config = config.sqlite.clone().unwrap_or_default();
+        let db = SqliteDatabase::new(sqlite_config, job_id)?;
+        Ok(Arc::new(db) as Arc<dyn Database>)
+    });
+
+    // Register PostgreSQL
+    registry.insert("postgres".to_string(), |config, job_id| {
+        let postgres_config = config.postgres.as_ref()
+            .ok_or_else(|| DatabaseError::ConfigError("PostgreSQL configuration missing".to_string()))?;
+
+        let db = PostgresDatabase::new(postgres_config.clone(), job_id)?;
+        Ok(Arc::new(db) as Arc<dyn Database>)
+    });
+
+    // Register in-memory SQLite
+    registry.insert("sqlite-memory".to_string(), |_config, job_id| {
+        let db = SqliteDatabase::new_in_memory(job_id)?;
         Ok(Arc::new(db) as Arc<dyn Database>)
     });
 
@@ -1,18 +1,31 @@
+pub mod catalog;
 pub mod clickhouse;
 pub mod config;
+mod dialect;
 pub mod error;
 pub mod factory;
+pub mod migration;
+pub mod postgres;
+pub mod query;
+pub mod rollup;
+pub mod sqlite;
 pub mod traits;
 
 // 共享的表名常量
 pub const SCAN_BASE_TABLE_BASE_NAME: &str = "scan_base";
 pub const SCAN_TEMP_TABLE_BASE_NAME: &str = "scan_temp";
 pub const SCAN_STATE_TABLE_BASE_NAME: &str = "scan_state";
+pub const SCAN_DIRS_TABLE_BASE_NAME: &str = "scan_dirs";
 
-pub use clickhouse::ClickHouseDatabase;
-pub use config::{ClickHouseConfig, DatabaseConfig, DatabaseType};
+pub use clickhouse::{ClickHouseDatabase, SHARED_SCAN_BASE_TABLE, SHARED_SCAN_STATE_TABLE};
+pub use config::{ClickHouseConfig, DatabaseConfig, DatabaseType, PostgresConfig, SqliteConfig};
 pub use error::{DatabaseError, Result};
 pub use factory::{DatabaseFactory, create_database};
+pub use migration::{Migration, MigrationStatus};
+pub use postgres::PostgresDatabase;
+pub use query::{Filter, FilterOp, OrderBy, RecordStream, ScanQuery, SortOrder};
+pub use rollup::DirRollup;
+pub use sqlite::SqliteDatabase;
 pub use traits::{Database, QueryResult};
 
 /// 根据job_id生成扫描基础表名
@@ -25,6 +38,11 @@ pub fn get_scan_state_table_name(job_id: &str) -> String {
     format!("{}_{}", SCAN_STATE_TABLE_BASE_NAME, job_id)
 }
 
+/// 根据job_id生成目录汇总表名
+pub fn get_scan_dirs_table_name(job_id: &str) -> String {
+    format!("{}_{}", SCAN_DIRS_TABLE_BASE_NAME, job_id)
+}
+
 /// 生成唯一的临时扫描表名
 pub fn generate_scan_temp_table_name() -> String {
     use uuid::Uuid;
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use slog_scope::{debug, info};
+
+use crate::SCAN_BASE_TABLE_BASE_NAME;
+use crate::clickhouse::SHARED_SCAN_BASE_TABLE;
+use crate::dialect::Dialect;
+use crate::error::Result;
+use crate::traits::Database;
+
+/// 记录各scan_base表结构版本的元数据表
+pub const SCHEMA_VERSION_TABLE: &str = "terrasync_schema_version";
+
+/// 引入版本管理前的初始表结构版本
+pub const BASELINE_VERSION: u32 = 1;
+
+/// 单个表结构迁移：为scan_base表新增一列
+/// 各后端的列类型需与对应的 `FILE_SCAN_COLUMNS_DEFINITION` 风格保持一致，并提供默认值
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub column: &'static str,
+    pub clickhouse_type: &'static str,
+    pub sqlite_type: &'static str,
+    pub postgres_type: &'static str,
+}
+
+impl Migration {
+    /// 指定后端的列类型
+    fn column_type(&self, dialect: Dialect) -> &'static str {
+        match dialect {
+            Dialect::ClickHouse => self.clickhouse_type,
+            Dialect::Sqlite => self.sqlite_type,
+            Dialect::Postgres => self.postgres_type,
+        }
+    }
+}
+
+/// 按版本号升序排列的迁移列表，版本号从 `BASELINE_VERSION + 1` 开始连续递增
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 2,
+    description: "add checksum column",
+    column: "checksum",
+    clickhouse_type: "Nullable(String)",
+    sqlite_type: "TEXT",
+    postgres_type: "TEXT",
+}];
+
+/// 当前代码对应的最新表结构版本
+pub fn latest_version() -> u32 {
+    latest_version_of(MIGRATIONS)
+}
+
+fn latest_version_of(migrations: &[Migration]) -> u32 {
+    migrations
+        .last()
+        .map_or(BASELINE_VERSION, |migration| migration.version)
+}
+
+/// 单个scan_base表的迁移状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub table_name: String,
+    pub current_version: u32,
+    /// 尚未应用的迁移版本号
+    pub pending: Vec<u32>,
+}
+
+/// 在初始列定义之后追加全部迁移新增的列，用于新建表
+pub(crate) fn columns_definition(base_definition: &str, database_type: &str) -> String {
+    let dialect = Dialect::from_type(database_type);
+    let mut definition = base_definition.trim_end().to_string();
+    for migration in MIGRATIONS {
+        definition.push_str(&format!(
+            ",\n    {} {}",
+            migration.column,
+            migration.column_type(dialect)
+        ));
+    }
+    definition
+}
+
+/// 创建版本元数据表
+async fn ensure_schema_version_table(db: &dyn Database, dialect: Dialect) -> Result<()> {
+    let sql = match dialect {
+        Dialect::ClickHouse => format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name String, version UInt32, applied_at DateTime DEFAULT now()) \
+             ENGINE = ReplacingMergeTree(version) ORDER BY (table_name)",
+            SCHEMA_VERSION_TABLE
+        ),
+        Dialect::Sqlite => format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name TEXT PRIMARY KEY, version INTEGER NOT NULL, \
+             applied_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')))",
+            SCHEMA_VERSION_TABLE
+        ),
+        Dialect::Postgres => format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name TEXT PRIMARY KEY, version INTEGER NOT NULL, \
+             applied_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+            SCHEMA_VERSION_TABLE
+        ),
+    };
+    db.execute(&sql, &[]).await?;
+    Ok(())
+}
+
+/// 从结果行中读取第一列的整数值
+fn first_u64(rows: &[Value], column: &str) -> Option<u64> {
+    rows.first()
+        .and_then(|row| row.get(column))
+        .and_then(Value::as_u64)
+}
+
+/// 读取表的结构版本，没有记录时视为初始版本
+async fn table_version(db: &dyn Database, dialect: Dialect, table_name: &str) -> Result<u32> {
+    let sql = format!(
+        "SELECT max(version) AS version FROM {} WHERE table_name = {}",
+        SCHEMA_VERSION_TABLE,
+        dialect.placeholder(1)
+    );
+    let result = db.execute(&sql, &[json!(table_name)]).await?;
+
+    // ClickHouse的聚合在无记录时返回0，其他后端返回NULL
+    Ok(match first_u64(&result.rows, "version") {
+        Some(version) if version > 0 => version as u32,
+        _ => BASELINE_VERSION,
+    })
+}
+
+/// 写入表的结构版本
+async fn record_version(
+    db: &dyn Database, dialect: Dialect, table_name: &str, version: u32,
+) -> Result<()> {
+    let sql = match dialect {
+        Dialect::ClickHouse => format!(
+            "INSERT INTO {} (table_name, version) VALUES (?, ?)",
+            SCHEMA_VERSION_TABLE
+        ),
+        Dialect::Sqlite => format!(
+            "INSERT OR REPLACE INTO {} (table_name, version) VALUES (?, ?)",
+            SCHEMA_VERSION_TABLE
+        ),
+        Dialect::Postgres => format!(
+            "INSERT INTO {} (table_name, version) VALUES ($1, $2::int8) \
+             ON CONFLICT (table_name) DO UPDATE SET version = EXCLUDED.version, applied_at = now()",
+            SCHEMA_VERSION_TABLE
+        ),
+    };
+    db.execute(&sql, &[json!(table_name), json!(version)])
+        .await?;
+    Ok(())
+}
+
+/// 判断表中是否已存在指定列
+async fn column_exists(
+    db: &dyn Database, dialect: Dialect, table_name: &str, column: &str,
+) -> Result<bool> {
+    let sql = match dialect {
+        Dialect::ClickHouse => "SELECT count() AS total FROM system.columns \
+             WHERE database = currentDatabase() AND table = ? AND name = ?"
+            .to_string(),
+        Dialect::Sqlite => {
+            "SELECT count(*) AS total FROM pragma_table_info(?) WHERE name = ?".to_string()
+        }
+        Dialect::Postgres => "SELECT count(*) AS total FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = lower($1) AND column_name = $2"
+            .to_string(),
+    };
+    let result = db
+        .execute(&sql, &[json!(table_name), json!(column)])
+        .await?;
+    Ok(first_u64(&result.rows, "total").unwrap_or(0) > 0)
+}
+
+/// 列出数据库中全部scan_base表
+async fn list_scan_base_tables(db: &dyn Database, dialect: Dialect) -> Result<Vec<String>> {
+    let prefix = format!("{}_", SCAN_BASE_TABLE_BASE_NAME);
+    let sql = match dialect {
+        // 共享表布局下所有作业共用一张表
+        Dialect::ClickHouse => format!(
+            "SELECT name FROM system.tables WHERE database = currentDatabase() \
+             AND (startsWith(name, ?) OR name = '{}') ORDER BY name",
+            SHARED_SCAN_BASE_TABLE
+        ),
+        Dialect::Sqlite => "SELECT name FROM sqlite_master \
+             WHERE type = 'table' AND substr(name, 1, length(?1)) = ?1 ORDER BY name"
+            .to_string(),
+        Dialect::Postgres => "SELECT tablename AS name FROM pg_catalog.pg_tables \
+             WHERE schemaname = current_schema() AND starts_with(tablename, $1) ORDER BY tablename"
+            .to_string(),
+    };
+    let result = db.execute(&sql, &[json!(prefix)]).await?;
+    Ok(result
+        .rows
+        .iter()
+        .filter_map(|row| row.get("name").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect())
+}
+
+/// 按顺序应用表上尚未执行的迁移，返回本次应用的版本号
+pub async fn migrate_table(
+    db: &dyn Database, table_name: &str, migrations: &[Migration],
+) -> Result<Vec<u32>> {
+    let dialect = Dialect::of(db)?;
+    ensure_schema_version_table(db, dialect).await?;
+
+    let current_version = table_version(db, dialect, table_name).await?;
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > current_version) {
+        // 迁移中途失败后重试时列可能已存在
+        if !column_exists(db, dialect, table_name, migration.column).await? {
+            let sql = format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table_name,
+                migration.column,
+                migration.column_type(dialect)
+            );
+            db.execute(&sql, &[]).await?;
+        }
+        record_version(db, dialect, table_name, migration.version).await?;
+
+        info!(
+            "Applied schema migration {} to {}: {}",
+            migration.version, table_name, migration.description
+        );
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+/// 新建或打开scan_base表后调用：新表直接记录最新版本，已有表补齐缺失的迁移
+pub(crate) async fn prepare_scan_base_table(
+    db: &dyn Database, table_name: &str, existed: bool,
+) -> Result<()> {
+    if existed {
+        migrate_table(db, table_name, MIGRATIONS).await?;
+    } else {
+        let dialect = Dialect::of(db)?;
+        ensure_schema_version_table(db, dialect).await?;
+        record_version(db, dialect, table_name, latest_version()).await?;
+        debug!(
+            "Created {} at schema version {}",
+            table_name,
+            latest_version()
+        );
+    }
+    Ok(())
+}
+
+/// 查询全部scan_base表的迁移状态
+pub async fn migration_status(db: &dyn Database) -> Result<Vec<MigrationStatus>> {
+    migration_status_with(db, MIGRATIONS).await
+}
+
+/// 使用指定迁移列表查询全部scan_base表的迁移状态
+pub async fn migration_status_with(
+    db: &dyn Database, migrations: &[Migration],
+) -> Result<Vec<MigrationStatus>> {
+    let dialect = Dialect::of(db)?;
+    ensure_schema_version_table(db, dialect).await?;
+
+    let mut statuses = Vec::new();
+    for table_name in list_scan_base_tables(db, dialect).await? {
+        let current_version = table_version(db, dialect, &table_name).await?;
+        let pending = migrations
+            .iter()
+            .filter(|m| m.version > current_version)
+            .map(|m| m.version)
+            .collect();
+        statuses.push(MigrationStatus {
+            table_name,
+            current_version,
+            pending,
+        });
+    }
+    Ok(statuses)
+}
+
+/// 对全部scan_base表应用尚未执行的迁移，返回迁移后的状态
+pub async fn migrate_all(db: &dyn Database) -> Result<Vec<MigrationStatus>> {
+    migrate_all_with(db, MIGRATIONS).await
+}
+
+/// 使用指定迁移列表对全部scan_base表执行迁移
+pub async fn migrate_all_with(
+    db: &dyn Database, migrations: &[Migration],
+) -> Result<Vec<MigrationStatus>> {
+    for status in migration_status_with(db, migrations).await? {
+        if !status.pending.is_empty() {
+            migrate_table(db, &status.table_name, migrations).await?;
+        }
+    }
+    migration_status_with(db, migrations).await
+}
@@ -0,0 +1,634 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::Value;
+use slog_scope::debug;
+use std::pin::pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::{Client, NoTls, Row, Transaction};
+
+use crate::config::PostgresConfig;
+use crate::error::{DatabaseError, Result};
+use crate::migration;
+use crate::query::{FILE_SCAN_COLUMNS, RecordStream, ScanQuery};
+use crate::rollup::{self, DirRollup};
+use crate::traits::FileScanRecord;
+use crate::traits::{Database, QueryResult};
+use crate::{SCAN_BASE_TABLE_BASE_NAME, SCAN_DIRS_TABLE_BASE_NAME, SCAN_STATE_TABLE_BASE_NAME};
+use crate::{
+    generate_scan_temp_table_name, get_scan_base_table_name, get_scan_dirs_table_name,
+    get_scan_state_table_name,
+};
+
+/// 二进制COPY使用的列类型，顺序与 `FILE_SCAN_COLUMNS` 一致
+const FILE_SCAN_COLUMN_TYPES: [Type; 13] = [
+    Type::TEXT,
+    Type::INT8,
+    Type::TEXT,
+    Type::TIMESTAMPTZ,
+    Type::TIMESTAMPTZ,
+    Type::TIMESTAMPTZ,
+    Type::TEXT,
+    Type::BOOL,
+    Type::BOOL,
+    Type::BOOL,
+    Type::INT2,
+    Type::INT2,
+    Type::TEXT,
+];
+
+/// 文件扫描记录的标准列定义
+const FILE_SCAN_COLUMNS_DEFINITION: &str = r#"
+    path TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    ext TEXT,
+    ctime TIMESTAMPTZ NOT NULL,
+    mtime TIMESTAMPTZ NOT NULL,
+    atime TIMESTAMPTZ NOT NULL,
+    perm TEXT,
+    is_symlink BOOLEAN NOT NULL,
+    is_dir BOOLEAN NOT NULL,
+    is_regular_file BOOLEAN NOT NULL,
+    hard_links SMALLINT NOT NULL,
+    current_state SMALLINT NOT NULL
+"#;
+
+pub struct PostgresDatabase {
+    config: tokio_postgres::Config,
+    /// 首次使用时建立连接；COPY和事务需要独占连接，因此以互斥锁串行化
+    client: Mutex<Option<Client>>,
+    job_id: String,
+    scan_temp_table_name: Option<String>,
+}
+
+impl PostgresDatabase {
+    pub fn new(config: PostgresConfig, job_id: String) -> Result<Self> {
+        let mut pg_config: tokio_postgres::Config = config
+            .dsn
+            .parse()
+            .map_err(|e| DatabaseError::ConfigError(format!("Invalid PostgreSQL DSN: {}", e)))?;
+        pg_config
+            .dbname(&config.database)
+            .user(&config.username)
+            .connect_timeout(Duration::from_secs(config.dial_timeout.max(1) as u64));
+
+        // 可选的密码配置
+        if let Some(password) = &config.password
+            && !password.is_empty()
+        {
+            pg_config.password(password);
+        }
+
+        Ok(Self {
+            config: pg_config,
+            client: Mutex::new(None),
+            job_id,
+            scan_temp_table_name: None,
+        })
+    }
+
+    /// 获取连接，未连接或连接已关闭时重新连接
+    async fn client(&self) -> Result<MappedMutexGuard<'_, Client>> {
+        let mut guard = self.client.lock().await;
+        let client = match guard.take() {
+            Some(client) if !client.is_closed() => client,
+            _ => {
+                let (client, connection) = self
+                    .config
+                    .connect(NoTls)
+                    .await
+                    .map_err(|e| DatabaseError::ConnectionError(e.to_string()))?;
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        debug!("PostgreSQL connection closed: {}", e);
+                    }
+                });
+                client
+            }
+        };
+        Ok(MutexGuard::map(guard, |slot| slot.insert(client)))
+    }
+
+    /// 创建主扫描表
+    /// 以path为主键，重复写入同一路径时更新旧记录；mtime上建立索引便于增量查询
+    pub async fn create_scan_base_table(&self) -> Result<()> {
+        let table_name = get_scan_base_table_name(&self.job_id);
+        let existed = self.table_exists(&table_name).await?;
+        let create_table_sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} ({}, PRIMARY KEY (path))",
+            table_name,
+            migration::columns_definition(FILE_SCAN_COLUMNS_DEFINITION, "postgres")
+        );
+        let create_index_sql = format!(
+            "CREATE INDEX IF NOT EXISTS {0}_mtime_idx ON {0} (mtime)",
+            table_name
+        );
+
+        debug!("Creating PostgreSQL scan base table: {}", table_name);
+        self.execute(&create_table_sql, &[]).await?;
+        self.execute(&create_index_sql, &[]).await?;
+
+        migration::prepare_scan_base_table(self, &table_name, existed).await?;
+
+        Ok(())
+    }
+
+    /// 创建状态表
+    pub async fn create_scan_state_table(&self) -> Result<()> {
+        let table_name = get_scan_state_table_name(&self.job_id);
+        let create_table_sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (id SMALLINT PRIMARY KEY, origin_state SMALLINT NOT NULL)",
+            table_name
+        );
+
+        debug!("Creating PostgreSQL scan state table: {}", table_name);
+        self.execute(&create_table_sql, &[]).await?;
+
+        Ok(())
+    }
+
+    /// 根据表名删除指定表
+    pub async fn drop_table_by_name(&self, table_name: &str) -> Result<()> {
+        let drop_table_sql = format!("DROP TABLE IF EXISTS {}", table_name);
+
+        debug!("Dropping PostgreSQL table: {}", table_name);
+        self.execute(&drop_table_sql, &[]).await?;
+
+        Ok(())
+    }
+
+    /// 在事务中通过二进制COPY写入记录
+    async fn copy_records(
+        transaction: &Transaction<'_>, table_name: &str, records: &[FileScanRecord],
+    ) -> Result<()> {
+        let copy_sql = format!(
+            "COPY {} ({}) FROM STDIN (FORMAT binary)",
+            table_name,
+            FILE_SCAN_COLUMNS.join(", ")
+        );
+        let sink = transaction.copy_in(&copy_sql).await?;
+        let mut writer = pin!(BinaryCopyInWriter::new(sink, &FILE_SCAN_COLUMN_TYPES));
+
+        for record in records {
+            let size = record.size as i64;
+            let ctime = from_epoch_millis(record.ctime);
+            let mtime = from_epoch_millis(record.mtime);
+            let atime = from_epoch_millis(record.atime);
+            let hard_links = record.hard_links as i16;
+            let current_state = record.current_state as i16;
+            let row: [&(dyn ToSql + Sync); 13] = [
+                &record.path,
+                &size,
+                &record.ext,
+                &ctime,
+                &mtime,
+                &atime,
+                &record.perm,
+                &record.is_symlink,
+                &record.is_dir,
+                &record.is_regular_file,
+                &hard_links,
+                &current_state,
+                &record.checksum,
+            ];
+            writer.as_mut().write(&row).await?;
+        }
+
+        writer.finish().await?;
+        Ok(())
+    }
+
+    /// 通过临时表COPY后合并到base表，路径冲突时以新记录为准
+    async fn upsert_base_records(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        let base_table_name = get_scan_base_table_name(&self.job_id);
+
+        if records.is_empty() {
+            debug!("No events to insert");
+            return Ok(());
+        }
+
+        let record_count = records.len();
+        let staging_table_name = format!("{}_staging", base_table_name);
+        let updates = FILE_SCAN_COLUMNS[1..]
+            .iter()
+            .map(|column| format!("{0} = EXCLUDED.{0}", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut client = self.client().await?;
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute(&format!(
+                "CREATE TEMP TABLE IF NOT EXISTS {0} (LIKE {1} INCLUDING DEFAULTS) ON COMMIT DELETE ROWS",
+                staging_table_name, base_table_name
+            ))
+            .await?;
+        Self::copy_records(&transaction, &staging_table_name, &records).await?;
+        // 同一批次内的重复路径只保留最后一条
+        transaction
+            .batch_execute(&format!(
+                "INSERT INTO {0} ({2}) SELECT DISTINCT ON (path) {2} FROM {1} ORDER BY path, ctid DESC \
+                 ON CONFLICT (path) DO UPDATE SET {3}",
+                base_table_name,
+                staging_table_name,
+                FILE_SCAN_COLUMNS.join(", "),
+                updates
+            ))
+            .await?;
+        transaction.commit().await?;
+
+        debug!(
+            "Successfully inserted {} events to base table",
+            record_count
+        );
+        Ok(())
+    }
+}
+
+/// 将Unix毫秒时间戳转换为SystemTime
+fn from_epoch_millis(millis: i64) -> SystemTime {
+    if millis >= 0 {
+        UNIX_EPOCH + Duration::from_millis(millis as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_millis(millis.unsigned_abs())
+    }
+}
+
+/// 将SystemTime转换为Unix毫秒时间戳
+fn to_epoch_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as i64,
+        Err(e) => -(e.duration().as_millis() as i64),
+    }
+}
+
+/// 过滤参数占位符，显式转换类型以便使用统一的JSON参数绑定
+fn placeholder(column: &'static str, index: usize) -> String {
+    match column {
+        "ctime" | "mtime" | "atime" => format!("to_timestamp(${}::int8 / 1000.0)", index),
+        "size" | "hard_links" | "current_state" => format!("${}::int8", index),
+        "is_symlink" | "is_dir" | "is_regular_file" => format!("${}::bool", index),
+        _ => format!("${}::text", index),
+    }
+}
+
+/// 将JSON参数转换为可绑定的参数
+fn to_sql_param(value: &Value) -> Box<dyn ToSql + Sync + Send> {
+    match value {
+        Value::Null => Box::new(None::<String>),
+        Value::Bool(b) => Box::new(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Box::new(i),
+            None => Box::new(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => Box::new(s.clone()),
+        other => Box::new(other.to_string()),
+    }
+}
+
+/// 将查询结果行转换为JSON对象，时间列转换为Unix毫秒时间戳
+fn row_to_json(row: &Row) -> Result<Value> {
+    let mut object = serde_json::Map::with_capacity(row.len());
+    for (index, column) in row.columns().iter().enumerate() {
+        let value = match *column.type_() {
+            Type::BOOL => row.try_get::<_, Option<bool>>(index)?.map(Value::from),
+            Type::INT2 => row.try_get::<_, Option<i16>>(index)?.map(Value::from),
+            Type::INT4 => row.try_get::<_, Option<i32>>(index)?.map(Value::from),
+            Type::INT8 => row.try_get::<_, Option<i64>>(index)?.map(Value::from),
+            Type::FLOAT4 => row.try_get::<_, Option<f32>>(index)?.map(Value::from),
+            Type::FLOAT8 => row.try_get::<_, Option<f64>>(index)?.map(Value::from),
+            Type::TIMESTAMP | Type::TIMESTAMPTZ => row
+                .try_get::<_, Option<SystemTime>>(index)?
+                .map(|t| Value::from(to_epoch_millis(t))),
+            _ => row
+                .try_get::<_, Option<String>>(index)
+                .map_err(|e| {
+                    DatabaseError::QueryError(format!(
+                        "Unsupported column type {} for {}: {}",
+                        column.type_(),
+                        column.name(),
+                        e
+                    ))
+                })?
+                .map(Value::from),
+        };
+        object.insert(column.name().to_string(), value.unwrap_or(Value::Null));
+    }
+    Ok(Value::Object(object))
+}
+
+/// 按查询列读取记录，未查询的字段保持默认值
+fn record_from_row(row: &Row, columns: &[&str]) -> Result<FileScanRecord> {
+    let mut record = FileScanRecord {
+        path: String::new(),
+        size: 0,
+        ext: None,
+        ctime: 0,
+        mtime: 0,
+        atime: 0,
+        perm: None,
+        is_symlink: false,
+        is_dir: false,
+        is_regular_file: false,
+        hard_links: 0,
+        current_state: 0,
+        checksum: None,
+    };
+
+    for (index, column) in columns.iter().enumerate() {
+        match *column {
+            "path" => record.path = row.try_get(index)?,
+            "size" => record.size = row.try_get::<_, i64>(index)? as u64,
+            "ext" => record.ext = row.try_get(index)?,
+            "ctime" => record.ctime = to_epoch_millis(row.try_get(index)?),
+            "mtime" => record.mtime = to_epoch_millis(row.try_get(index)?),
+            "atime" => record.atime = to_epoch_millis(row.try_get(index)?),
+            "perm" => record.perm = row.try_get(index)?,
+            "is_symlink" => record.is_symlink = row.try_get(index)?,
+            "is_dir" => record.is_dir = row.try_get(index)?,
+            "is_regular_file" => record.is_regular_file = row.try_get(index)?,
+            "hard_links" => record.hard_links = row.try_get::<_, i16>(index)? as u8,
+            "current_state" => record.current_state = row.try_get::<_, i16>(index)? as u8,
+            "checksum" => record.checksum = row.try_get(index)?,
+            _ => {}
+        }
+    }
+
+    Ok(record)
+}
+
+#[async_trait]
+impl Database for PostgresDatabase {
+    async fn ping(&self) -> Result<()> {
+        let client = self.client().await?;
+        client
+            .simple_query("SELECT 1")
+            .await
+            .map_err(|e| DatabaseError::ConnectionError(e.to_string()))?;
+
+        debug!("PostgreSQL connection established successfully");
+        Ok(())
+    }
+
+    async fn create_table(&self, table_name: &str) -> Result<()> {
+        // 根据表名调用相应的创建方法
+        match table_name {
+            SCAN_BASE_TABLE_BASE_NAME => self.create_scan_base_table().await,
+            SCAN_STATE_TABLE_BASE_NAME => self.create_scan_state_table().await,
+            SCAN_DIRS_TABLE_BASE_NAME => {
+                rollup::create_dirs_table(self, &get_scan_dirs_table_name(&self.job_id)).await
+            }
+            _ => Err(DatabaseError::UnsupportedType(format!(
+                "Unknown table: {}",
+                table_name
+            ))),
+        }
+    }
+
+    async fn drop_table(&self, table_name: &str) -> Result<()> {
+        // 根据表名调用相应的删除方法
+        match table_name {
+            SCAN_BASE_TABLE_BASE_NAME => {
+                self.drop_table_by_name(&get_scan_base_table_name(&self.job_id))
+                    .await
+            }
+            SCAN_STATE_TABLE_BASE_NAME => {
+                self.drop_table_by_name(&get_scan_state_table_name(&self.job_id))
+                    .await
+            }
+            SCAN_DIRS_TABLE_BASE_NAME => {
+                self.drop_table_by_name(&get_scan_dirs_table_name(&self.job_id))
+                    .await
+            }
+            _ => self.drop_table_by_name(table_name).await,
+        }
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> Result<QueryResult> {
+        debug!("Executing PostgreSQL statement: {}", sql);
+
+        let client = self.client().await?;
+
+        // DDL等无参数语句使用简单协议执行
+        if params.is_empty() && !sql.trim_start().to_uppercase().starts_with("SELECT") {
+            client.batch_execute(sql).await?;
+            return Ok(QueryResult {
+                rows: Vec::new(),
+                affected_rows: 0,
+                last_insert_id: None,
+            });
+        }
+
+        let params: Vec<Box<dyn ToSql + Sync + Send>> = params.iter().map(to_sql_param).collect();
+        let params: Vec<&(dyn ToSql + Sync)> = params
+            .iter()
+            .map(|p| p.as_ref() as &(dyn ToSql + Sync))
+            .collect();
+        let statement = client
+            .prepare(sql)
+            .await
+            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+
+        // 有结果列的语句返回查询结果，否则返回影响行数
+        if statement.columns().is_empty() {
+            let affected_rows = client.execute(&statement, &params).await?;
+            Ok(QueryResult {
+                rows: Vec::new(),
+                affected_rows,
+                last_insert_id: None,
+            })
+        } else {
+            let rows = client
+                .query(&statement, &params)
+                .await?
+                .iter()
+                .map(row_to_json)
+                .collect::<Result<Vec<_>>>()?;
+            Ok(QueryResult {
+                rows,
+                affected_rows: 0,
+                last_insert_id: None,
+            })
+        }
+    }
+
+    async fn table_exists(&self, table_name: &str) -> Result<bool> {
+        debug!("Checking if PostgreSQL table exists: {}", table_name);
+
+        // 未加引号的标识符会被折叠为小写
+        let client = self.client().await?;
+        let row = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM pg_catalog.pg_tables \
+                 WHERE schemaname = current_schema() AND tablename = lower($1))",
+                &[&table_name],
+            )
+            .await
+            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+
+        Ok(row.get(0))
+    }
+
+    async fn close(&self) -> Result<()> {
+        debug!("Closing PostgreSQL connection...");
+        // 释放Client后后台连接任务随之结束
+        self.client.lock().await.take();
+        Ok(())
+    }
+
+    fn database_type(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn create_scan_temporary_table(&mut self) -> Result<()> {
+        let temp_table_name = generate_scan_temp_table_name();
+        let create_table_sql = format!(
+            "CREATE UNLOGGED TABLE IF NOT EXISTS {} ({})",
+            temp_table_name,
+            migration::columns_definition(FILE_SCAN_COLUMNS_DEFINITION, "postgres")
+        );
+
+        debug!(
+            "Creating PostgreSQL scan temporary table: {}",
+            temp_table_name
+        );
+        self.execute(&create_table_sql, &[]).await?;
+
+        self.scan_temp_table_name = Some(temp_table_name);
+        Ok(())
+    }
+
+    async fn drop_scan_temporary_table(&mut self) -> Result<()> {
+        if let Some(temp_table_name) = self.scan_temp_table_name.take() {
+            self.drop_table_by_name(&temp_table_name).await?;
+        } else {
+            debug!("No temporary table to drop");
+        }
+        Ok(())
+    }
+
+    async fn batch_insert_temp_record_sync(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        let temp_table_name = self.scan_temp_table_name.as_deref().ok_or_else(|| {
+            DatabaseError::UnsupportedType("No temporary table available".to_string())
+        })?;
+
+        if records.is_empty() {
+            debug!("No events to insert");
+            return Ok(());
+        }
+
+        let mut client = self.client().await?;
+        let transaction = client.transaction().await?;
+        Self::copy_records(&transaction, temp_table_name, &records).await?;
+        transaction.commit().await?;
+
+        debug!(
+            "Successfully inserted {} events to temporary table",
+            records.len()
+        );
+        Ok(())
+    }
+
+    fn get_scan_temp_table_name(&self) -> Option<&str> {
+        self.scan_temp_table_name.as_deref()
+    }
+
+    async fn batch_insert_base_record_sync(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        self.upsert_base_records(records).await
+    }
+
+    /// COPY在单个事务中完成，与同步插入相同
+    async fn batch_insert_base_record_async(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        self.upsert_base_records(records).await
+    }
+
+    /// 服务端按需推送结果行，消费端处理慢时连接读取随之暂停
+    /// 流未消费完或未释放前，同一连接上的其他操作需等待
+    async fn query_stream(&self, query: &ScanQuery) -> Result<RecordStream> {
+        let table_name = get_scan_base_table_name(&self.job_id);
+        let columns = query.select_columns()?;
+        let (where_clause, params) = query.where_clause(placeholder)?;
+        let sql = format!(
+            "SELECT {} FROM {}{}{}",
+            columns.join(", "),
+            table_name,
+            where_clause,
+            query.order_limit_clause(None)?
+        );
+        debug!("Streaming PostgreSQL query: {}", sql);
+
+        let params: Vec<Box<dyn ToSql + Sync + Send>> = params.iter().map(to_sql_param).collect();
+        let client = self.client().await?;
+        let rows = client
+            .query_raw(
+                &sql,
+                params.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync)),
+            )
+            .await
+            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+
+        Ok(Box::pin(rows.map(move |row| {
+            row.map_err(DatabaseError::from)
+                .and_then(|row| record_from_row(&row, &columns))
+        })))
+    }
+
+    /// 查询scan_state表，返回id=1的origin_state值
+    /// 当记录不存在时返回错误
+    async fn query_scan_state_table(&self) -> Result<u8> {
+        let table_name = get_scan_state_table_name(&self.job_id);
+        let query = format!("SELECT origin_state FROM {} WHERE id = 1", table_name);
+
+        let client = self.client().await?;
+        let row = client
+            .query_opt(&query, &[])
+            .await
+            .map_err(|e| {
+                DatabaseError::QueryError(format!("Failed to query scan_state table: {}", e))
+            })?
+            .ok_or_else(|| {
+                DatabaseError::QueryError("No scan state record found for id=1".to_string())
+            })?;
+
+        Ok(row.try_get::<_, i16>(0)? as u8)
+    }
+
+    /// 切换scan_state表状态
+    async fn switch_scan_state(&self) -> Result<()> {
+        let current_state = self.query_scan_state_table().await?;
+        let new_state = 1 - current_state;
+        self.insert_scan_state_sync(new_state).await?;
+
+        debug!("Switched scan state: {} -> {}", current_state, new_state);
+        Ok(())
+    }
+
+    /// 插入或更新scan_state表中id=1的记录
+    async fn insert_scan_state_sync(&self, origin_state: u8) -> Result<()> {
+        let table_name = get_scan_state_table_name(&self.job_id);
+        let insert_sql = format!(
+            "INSERT INTO {} (id, origin_state) VALUES (1, $1) \
+             ON CONFLICT (id) DO UPDATE SET origin_state = EXCLUDED.origin_state",
+            table_name
+        );
+
+        debug!("Inserting scan state: id=1, origin_state={}", origin_state);
+
+        let client = self.client().await?;
+        client
+            .execute(&insert_sql, &[&(origin_state as i16)])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn replace_dir_rollups(&self, rollups: &[DirRollup]) -> Result<()> {
+        rollup::replace_dir_rollups(self, &get_scan_dirs_table_name(&self.job_id), rollups).await
+    }
+
+    async fn query_dir_rollups(&self, max_depth: Option<u32>) -> Result<Vec<DirRollup>> {
+        rollup::query_dir_rollups(self, &get_scan_dirs_table_name(&self.job_id), max_depth).await
+    }
+}
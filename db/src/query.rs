@@ -0,0 +1,220 @@
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::pin::Pin;
+
+use crate::error::{DatabaseError, Result};
+use crate::traits::FileScanRecord;
+
+/// 文件扫描记录的列名，顺序与 `FileScanRecord` 字段一致
+pub const FILE_SCAN_COLUMNS: [&str; 13] = [
+    "path",
+    "size",
+    "ext",
+    "ctime",
+    "mtime",
+    "atime",
+    "perm",
+    "is_symlink",
+    "is_dir",
+    "is_regular_file",
+    "hard_links",
+    "current_state",
+    "checksum",
+];
+
+/// 逐行返回扫描记录的异步流
+pub type RecordStream = Pin<Box<dyn Stream<Item = Result<FileScanRecord>> + Send>>;
+
+/// 过滤条件的比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// SQL LIKE模式匹配，仅适用于文本列
+    Like,
+}
+
+impl FilterOp {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq => "=",
+            FilterOp::Ne => "<>",
+            FilterOp::Lt => "<",
+            FilterOp::Le => "<=",
+            FilterOp::Gt => ">",
+            FilterOp::Ge => ">=",
+            FilterOp::Like => "LIKE",
+        }
+    }
+}
+
+/// 单个过滤条件，时间列的值为Unix毫秒时间戳
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Filter {
+    pub column: String,
+    pub op: FilterOp,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBy {
+    pub column: String,
+    pub order: SortOrder,
+}
+
+/// scan_base表的结构化查询条件
+/// 列名均需为 `FILE_SCAN_COLUMNS` 之一，未选择的列在结果中保持默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanQuery {
+    /// 查询列，为空时查询全部列
+    pub columns: Vec<String>,
+    /// 过滤条件，多个条件之间为AND关系
+    pub filters: Vec<Filter>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+impl ScanQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    pub fn filter(mut self, column: &str, op: FilterOp, value: impl Into<Value>) -> Self {
+        self.filters.push(Filter {
+            column: column.to_string(),
+            op,
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn order_by(mut self, column: &str, order: SortOrder) -> Self {
+        self.order_by.push(OrderBy {
+            column: column.to_string(),
+            order,
+        });
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// 校验并返回查询列
+    pub(crate) fn select_columns(&self) -> Result<Vec<&'static str>> {
+        if self.columns.is_empty() {
+            return Ok(FILE_SCAN_COLUMNS.to_vec());
+        }
+        self.columns.iter().map(|c| validate_column(c)).collect()
+    }
+
+    /// 生成WHERE子句及按顺序绑定的参数
+    /// `placeholder` 根据列名和参数序号（从1开始）生成占位符表达式
+    pub(crate) fn where_clause<F>(&self, placeholder: F) -> Result<(String, Vec<Value>)>
+    where
+        F: Fn(&'static str, usize) -> String,
+    {
+        if self.filters.is_empty() {
+            return Ok((String::new(), Vec::new()));
+        }
+
+        let mut conditions = Vec::with_capacity(self.filters.len());
+        let mut params = Vec::with_capacity(self.filters.len());
+        for (index, filter) in self.filters.iter().enumerate() {
+            let column = validate_column(&filter.column)?;
+            if filter.value.is_null() {
+                // NULL无法通过比较运算匹配，仅支持判断是否为空
+                let condition = match filter.op {
+                    FilterOp::Eq => "IS NULL",
+                    FilterOp::Ne => "IS NOT NULL",
+                    _ => {
+                        return Err(DatabaseError::QueryError(format!(
+                            "Unsupported NULL comparison on column: {}",
+                            column
+                        )));
+                    }
+                };
+                conditions.push(format!("{} {}", column, condition));
+                continue;
+            }
+            conditions.push(format!(
+                "{} {} {}",
+                column,
+                filter.op.as_sql(),
+                placeholder(column, index + 1)
+            ));
+            params.push(filter.value.clone());
+        }
+
+        Ok((format!(" WHERE {}", conditions.join(" AND ")), params))
+    }
+
+    /// 生成ORDER BY、LIMIT和OFFSET子句
+    /// `unbounded_limit` 用于只指定offset而后端要求同时给出LIMIT的情况
+    pub(crate) fn order_limit_clause(&self, unbounded_limit: Option<&str>) -> Result<String> {
+        let mut clause = String::new();
+
+        if !self.order_by.is_empty() {
+            let order_by = self
+                .order_by
+                .iter()
+                .map(|o| {
+                    let direction = match o.order {
+                        SortOrder::Asc => "ASC",
+                        SortOrder::Desc => "DESC",
+                    };
+                    validate_column(&o.column).map(|c| format!("{} {}", c, direction))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            clause.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+        }
+
+        match (self.limit, unbounded_limit) {
+            (Some(limit), _) => clause.push_str(&format!(" LIMIT {}", limit)),
+            (None, Some(unbounded)) if self.offset.is_some() => {
+                clause.push_str(&format!(" LIMIT {}", unbounded))
+            }
+            _ => {}
+        }
+        if let Some(offset) = self.offset {
+            clause.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        Ok(clause)
+    }
+}
+
+/// 校验列名，防止拼接到SQL中的列名被注入
+pub(crate) fn validate_column(column: &str) -> Result<&'static str> {
+    FILE_SCAN_COLUMNS
+        .iter()
+        .find(|c| **c == column)
+        .copied()
+        .ok_or_else(|| DatabaseError::QueryError(format!("Unknown column: {}", column)))
+}
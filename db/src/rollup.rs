@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use slog_scope::debug;
+
+use crate::dialect::Dialect;
+use crate::error::{DatabaseError, Result};
+use crate::traits::Database;
+
+/// 每条INSERT语句写入的目录行数
+const INSERT_CHUNK_SIZE: usize = 500;
+
+/// 目录汇总：本目录直接包含的条目统计，以及包含全部子目录的递归统计
+/// 时间均为Unix毫秒时间戳，子树中没有文件时为0
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirRollup {
+    /// 相对扫描根目录的路径，根目录为空字符串
+    pub path: String,
+    /// 相对扫描根目录的深度，根目录为0
+    pub depth: u32,
+    pub own_files: u64,
+    pub own_dirs: u64,
+    pub own_bytes: u64,
+    pub total_files: u64,
+    pub total_dirs: u64,
+    pub total_bytes: u64,
+    pub newest_mtime: i64,
+    pub oldest_atime: i64,
+}
+
+const DIR_COLUMNS: [&str; 10] = [
+    "path",
+    "depth",
+    "own_files",
+    "own_dirs",
+    "own_bytes",
+    "total_files",
+    "total_dirs",
+    "total_bytes",
+    "newest_mtime",
+    "oldest_atime",
+];
+
+/// 创建目录汇总表
+pub(crate) async fn create_dirs_table(db: &dyn Database, table: &str) -> Result<()> {
+    let dialect = Dialect::of(db)?;
+    let sql = match dialect {
+        Dialect::ClickHouse => format!(
+            "CREATE TABLE IF NOT EXISTS {} (path String, depth UInt32, own_files UInt64, own_dirs UInt64, \
+             own_bytes UInt64, total_files UInt64, total_dirs UInt64, total_bytes UInt64, \
+             newest_mtime Int64, oldest_atime Int64) ENGINE = MergeTree() ORDER BY (path)",
+            table
+        ),
+        Dialect::Sqlite | Dialect::Postgres => {
+            let int = if dialect == Dialect::Postgres {
+                "BIGINT NOT NULL"
+            } else {
+                "INTEGER NOT NULL"
+            };
+            format!(
+                "CREATE TABLE IF NOT EXISTS {0} (path TEXT PRIMARY KEY, depth {1}, own_files {1}, \
+                 own_dirs {1}, own_bytes {1}, total_files {1}, total_dirs {1}, total_bytes {1}, \
+                 newest_mtime {1}, oldest_atime {1})",
+                table, int
+            )
+        }
+    };
+
+    debug!("Creating directory rollup table: {}", table);
+    db.execute(&sql, &[]).await?;
+    Ok(())
+}
+
+/// 以本次扫描的汇总替换表中的全部目录行
+pub(crate) async fn replace_dir_rollups(
+    db: &dyn Database, table: &str, rollups: &[DirRollup],
+) -> Result<()> {
+    let dialect = Dialect::of(db)?;
+    let clear_sql = match dialect {
+        Dialect::ClickHouse => format!("TRUNCATE TABLE {}", table),
+        Dialect::Sqlite | Dialect::Postgres => format!("DELETE FROM {}", table),
+    };
+    db.execute(&clear_sql, &[]).await?;
+
+    for chunk in rollups.chunks(INSERT_CHUNK_SIZE) {
+        let rows = (0..chunk.len())
+            .map(|row| {
+                let placeholders = (1..=DIR_COLUMNS.len())
+                    .map(|column| dialect.placeholder(row * DIR_COLUMNS.len() + column))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("({})", placeholders)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            table,
+            DIR_COLUMNS.join(", "),
+            rows
+        );
+        let params: Vec<Value> = chunk
+            .iter()
+            .flat_map(|rollup| {
+                [
+                    json!(rollup.path),
+                    json!(rollup.depth),
+                    json!(rollup.own_files),
+                    json!(rollup.own_dirs),
+                    json!(rollup.own_bytes),
+                    json!(rollup.total_files),
+                    json!(rollup.total_dirs),
+                    json!(rollup.total_bytes),
+                    json!(rollup.newest_mtime),
+                    json!(rollup.oldest_atime),
+                ]
+            })
+            .collect();
+        db.execute(&sql, &params).await?;
+    }
+
+    debug!("Stored {} directory rollups in {}", rollups.len(), table);
+    Ok(())
+}
+
+/// 按路径顺序查询目录汇总，`max_depth` 限制返回的最大深度
+pub(crate) async fn query_dir_rollups(
+    db: &dyn Database, table: &str, max_depth: Option<u32>,
+) -> Result<Vec<DirRollup>> {
+    let dialect = Dialect::of(db)?;
+    let (where_clause, params) = match max_depth {
+        Some(depth) => (
+            format!(" WHERE depth <= {}", dialect.placeholder(1)),
+            vec![json!(depth)],
+        ),
+        None => (String::new(), Vec::new()),
+    };
+    let sql = format!(
+        "SELECT {} FROM {}{} ORDER BY path",
+        DIR_COLUMNS.join(", "),
+        table,
+        where_clause
+    );
+
+    db.execute(&sql, &params)
+        .await?
+        .rows
+        .into_iter()
+        .map(|row| {
+            serde_json::from_value(row)
+                .map_err(|e| DatabaseError::QueryError(format!("Invalid directory rollup: {}", e)))
+        })
+        .collect()
+}
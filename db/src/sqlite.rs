@@ -0,0 +1,538 @@
+use async_trait::async_trait;
+use futures::stream;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, Row, params_from_iter};
+use serde_json::Value;
+use slog_scope::debug;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use crate::config::SqliteConfig;
+use crate::error::{DatabaseError, Result};
+use crate::migration;
+use crate::query::{FILE_SCAN_COLUMNS, RecordStream, ScanQuery};
+use crate::rollup::{self, DirRollup};
+use crate::traits::FileScanRecord;
+use crate::traits::{Database, QueryResult};
+use crate::{SCAN_BASE_TABLE_BASE_NAME, SCAN_DIRS_TABLE_BASE_NAME, SCAN_STATE_TABLE_BASE_NAME};
+use crate::{
+    generate_scan_temp_table_name, get_scan_base_table_name, get_scan_dirs_table_name,
+    get_scan_state_table_name,
+};
+
+/// 流式查询时缓冲的记录数
+const STREAM_BUFFER_SIZE: usize = 1024;
+
+/// 文件扫描记录的标准列定义，时间列为Unix毫秒时间戳
+const FILE_SCAN_COLUMNS_DEFINITION: &str = r#"
+    path TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    ext TEXT,
+    ctime INTEGER NOT NULL,
+    mtime INTEGER NOT NULL,
+    atime INTEGER NOT NULL,
+    perm TEXT,
+    is_symlink INTEGER NOT NULL,
+    is_dir INTEGER NOT NULL,
+    is_regular_file INTEGER NOT NULL,
+    hard_links INTEGER NOT NULL,
+    current_state INTEGER NOT NULL
+"#;
+
+pub struct SqliteDatabase {
+    conn: Arc<Mutex<Connection>>,
+    job_id: String,
+    in_memory: bool,
+    scan_temp_table_name: Option<String>,
+}
+
+impl SqliteDatabase {
+    /// 打开（必要时创建）数据库文件
+    pub fn new(config: SqliteConfig, job_id: String) -> Result<Self> {
+        if let Some(parent) = Path::new(&config.path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(&config.path)?;
+        // WAL模式下读写互不阻塞，批量写入性能更好
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+        debug!("Opened SQLite database: {}", config.path);
+        Ok(Self::with_connection(conn, job_id, false))
+    }
+
+    /// 创建内存数据库，进程退出后数据丢失
+    pub fn new_in_memory(job_id: String) -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        Ok(Self::with_connection(conn, job_id, true))
+    }
+
+    fn with_connection(conn: Connection, job_id: String, in_memory: bool) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+            job_id,
+            in_memory,
+            scan_temp_table_name: None,
+        }
+    }
+
+    /// 在阻塞线程池中使用连接执行操作
+    async fn run<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().map_err(|_| {
+                DatabaseError::ConnectionError("SQLite connection lock poisoned".to_string())
+            })?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| DatabaseError::OperationError(e.to_string()))?
+    }
+
+    /// 创建主扫描表
+    /// 以path为主键，重复写入同一路径时替换旧记录
+    pub async fn create_scan_base_table(&self) -> Result<()> {
+        let table_name = get_scan_base_table_name(&self.job_id);
+        let existed = self.table_exists(&table_name).await?;
+        let create_table_sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} ({}, PRIMARY KEY (path))",
+            table_name,
+            migration::columns_definition(FILE_SCAN_COLUMNS_DEFINITION, "sqlite")
+        );
+
+        debug!("Creating SQLite scan base table: {}", table_name);
+        self.execute(&create_table_sql, &[]).await?;
+
+        migration::prepare_scan_base_table(self, &table_name, existed).await?;
+
+        Ok(())
+    }
+
+    /// 创建状态表
+    pub async fn create_scan_state_table(&self) -> Result<()> {
+        let table_name = get_scan_state_table_name(&self.job_id);
+        let create_table_sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (id INTEGER PRIMARY KEY, origin_state INTEGER NOT NULL)",
+            table_name
+        );
+
+        debug!("Creating SQLite scan state table: {}", table_name);
+        self.execute(&create_table_sql, &[]).await?;
+
+        Ok(())
+    }
+
+    /// 根据表名删除指定表
+    pub async fn drop_table_by_name(&self, table_name: &str) -> Result<()> {
+        let drop_table_sql = format!("DROP TABLE IF EXISTS {}", table_name);
+
+        debug!("Dropping SQLite table: {}", table_name);
+        self.execute(&drop_table_sql, &[]).await?;
+
+        Ok(())
+    }
+
+    /// 在单个事务中批量插入记录
+    async fn insert_records(
+        &self, table_name: String, records: Vec<FileScanRecord>, replace: bool,
+    ) -> Result<()> {
+        if records.is_empty() {
+            debug!("No events to insert");
+            return Ok(());
+        }
+
+        let record_count = records.len();
+        let insert_sql = format!(
+            "INSERT {}INTO {} ({}) VALUES ({})",
+            if replace { "OR REPLACE " } else { "" },
+            table_name,
+            FILE_SCAN_COLUMNS.join(", "),
+            vec!["?"; FILE_SCAN_COLUMNS.len()].join(", ")
+        );
+
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(&insert_sql)?;
+                for record in &records {
+                    stmt.execute(rusqlite::params![
+                        record.path,
+                        record.size as i64,
+                        record.ext,
+                        record.ctime,
+                        record.mtime,
+                        record.atime,
+                        record.perm,
+                        record.is_symlink,
+                        record.is_dir,
+                        record.is_regular_file,
+                        record.hard_links,
+                        record.current_state,
+                        record.checksum,
+                    ])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+
+        debug!(
+            "Successfully inserted {} events to {}",
+            record_count, table_name
+        );
+        Ok(())
+    }
+}
+
+/// 将JSON参数转换为SQLite值
+fn to_sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// 将SQLite值转换为JSON
+fn to_json_value(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => Value::from(b.to_vec()),
+    }
+}
+
+/// 按查询列读取记录，未查询的字段保持默认值
+fn record_from_row(row: &Row<'_>, columns: &[&str]) -> rusqlite::Result<FileScanRecord> {
+    let mut record = FileScanRecord {
+        path: String::new(),
+        size: 0,
+        ext: None,
+        ctime: 0,
+        mtime: 0,
+        atime: 0,
+        perm: None,
+        is_symlink: false,
+        is_dir: false,
+        is_regular_file: false,
+        hard_links: 0,
+        current_state: 0,
+        checksum: None,
+    };
+
+    for (index, column) in columns.iter().enumerate() {
+        match *column {
+            "path" => record.path = row.get(index)?,
+            "size" => record.size = row.get::<_, i64>(index)? as u64,
+            "ext" => record.ext = row.get(index)?,
+            "ctime" => record.ctime = row.get(index)?,
+            "mtime" => record.mtime = row.get(index)?,
+            "atime" => record.atime = row.get(index)?,
+            "perm" => record.perm = row.get(index)?,
+            "is_symlink" => record.is_symlink = row.get(index)?,
+            "is_dir" => record.is_dir = row.get(index)?,
+            "is_regular_file" => record.is_regular_file = row.get(index)?,
+            "hard_links" => record.hard_links = row.get(index)?,
+            "current_state" => record.current_state = row.get(index)?,
+            "checksum" => record.checksum = row.get(index)?,
+            _ => {}
+        }
+    }
+
+    Ok(record)
+}
+
+#[async_trait]
+impl Database for SqliteDatabase {
+    async fn ping(&self) -> Result<()> {
+        self.run(|conn| {
+            conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+                .map_err(|e| DatabaseError::ConnectionError(e.to_string()))
+        })
+        .await?;
+
+        debug!("SQLite connection established successfully");
+        Ok(())
+    }
+
+    async fn create_table(&self, table_name: &str) -> Result<()> {
+        // 根据表名调用相应的创建方法
+        match table_name {
+            SCAN_BASE_TABLE_BASE_NAME => self.create_scan_base_table().await,
+            SCAN_STATE_TABLE_BASE_NAME => self.create_scan_state_table().await,
+            SCAN_DIRS_TABLE_BASE_NAME => {
+                rollup::create_dirs_table(self, &get_scan_dirs_table_name(&self.job_id)).await
+            }
+            _ => Err(DatabaseError::UnsupportedType(format!(
+                "Unknown table: {}",
+                table_name
+            ))),
+        }
+    }
+
+    async fn drop_table(&self, table_name: &str) -> Result<()> {
+        // 根据表名调用相应的删除方法
+        match table_name {
+            SCAN_BASE_TABLE_BASE_NAME => {
+                self.drop_table_by_name(&get_scan_base_table_name(&self.job_id))
+                    .await
+            }
+            SCAN_STATE_TABLE_BASE_NAME => {
+                self.drop_table_by_name(&get_scan_state_table_name(&self.job_id))
+                    .await
+            }
+            SCAN_DIRS_TABLE_BASE_NAME => {
+                self.drop_table_by_name(&get_scan_dirs_table_name(&self.job_id))
+                    .await
+            }
+            _ => self.drop_table_by_name(table_name).await,
+        }
+    }
+
+    async fn execute(&self, sql: &str, params: &[Value]) -> Result<QueryResult> {
+        debug!("Executing SQLite statement: {}", sql);
+
+        let sql = sql.to_string();
+        let params: Vec<SqlValue> = params.iter().map(to_sql_value).collect();
+
+        self.run(move |conn| {
+            let mut stmt = conn
+                .prepare(&sql)
+                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+
+            // 有结果列的语句返回查询结果，否则返回影响行数
+            if stmt.column_count() > 0 {
+                let names: Vec<String> =
+                    stmt.column_names().iter().map(|s| s.to_string()).collect();
+                let mut rows = stmt.query(params_from_iter(params))?;
+                let mut result = Vec::new();
+                while let Some(row) = rows.next()? {
+                    let mut object = serde_json::Map::with_capacity(names.len());
+                    for (index, name) in names.iter().enumerate() {
+                        object.insert(name.clone(), to_json_value(row.get_ref(index)?));
+                    }
+                    result.push(Value::Object(object));
+                }
+
+                Ok(QueryResult {
+                    rows: result,
+                    affected_rows: 0,
+                    last_insert_id: None,
+                })
+            } else {
+                let affected_rows = stmt.execute(params_from_iter(params))? as u64;
+                Ok(QueryResult {
+                    rows: Vec::new(),
+                    affected_rows,
+                    last_insert_id: Some(conn.last_insert_rowid() as u64),
+                })
+            }
+        })
+        .await
+    }
+
+    async fn table_exists(&self, table_name: &str) -> Result<bool> {
+        debug!("Checking if SQLite table exists: {}", table_name);
+
+        let table_name = table_name.to_string();
+        self.run(move |conn| {
+            let count: i64 = conn.query_row(
+                "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [&table_name],
+                |row| row.get(0),
+            )?;
+            Ok(count > 0)
+        })
+        .await
+    }
+
+    async fn close(&self) -> Result<()> {
+        debug!("Closing SQLite connection...");
+        if !self.in_memory {
+            // 将WAL中的数据写回主文件
+            self.run(|conn| {
+                conn.pragma_update(None, "wal_checkpoint", "TRUNCATE")?;
+                Ok(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    fn database_type(&self) -> &'static str {
+        if self.in_memory {
+            "sqlite-memory"
+        } else {
+            "sqlite"
+        }
+    }
+
+    async fn create_scan_temporary_table(&mut self) -> Result<()> {
+        let temp_table_name = generate_scan_temp_table_name();
+        let create_table_sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            temp_table_name,
+            migration::columns_definition(FILE_SCAN_COLUMNS_DEFINITION, "sqlite")
+        );
+
+        debug!("Creating SQLite scan temporary table: {}", temp_table_name);
+        self.execute(&create_table_sql, &[]).await?;
+
+        self.scan_temp_table_name = Some(temp_table_name);
+        Ok(())
+    }
+
+    async fn drop_scan_temporary_table(&mut self) -> Result<()> {
+        if let Some(temp_table_name) = self.scan_temp_table_name.take() {
+            self.drop_table_by_name(&temp_table_name).await?;
+        } else {
+            debug!("No temporary table to drop");
+        }
+        Ok(())
+    }
+
+    async fn batch_insert_temp_record_sync(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        let temp_table_name = self.scan_temp_table_name.clone().ok_or_else(|| {
+            DatabaseError::UnsupportedType("No temporary table available".to_string())
+        })?;
+
+        self.insert_records(temp_table_name, records, false).await
+    }
+
+    fn get_scan_temp_table_name(&self) -> Option<&str> {
+        self.scan_temp_table_name.as_deref()
+    }
+
+    async fn batch_insert_base_record_sync(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        self.insert_records(get_scan_base_table_name(&self.job_id), records, true)
+            .await
+    }
+
+    /// SQLite写入本身在阻塞线程池中执行，与同步插入相同
+    async fn batch_insert_base_record_async(&self, records: Vec<FileScanRecord>) -> Result<()> {
+        self.insert_records(get_scan_base_table_name(&self.job_id), records, true)
+            .await
+    }
+
+    /// 在阻塞线程中逐行读取并通过有界通道发送，消费端处理慢时读取随之暂停
+    /// 流未消费完或未释放前会一直占用连接，其他操作需等待
+    async fn query_stream(&self, query: &ScanQuery) -> Result<RecordStream> {
+        let table_name = get_scan_base_table_name(&self.job_id);
+        let columns = query.select_columns()?;
+        let (where_clause, params) = query.where_clause(|_, _| "?".to_string())?;
+        let sql = format!(
+            "SELECT {} FROM {}{}{}",
+            columns.join(", "),
+            table_name,
+            where_clause,
+            query.order_limit_clause(Some("-1"))?
+        );
+        let params: Vec<SqlValue> = params.iter().map(to_sql_value).collect();
+
+        let (tx, rx) = mpsc::channel::<Result<FileScanRecord>>(STREAM_BUFFER_SIZE);
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = match conn.lock() {
+                Ok(conn) => conn,
+                Err(_) => {
+                    let _ = tx.blocking_send(Err(DatabaseError::ConnectionError(
+                        "SQLite connection lock poisoned".to_string(),
+                    )));
+                    return;
+                }
+            };
+            let result = conn
+                .prepare(&sql)
+                .map_err(|e| DatabaseError::QueryError(e.to_string()))
+                .and_then(|mut stmt| {
+                    let rows = stmt.query_map(params_from_iter(params), |row| {
+                        record_from_row(row, &columns)
+                    })?;
+                    for row in rows {
+                        // 接收端已释放时停止读取
+                        if tx.blocking_send(row.map_err(Into::into)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(())
+                });
+            if let Err(e) = result {
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+
+        Ok(Box::pin(stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        })))
+    }
+
+    /// 查询scan_state表，返回id=1的origin_state值
+    /// 当记录不存在时返回错误
+    async fn query_scan_state_table(&self) -> Result<u8> {
+        let table_name = get_scan_state_table_name(&self.job_id);
+        let query = format!("SELECT origin_state FROM {} WHERE id = 1", table_name);
+
+        self.run(move |conn| {
+            conn.query_row(&query, [], |row| row.get::<_, u8>(0))
+                .map_err(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => {
+                        DatabaseError::QueryError("No scan state record found for id=1".to_string())
+                    }
+                    _ => DatabaseError::QueryError(format!(
+                        "Failed to query scan_state table: {}",
+                        e
+                    )),
+                })
+        })
+        .await
+    }
+
+    /// 切换scan_state表状态
+    async fn switch_scan_state(&self) -> Result<()> {
+        let current_state = self.query_scan_state_table().await?;
+        let new_state = 1 - current_state;
+        self.insert_scan_state_sync(new_state).await?;
+
+        debug!("Switched scan state: {} -> {}", current_state, new_state);
+        Ok(())
+    }
+
+    /// 插入或替换scan_state表中id=1的记录
+    async fn insert_scan_state_sync(&self, origin_state: u8) -> Result<()> {
+        let table_name = get_scan_state_table_name(&self.job_id);
+        let insert_sql = format!(
+            "INSERT OR REPLACE INTO {} (id, origin_state) VALUES (1, ?1)",
+            table_name
+        );
+
+        debug!("Inserting scan state: id=1, origin_state={}", origin_state);
+        self.execute(&insert_sql, &[Value::from(origin_state)])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn replace_dir_rollups(&self, rollups: &[DirRollup]) -> Result<()> {
+        rollup::replace_dir_rollups(self, &get_scan_dirs_table_name(&self.job_id), rollups).await
+    }
+
+    async fn query_dir_rollups(&self, max_depth: Option<u32>) -> Result<Vec<DirRollup>> {
+        rollup::query_dir_rollups(self, &get_scan_dirs_table_name(&self.job_id), max_depth).await
+    }
+}
@@ -1,8 +1,11 @@
 use async_trait::async_trait;
+use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::error::Result;
+use crate::query::{RecordStream, ScanQuery};
+use crate::rollup::DirRollup;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResult {
@@ -26,6 +29,9 @@ pub struct FileScanRecord {
     pub is_regular_file: bool,
     pub hard_links: u8,
     pub current_state: u8,
+    /// 同步校验时计算的文件哈希，格式为 `算法:十六进制摘要`
+    #[serde(default)]
+    pub checksum: Option<String>,
 }
 
 #[async_trait]
@@ -39,7 +45,7 @@ pub trait Database: Send + Sync {
     /// Drop table by name
     async fn drop_table(&self, table_name: &str) -> Result<()>;
 
-    /// Execute a query without returning results (INSERT, UPDATE, DELETE)
+    /// Execute a statement; queries return their rows where the backend supports it
     async fn execute(&self, sql: &str, params: &[Value]) -> Result<QueryResult>;
 
     /// Check if table exists
@@ -69,8 +75,46 @@ pub trait Database: Send + Sync {
     /// 异步批量插入数据到base表
     async fn batch_insert_base_record_async(&self, records: Vec<FileScanRecord>) -> Result<()>;
 
+    /// 是否支持流式写入base表，支持时调用方应小批量持续写入而非在内存中累积大批次
+    fn supports_streaming_insert(&self) -> bool {
+        false
+    }
+
+    /// 流式写入base表，由后端按阈值提交，`records` 为空时只检查提交阈值
+    /// 返回true表示此前写入的全部记录均已提交；出错时未提交的记录全部丢弃，需由调用方重新写入
+    async fn stream_insert_base_records(&self, records: &[FileScanRecord]) -> Result<bool> {
+        self.batch_insert_base_record_sync(records.to_vec()).await?;
+        Ok(true)
+    }
+
+    /// 提交流式写入中尚未提交的记录
+    async fn flush_base_records(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 开始新一轮扫描的数据代次，之后的写入针对新代次，查询在提交前仍读取旧代次
+    /// 每个作业独立建表时扫描结果直接覆盖，无需处理
+    async fn start_scan_generation(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 提交本轮扫描的数据代次，应在全部记录写入成功后调用
+    /// 之后的查询读取新代次，超出保留数的旧代次被删除
+    async fn commit_scan_generation(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 流式查询scan_base表，结果逐行从数据库拉取，不会一次性加载到内存
+    async fn query_stream(&self, query: &ScanQuery) -> Result<RecordStream>;
+
     /// 查询scan_base表，支持指定列查询
-    async fn query_scan_base_table(&self, columns: &[&str]) -> Result<Vec<FileScanRecord>>;
+    /// 会将全部结果加载到内存，大表应使用 `query_stream`
+    async fn query_scan_base_table(&self, columns: &[&str]) -> Result<Vec<FileScanRecord>> {
+        self.query_stream(&ScanQuery::new().columns(columns))
+            .await?
+            .try_collect()
+            .await
+    }
 
     /// 查询scan_state表
     async fn query_scan_state_table(&self) -> Result<u8>;
@@ -79,4 +123,10 @@ pub trait Database: Send + Sync {
     async fn switch_scan_state(&self) -> Result<()>;
 
     async fn insert_scan_state_sync(&self, origin_state: u8) -> Result<()>;
+
+    /// 以本次扫描的目录汇总替换scan_dirs表内容
+    async fn replace_dir_rollups(&self, rollups: &[DirRollup]) -> Result<()>;
+
+    /// 查询scan_dirs表，`max_depth` 限制返回的最大目录深度
+    async fn query_dir_rollups(&self, max_depth: Option<u32>) -> Result<Vec<DirRollup>>;
 }
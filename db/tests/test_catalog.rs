@@ -0,0 +1,88 @@
+use db::catalog::{self, JobRun, RunStatus};
+use db::{Database, SqliteDatabase};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试运行开始和结束时写入作业目录和运行历史
+    #[tokio::test]
+    async fn test_record_run_lifecycle() {
+        let db = SqliteDatabase::new_in_memory("catalog".to_string()).unwrap();
+        catalog::ensure_catalog_tables(&db).await.unwrap();
+        // 重复创建不会报错
+        catalog::ensure_catalog_tables(&db).await.unwrap();
+
+        let mut run = JobRun::start("job1", "scan", r#"{"path":"/data"}"#.to_string());
+        catalog::record_run(&db, &run).await.unwrap();
+
+        let runs = catalog::list_runs(&db, Some("job1"), 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].status, RunStatus::Running);
+        assert_eq!(runs[0].finished_at, 0);
+
+        run.total_files = 3;
+        run.total_dirs = 1;
+        run.total_bytes = 42;
+        run.finish();
+        catalog::record_run(&db, &run).await.unwrap();
+
+        let runs = catalog::list_runs(&db, Some("job1"), 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].run_id, run.run_id);
+        assert_eq!(runs[0].status, RunStatus::Completed);
+        assert_eq!(runs[0].total_files, 3);
+        assert_eq!(runs[0].total_bytes, 42);
+        assert_eq!(runs[0].params, r#"{"path":"/data"}"#);
+        assert!(runs[0].finished_at >= runs[0].started_at);
+
+        let jobs = db
+            .execute(
+                "SELECT job_id, job_type, last_run_id, last_status FROM terrasync_jobs",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            jobs.rows,
+            vec![serde_json::json!({
+                "job_id": "job1",
+                "job_type": "scan",
+                "last_run_id": run.run_id,
+                "last_status": "completed",
+            })]
+        );
+    }
+
+    /// 测试失败运行和按作业过滤的历史查询
+    #[tokio::test]
+    async fn test_failed_run_and_history() {
+        let db = SqliteDatabase::new_in_memory("history".to_string()).unwrap();
+        catalog::ensure_catalog_tables(&db).await.unwrap();
+
+        let mut failed = JobRun::start("job1", "sync", "{}".to_string());
+        failed.started_at -= 1_000;
+        failed.errors = 1;
+        failed.error_message = "permission denied".to_string();
+        failed.finish();
+        catalog::record_run(&db, &failed).await.unwrap();
+        assert_eq!(failed.status, RunStatus::Failed);
+
+        let mut second = JobRun::start("job1", "sync", "{}".to_string());
+        second.finish();
+        catalog::record_run(&db, &second).await.unwrap();
+
+        let other = JobRun::start("job2", "scan", "{}".to_string());
+        catalog::record_run(&db, &other).await.unwrap();
+
+        // 按开始时间倒序返回
+        let runs = catalog::list_runs(&db, Some("job1"), 10).await.unwrap();
+        let ids: Vec<_> = runs.iter().map(|r| r.run_id.as_str()).collect();
+        assert_eq!(ids, vec![second.run_id.as_str(), failed.run_id.as_str()]);
+        assert_eq!(runs[1].error_message, "permission denied");
+        assert!(runs[1].duration_ms >= 1_000);
+
+        assert_eq!(catalog::list_runs(&db, None, 10).await.unwrap().len(), 3);
+        assert_eq!(catalog::list_runs(&db, None, 1).await.unwrap().len(), 1);
+    }
+}
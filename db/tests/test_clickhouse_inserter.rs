@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use clickhouse::test::{Mock, handlers};
+    use db::Database;
+    use db::clickhouse::ClickHouseDatabase;
+    use db::config::ClickHouseConfig;
+    use db::traits::FileScanRecord;
+    use std::time::Duration;
+
+    fn record(path: &str) -> FileScanRecord {
+        FileScanRecord {
+            path: path.to_string(),
+            size: 1,
+            ext: None,
+            ctime: 0,
+            mtime: 0,
+            atime: 0,
+            perm: None,
+            is_symlink: false,
+            is_dir: false,
+            is_regular_file: true,
+            hard_links: 1,
+            current_state: 0,
+            checksum: None,
+        }
+    }
+
+    fn setup_mock_db(mock: &Mock) -> ClickHouseDatabase {
+        let config = ClickHouseConfig {
+            dsn: mock.url().to_string(),
+            compression: false,
+            inserter_period: 10,
+            ..ClickHouseConfig::default()
+        };
+        ClickHouseDatabase::new(config, "inserter".to_string()).unwrap()
+    }
+
+    /// 测试没有新记录写入时，空写入也会在超过时间阈值后提交
+    #[tokio::test]
+    async fn test_stream_insert_commits_after_period() {
+        let mock = Mock::new();
+        let db = setup_mock_db(&mock);
+        let recording = mock.add(handlers::record::<FileScanRecord>());
+
+        // 未达到行数、字节数和时间阈值，记录保持未提交
+        let committed = db
+            .stream_insert_base_records(&[record("/a"), record("/b")])
+            .await
+            .unwrap();
+        assert!(!committed);
+        assert!(!db.stream_insert_base_records(&[]).await.unwrap());
+
+        tokio::time::pause();
+        tokio::time::advance(Duration::from_secs(11)).await;
+        tokio::time::resume();
+
+        assert!(db.stream_insert_base_records(&[]).await.unwrap());
+        let rows: Vec<FileScanRecord> = recording.collect().await;
+        let paths: Vec<_> = rows.into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["/a", "/b"]);
+    }
+
+    /// 测试flush无条件提交未提交的记录
+    #[tokio::test]
+    async fn test_flush_commits_pending_records() {
+        let mock = Mock::new();
+        let db = setup_mock_db(&mock);
+        let recording = mock.add(handlers::record::<FileScanRecord>());
+
+        assert!(
+            !db.stream_insert_base_records(&[record("/a")])
+                .await
+                .unwrap()
+        );
+        db.flush_base_records().await.unwrap();
+
+        let rows: Vec<FileScanRecord> = recording.collect().await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].path, "/a");
+    }
+}
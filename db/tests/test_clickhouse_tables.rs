@@ -30,9 +30,10 @@ mod tests {
             database: "default".to_string(),
             username: "default".to_string(),
             password: None,
+            ..ClickHouseConfig::default()
         };
 
-        ClickHouseDatabase::new(config, job_id.to_string())
+        ClickHouseDatabase::new(config, job_id.to_string()).unwrap()
     }
 
     // 测试清理辅助函数
@@ -166,8 +167,7 @@ mod tests {
         }
 
         // 先创建临时表
-        let _ = db
-            .create_scan_temporary_table()
+        db.create_scan_temporary_table()
             .await
             .expect("Failed to create temporary table");
 
@@ -351,6 +351,7 @@ mod tests {
                 is_regular_file: true,
                 current_state: 1,
                 hard_links: 1,
+                checksum: None,
             },
             FileScanRecord {
                 path: "/test/path/file2.jpg".to_string(),
@@ -365,6 +366,7 @@ mod tests {
                 is_regular_file: true,
                 current_state: 1,
                 hard_links: 2,
+                checksum: None,
             },
         ];
 
@@ -447,6 +449,7 @@ mod tests {
                 is_dir: false,
                 is_regular_file: true,
                 current_state: 1,
+                checksum: None,
             });
         }
 
@@ -522,4 +525,104 @@ mod tests {
         // 测试结束后清理
         let _ = cleanup_test_tables(&db, &job_id).await;
     }
+
+    #[tokio::test]
+    async fn test_shared_layout_generations() {
+        let job_id = generate_unique_job_id("test_shared");
+        let config = ClickHouseConfig {
+            dsn: "http://10.131.9.20:8123".to_string(),
+            table_layout: "shared".to_string(),
+            retain_generations: 2,
+            ..ClickHouseConfig::default()
+        };
+        let db = ClickHouseDatabase::new(config, job_id.clone()).unwrap();
+
+        if db.ping().await.is_err() {
+            println!("ClickHouse server not available, skipping test");
+            return;
+        }
+
+        let record = |path: &str| FileScanRecord {
+            path: path.to_string(),
+            size: 1,
+            ext: None,
+            ctime: 0,
+            mtime: 0,
+            atime: 0,
+            perm: None,
+            hard_links: 1,
+            is_symlink: false,
+            is_dir: false,
+            is_regular_file: true,
+            current_state: 0,
+            checksum: None,
+        };
+
+        db.create_scan_base_table().await.unwrap();
+        db.create_scan_state_table().await.unwrap();
+
+        let paths = || async {
+            let mut paths: Vec<_> = db
+                .query_scan_base_table(&["path"])
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        // 每次扫描写入新代次，提交后查询才切换到新代次
+        for generation in 1..=3 {
+            db.start_scan_generation().await.unwrap();
+            db.batch_insert_base_record_sync(vec![
+                record(&format!("/gen{}", generation)),
+                record("/common"),
+            ])
+            .await
+            .unwrap();
+            // 同一代次重复写入按版本去重
+            db.batch_insert_base_record_sync(vec![record("/common")])
+                .await
+                .unwrap();
+
+            if generation > 1 {
+                assert_eq!(
+                    paths().await,
+                    vec!["/common".to_string(), format!("/gen{}", generation - 1)]
+                );
+            }
+
+            db.commit_scan_generation().await.unwrap();
+            assert_eq!(
+                paths().await,
+                vec!["/common".to_string(), format!("/gen{}", generation)]
+            );
+        }
+
+        // 超出保留数的旧代次分区已被删除
+        let dropped = db.drop_generations(Some(1)).await.unwrap();
+        assert!(dropped.is_empty(), "Generation 1 should already be dropped");
+
+        db.drop_table(db::SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        assert!(
+            db.query_scan_base_table(&["path"])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_table_layout() {
+        let config = ClickHouseConfig {
+            table_layout: "per_host".to_string(),
+            ..ClickHouseConfig::default()
+        };
+        assert!(matches!(
+            ClickHouseDatabase::new(config, "layout".to_string()),
+            Err(db::error::DatabaseError::ConfigError(_))
+        ));
+    }
 }
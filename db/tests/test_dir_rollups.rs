@@ -0,0 +1,58 @@
+use db::{Database, DirRollup, SCAN_DIRS_TABLE_BASE_NAME, SqliteDatabase};
+
+fn test_rollup(path: &str, depth: u32, total_bytes: u64) -> DirRollup {
+    DirRollup {
+        path: path.to_string(),
+        depth,
+        own_files: 1,
+        own_bytes: total_bytes / 2,
+        total_files: 2,
+        total_bytes,
+        newest_mtime: 2_000,
+        oldest_atime: 1_000,
+        ..DirRollup::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试目录汇总的写入、按深度查询以及重新扫描时的替换
+    #[tokio::test]
+    async fn test_replace_and_query_dir_rollups() {
+        let db = SqliteDatabase::new_in_memory("rollups".to_string()).unwrap();
+        db.create_table(SCAN_DIRS_TABLE_BASE_NAME).await.unwrap();
+        assert!(db.table_exists("scan_dirs_rollups").await.unwrap());
+
+        let rollups = vec![
+            test_rollup("", 0, 4_000_000_000_000),
+            test_rollup("a", 1, 300),
+            test_rollup("a/b", 2, 200),
+        ];
+        db.replace_dir_rollups(&rollups).await.unwrap();
+        assert_eq!(db.query_dir_rollups(None).await.unwrap(), rollups);
+        assert_eq!(db.query_dir_rollups(Some(1)).await.unwrap(), rollups[..2]);
+
+        // 重新写入时替换上一次扫描的全部目录
+        let rescanned = vec![test_rollup("", 0, 10), test_rollup("c", 1, 10)];
+        db.replace_dir_rollups(&rescanned).await.unwrap();
+        assert_eq!(db.query_dir_rollups(None).await.unwrap(), rescanned);
+
+        db.drop_table(SCAN_DIRS_TABLE_BASE_NAME).await.unwrap();
+        assert!(!db.table_exists("scan_dirs_rollups").await.unwrap());
+    }
+
+    /// 测试超过单条INSERT行数上限的目录汇总分批写入
+    #[tokio::test]
+    async fn test_replace_many_dir_rollups() {
+        let db = SqliteDatabase::new_in_memory("many_rollups".to_string()).unwrap();
+        db.create_table(SCAN_DIRS_TABLE_BASE_NAME).await.unwrap();
+
+        let rollups: Vec<DirRollup> = (0..1_234)
+            .map(|i| test_rollup(&format!("dir{:05}", i), 1, i))
+            .collect();
+        db.replace_dir_rollups(&rollups).await.unwrap();
+        assert_eq!(db.query_dir_rollups(None).await.unwrap(), rollups);
+    }
+}
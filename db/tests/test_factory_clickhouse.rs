@@ -23,7 +23,10 @@ fn setup_clickhouse_config() -> DatabaseConfig {
             database: "default".to_string(),
             username: "default".to_string(),
             password: None,
+            ..ClickHouseConfig::default()
         }),
+        sqlite: None,
+        postgres: None,
     }
 }
 
@@ -76,6 +79,8 @@ mod tests {
             enabled: true,
             batch_size: 200000,
             clickhouse: None,
+            sqlite: None,
+            postgres: None,
         };
 
         let result = DatabaseFactory::create_database(&config, job_id.to_string());
@@ -95,6 +100,8 @@ mod tests {
             enabled: true,
             batch_size: 200000,
             clickhouse: None,
+            sqlite: None,
+            postgres: None,
         };
 
         let result = DatabaseFactory::create_database(&config, job_id.to_string());
@@ -105,6 +112,65 @@ mod tests {
         ));
     }
 
+    /// 测试密码来源和TLS配置错误在创建时报告
+    #[tokio::test]
+    async fn test_invalid_clickhouse_secret_and_tls_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.pem").display().to_string();
+
+        let invalid_configs = [
+            ClickHouseConfig {
+                password_file: Some(missing.clone()),
+                ..ClickHouseConfig::default()
+            },
+            ClickHouseConfig {
+                password_env: Some("TERRASYNC_TEST_UNSET_PASSWORD".to_string()),
+                ..ClickHouseConfig::default()
+            },
+            ClickHouseConfig {
+                ca_cert: Some(missing.clone()),
+                ..ClickHouseConfig::default()
+            },
+            ClickHouseConfig {
+                client_cert: Some(missing),
+                ..ClickHouseConfig::default()
+            },
+        ];
+
+        for clickhouse in invalid_configs {
+            let config = DatabaseConfig {
+                clickhouse: Some(clickhouse),
+                ..setup_clickhouse_config()
+            };
+            let result = create_database(&config, generate_unique_job_id("factory_tls"));
+            assert!(matches!(
+                result,
+                Err(db::error::DatabaseError::ConfigError(_))
+            ));
+        }
+    }
+
+    /// 测试HTTPS、密码文件和会话设置，创建客户端时不连接服务器
+    #[tokio::test]
+    async fn test_clickhouse_password_file_and_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let password_file = dir.path().join("password");
+        std::fs::write(&password_file, "secret\n").unwrap();
+
+        let config = DatabaseConfig {
+            clickhouse: Some(ClickHouseConfig {
+                dsn: "https://clickhouse.invalid:8443".to_string(),
+                password_file: Some(password_file.display().to_string()),
+                settings: [("max_insert_threads".to_string(), "4".to_string())].into(),
+                ..ClickHouseConfig::default()
+            }),
+            ..setup_clickhouse_config()
+        };
+
+        let result = create_database(&config, generate_unique_job_id("factory_https"));
+        assert!(result.is_ok(), "Should create client without connecting");
+    }
+
     /// 测试完整的工厂创建流程
     #[tokio::test]
     async fn test_complete_factory_workflow() {
@@ -0,0 +1,355 @@
+use db::catalog::{self, JobRun, RunStatus};
+use db::config::{DatabaseConfig, PostgresConfig};
+use db::error::DatabaseError;
+use db::migration::{self, Migration};
+use db::traits::{Database, FileScanRecord};
+use db::{
+    DatabaseFactory, DirRollup, FilterOp, PostgresDatabase, SCAN_BASE_TABLE_BASE_NAME,
+    SCAN_DIRS_TABLE_BASE_NAME, SCAN_STATE_TABLE_BASE_NAME, ScanQuery, SortOrder,
+};
+use futures::TryStreamExt;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 生成唯一的job_id用于测试隔离
+fn generate_unique_job_id(prefix: &str) -> String {
+    let count = COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}_{}_{}", prefix, std::process::id(), count)
+}
+
+/// 设置PostgreSQL测试配置，DSN可通过TERRASYNC_TEST_POSTGRES_DSN覆盖
+fn setup_postgres_config() -> DatabaseConfig {
+    let dsn = std::env::var("TERRASYNC_TEST_POSTGRES_DSN")
+        .unwrap_or_else(|_| "postgres://127.0.0.1:5432".to_string());
+    DatabaseConfig {
+        db_type: "postgres".to_string(),
+        enabled: true,
+        batch_size: 10000,
+        clickhouse: None,
+        sqlite: None,
+        postgres: Some(PostgresConfig {
+            dsn,
+            dial_timeout: 3,
+            ..PostgresConfig::default()
+        }),
+    }
+}
+
+/// 连接测试数据库，不可用时返回None以跳过测试
+async fn connect(job_id: &str) -> Option<Arc<dyn Database>> {
+    let db = DatabaseFactory::create_database(&setup_postgres_config(), job_id.to_string())
+        .expect("Failed to create PostgreSQL database");
+    match db.ping().await {
+        Ok(()) => Some(db),
+        Err(e) => {
+            eprintln!("PostgreSQL not available, skipping: {}", e);
+            None
+        }
+    }
+}
+
+fn test_record(path: &str, size: u64) -> FileScanRecord {
+    FileScanRecord {
+        path: path.to_string(),
+        size,
+        ext: Some("txt".to_string()),
+        ctime: 1_000,
+        mtime: 2_000,
+        atime: 3_000,
+        perm: Some("rw-r--r--".to_string()),
+        is_symlink: false,
+        is_dir: false,
+        is_regular_file: true,
+        hard_links: 1,
+        current_state: 0,
+        checksum: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试缺少postgres配置时返回配置错误
+    #[test]
+    fn test_missing_postgres_config() {
+        let mut config = setup_postgres_config();
+        config.postgres = None;
+
+        let result = DatabaseFactory::create_database(&config, "missing".to_string());
+        assert!(matches!(result, Err(DatabaseError::ConfigError(_))));
+    }
+
+    /// 测试无效DSN返回配置错误
+    #[test]
+    fn test_invalid_postgres_dsn() {
+        let config = PostgresConfig {
+            dsn: "not a dsn://".to_string(),
+            ..PostgresConfig::default()
+        };
+
+        let result = PostgresDatabase::new(config, "invalid".to_string());
+        assert!(matches!(result, Err(DatabaseError::ConfigError(_))));
+    }
+
+    /// 测试基础表的COPY写入、路径冲突更新和按列查询
+    #[tokio::test]
+    async fn test_base_table_copy_and_upsert() {
+        let job_id = generate_unique_job_id("pg_base");
+        let Some(db) = connect(&job_id).await else {
+            return;
+        };
+        assert_eq!(db.database_type(), "postgres");
+
+        let table_name = format!("scan_base_{}", job_id);
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        assert!(db.table_exists(&table_name).await.unwrap());
+
+        db.batch_insert_base_record_sync(vec![test_record("/a", 1), test_record("/b", 2)])
+            .await
+            .unwrap();
+        // 同一路径再次写入时更新旧记录，批次内重复路径以最后一条为准
+        db.batch_insert_base_record_async(vec![test_record("/a", 5), test_record("/a", 10)])
+            .await
+            .unwrap();
+
+        let mut records = db.query_scan_base_table(&[]).await.unwrap();
+        records.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].path, "/a");
+        assert_eq!(records[0].size, 10);
+        assert_eq!(records[0].ext.as_deref(), Some("txt"));
+        assert_eq!(records[0].mtime, 2_000);
+        assert!(records[0].is_regular_file);
+        assert_eq!(records[1].hard_links, 1);
+
+        let partial = db.query_scan_base_table(&["path", "size"]).await.unwrap();
+        assert!(partial.iter().all(|r| r.ext.is_none() && r.mtime == 0));
+        assert!(db.query_scan_base_table(&["owner"]).await.is_err());
+
+        db.drop_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        assert!(!db.table_exists(&table_name).await.unwrap());
+    }
+
+    /// 测试状态表的写入和切换
+    #[tokio::test]
+    async fn test_scan_state_switch() {
+        let job_id = generate_unique_job_id("pg_state");
+        let Some(db) = connect(&job_id).await else {
+            return;
+        };
+
+        db.create_table(SCAN_STATE_TABLE_BASE_NAME).await.unwrap();
+        assert!(db.query_scan_state_table().await.is_err());
+
+        db.insert_scan_state_sync(0).await.unwrap();
+        db.switch_scan_state().await.unwrap();
+        assert_eq!(db.query_scan_state_table().await.unwrap(), 1);
+        db.switch_scan_state().await.unwrap();
+        assert_eq!(db.query_scan_state_table().await.unwrap(), 0);
+
+        db.drop_table(SCAN_STATE_TABLE_BASE_NAME).await.unwrap();
+    }
+
+    /// 测试临时表的COPY写入和通用execute接口
+    #[tokio::test]
+    async fn test_temporary_table_and_execute() {
+        let job_id = generate_unique_job_id("pg_temp");
+        let config = setup_postgres_config().postgres.unwrap();
+        let mut db = PostgresDatabase::new(config, job_id).unwrap();
+        if let Err(e) = db.ping().await {
+            eprintln!("PostgreSQL not available, skipping: {}", e);
+            return;
+        }
+
+        assert!(
+            db.batch_insert_temp_record_sync(vec![test_record("/a", 1)])
+                .await
+                .is_err()
+        );
+        db.create_scan_temporary_table().await.unwrap();
+        let temp_table_name = db.get_scan_temp_table_name().unwrap().to_string();
+
+        db.batch_insert_temp_record_sync(vec![test_record("/a", 1), test_record("/a", 1)])
+            .await
+            .unwrap();
+        let result = db
+            .execute(
+                &format!(
+                    "SELECT count(*) AS total, max(path) AS path FROM {} WHERE size = $1",
+                    temp_table_name
+                ),
+                &[json!(1)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.rows, vec![json!({"total": 2, "path": "/a"})]);
+
+        let deleted = db
+            .execute(
+                &format!("DELETE FROM {} WHERE path = $1", temp_table_name),
+                &[json!("/a")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(deleted.affected_rows, 2);
+
+        db.drop_scan_temporary_table().await.unwrap();
+        assert!(db.get_scan_temp_table_name().is_none());
+        assert!(!db.table_exists(&temp_table_name).await.unwrap());
+    }
+
+    /// 测试流式查询的过滤、排序和分页
+    #[tokio::test]
+    async fn test_query_stream_filter_order_and_page() {
+        let job_id = generate_unique_job_id("pg_stream");
+        let Some(db) = connect(&job_id).await else {
+            return;
+        };
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+
+        let records = (0..10)
+            .map(|i| {
+                let mut record = test_record(&format!("/dir/file{}", i), i);
+                record.mtime = 1_000 * i as i64;
+                record
+            })
+            .collect();
+        db.batch_insert_base_record_sync(records).await.unwrap();
+
+        let query = ScanQuery::new()
+            .columns(&["path", "size", "mtime"])
+            .filter("mtime", FilterOp::Ge, 2_000)
+            .filter("is_regular_file", FilterOp::Eq, true)
+            .filter("hard_links", FilterOp::Eq, 1)
+            .order_by("size", SortOrder::Desc)
+            .limit(3)
+            .offset(1);
+        let page: Vec<_> = db
+            .query_stream(&query)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let sizes: Vec<u64> = page.iter().map(|r| r.size).collect();
+        assert_eq!(sizes, vec![8, 7, 6]);
+        assert_eq!(page[0].mtime, 8_000);
+
+        let rest: Vec<_> = db
+            .query_stream(&ScanQuery::new().order_by("path", SortOrder::Asc).offset(8))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 2);
+
+        db.drop_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+    }
+
+    /// 测试表结构版本记录和ADD COLUMN迁移
+    #[tokio::test]
+    async fn test_schema_migration() {
+        let job_id = generate_unique_job_id("pg_migrate");
+        let Some(db) = connect(&job_id).await else {
+            return;
+        };
+        let table_name = format!("scan_base_{}", job_id);
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+
+        let migrations = [Migration {
+            version: migration::latest_version() + 1,
+            description: "add owner column",
+            column: "owner",
+            clickhouse_type: "Nullable(String)",
+            sqlite_type: "TEXT",
+            postgres_type: "TEXT",
+        }];
+        let status = migration::migration_status_with(db.as_ref(), &migrations)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|s| s.table_name == table_name)
+            .unwrap();
+        assert_eq!(status.current_version, migration::latest_version());
+        assert_eq!(status.pending, vec![migrations[0].version]);
+
+        let applied = migration::migrate_table(db.as_ref(), &table_name, &migrations)
+            .await
+            .unwrap();
+        assert_eq!(applied, vec![migrations[0].version]);
+        let result = db
+            .execute(&format!("SELECT owner FROM {}", table_name), &[])
+            .await
+            .unwrap();
+        assert!(result.rows.is_empty());
+
+        db.drop_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+    }
+
+    /// 测试作业目录和运行历史的写入
+    #[tokio::test]
+    async fn test_catalog_record_run() {
+        let job_id = generate_unique_job_id("pg_catalog");
+        let Some(db) = connect(&job_id).await else {
+            return;
+        };
+        catalog::ensure_catalog_tables(db.as_ref()).await.unwrap();
+
+        let mut run = JobRun::start(&job_id, "scan", "{}".to_string());
+        catalog::record_run(db.as_ref(), &run).await.unwrap();
+        run.total_files = 5;
+        run.total_bytes = 1 << 40;
+        run.finish();
+        catalog::record_run(db.as_ref(), &run).await.unwrap();
+
+        let runs = catalog::list_runs(db.as_ref(), Some(&job_id), 10)
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].status, RunStatus::Completed);
+        assert_eq!(runs[0].total_bytes, 1 << 40);
+    }
+
+    /// 测试目录汇总的写入和按深度查询
+    #[tokio::test]
+    async fn test_dir_rollups() {
+        let job_id = generate_unique_job_id("pg_rollups");
+        let Some(db) = connect(&job_id).await else {
+            return;
+        };
+        db.create_table(SCAN_DIRS_TABLE_BASE_NAME).await.unwrap();
+
+        let rollups = vec![
+            DirRollup {
+                path: String::new(),
+                total_files: 2,
+                total_dirs: 1,
+                total_bytes: 1 << 40,
+                newest_mtime: 2_000,
+                oldest_atime: 1_000,
+                ..DirRollup::default()
+            },
+            DirRollup {
+                path: "a".to_string(),
+                depth: 1,
+                own_files: 2,
+                own_bytes: 1 << 40,
+                total_files: 2,
+                total_bytes: 1 << 40,
+                ..DirRollup::default()
+            },
+        ];
+        db.replace_dir_rollups(&rollups).await.unwrap();
+        db.replace_dir_rollups(&rollups).await.unwrap();
+
+        assert_eq!(db.query_dir_rollups(None).await.unwrap(), rollups);
+        assert_eq!(db.query_dir_rollups(Some(0)).await.unwrap(), rollups[..1]);
+
+        db.drop_table(SCAN_DIRS_TABLE_BASE_NAME).await.unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+use db::config::{DatabaseConfig, SqliteConfig};
+use db::traits::FileScanRecord;
+use db::{
+    DatabaseFactory, FilterOp, SCAN_BASE_TABLE_BASE_NAME, SCAN_STATE_TABLE_BASE_NAME, ScanQuery,
+    SortOrder, SqliteDatabase,
+};
+use futures::{StreamExt, TryStreamExt};
+use serde_json::json;
+
+/// 设置SQLite测试配置
+fn setup_sqlite_config(db_type: &str, path: Option<String>) -> DatabaseConfig {
+    DatabaseConfig {
+        db_type: db_type.to_string(),
+        enabled: true,
+        batch_size: 10000,
+        clickhouse: None,
+        sqlite: path.map(|path| SqliteConfig { path }),
+        postgres: None,
+    }
+}
+
+fn test_record(path: &str, size: u64) -> FileScanRecord {
+    FileScanRecord {
+        path: path.to_string(),
+        size,
+        ext: Some("txt".to_string()),
+        ctime: 1_000,
+        mtime: 2_000,
+        atime: 3_000,
+        perm: Some("rw-r--r--".to_string()),
+        is_symlink: false,
+        is_dir: false,
+        is_regular_file: true,
+        hard_links: 1,
+        current_state: 0,
+        checksum: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试通过工厂创建内存数据库
+    #[tokio::test]
+    async fn test_create_sqlite_memory_database() {
+        let config = setup_sqlite_config("sqlite-memory", None);
+        let db = DatabaseFactory::create_database(&config, "memory".to_string())
+            .expect("Failed to create in-memory SQLite database");
+
+        assert!(db.ping().await.is_ok());
+        assert_eq!(db.database_type(), "sqlite-memory");
+    }
+
+    /// 测试基础表的批量写入、替换和按列查询
+    #[tokio::test]
+    async fn test_base_table_insert_and_query() {
+        let config = setup_sqlite_config("sqlite-memory", None);
+        let db = DatabaseFactory::create_database(&config, "base".to_string()).unwrap();
+
+        assert!(!db.table_exists("scan_base_base").await.unwrap());
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        assert!(db.table_exists("scan_base_base").await.unwrap());
+
+        let checksummed = FileScanRecord {
+            checksum: Some("md5:d41d8cd98f00b204e9800998ecf8427e".to_string()),
+            ..test_record("/b", 2)
+        };
+        db.batch_insert_base_record_sync(vec![test_record("/a", 1), checksummed])
+            .await
+            .unwrap();
+        // 同一路径再次写入时替换旧记录
+        db.batch_insert_base_record_async(vec![test_record("/a", 10)])
+            .await
+            .unwrap();
+
+        let mut records = db.query_scan_base_table(&[]).await.unwrap();
+        records.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].path, "/a");
+        assert_eq!(records[0].size, 10);
+        assert_eq!(records[0].ext.as_deref(), Some("txt"));
+        assert_eq!(records[0].mtime, 2_000);
+        assert!(records[0].is_regular_file);
+        assert_eq!(records[1].hard_links, 1);
+        assert!(records[0].checksum.is_none());
+        assert_eq!(
+            records[1].checksum.as_deref(),
+            Some("md5:d41d8cd98f00b204e9800998ecf8427e")
+        );
+
+        let partial = db.query_scan_base_table(&["path", "size"]).await.unwrap();
+        assert_eq!(partial.len(), 2);
+        assert!(partial.iter().all(|r| r.ext.is_none() && r.mtime == 0));
+
+        assert!(db.query_scan_base_table(&["owner"]).await.is_err());
+    }
+
+    /// 测试状态表的写入和切换
+    #[tokio::test]
+    async fn test_scan_state_switch() {
+        let config = setup_sqlite_config("sqlite-memory", None);
+        let db = DatabaseFactory::create_database(&config, "state".to_string()).unwrap();
+
+        db.create_table(SCAN_STATE_TABLE_BASE_NAME).await.unwrap();
+        assert!(db.query_scan_state_table().await.is_err());
+
+        db.insert_scan_state_sync(0).await.unwrap();
+        assert_eq!(db.query_scan_state_table().await.unwrap(), 0);
+
+        db.switch_scan_state().await.unwrap();
+        assert_eq!(db.query_scan_state_table().await.unwrap(), 1);
+
+        db.switch_scan_state().await.unwrap();
+        assert_eq!(db.query_scan_state_table().await.unwrap(), 0);
+    }
+
+    /// 测试临时表的创建、写入和删除
+    #[tokio::test]
+    async fn test_temporary_table() {
+        let mut db = SqliteDatabase::new_in_memory("temp".to_string()).unwrap();
+        use db::Database;
+
+        assert!(db.get_scan_temp_table_name().is_none());
+        assert!(
+            db.batch_insert_temp_record_sync(vec![test_record("/a", 1)])
+                .await
+                .is_err()
+        );
+
+        db.create_scan_temporary_table().await.unwrap();
+        let temp_table_name = db.get_scan_temp_table_name().unwrap().to_string();
+        assert!(temp_table_name.starts_with("scan_temp_"));
+
+        db.batch_insert_temp_record_sync(vec![test_record("/a", 1), test_record("/a", 1)])
+            .await
+            .unwrap();
+        let result = db
+            .execute(
+                &format!("SELECT count(*) AS total FROM {}", temp_table_name),
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.rows, vec![json!({"total": 2})]);
+
+        db.drop_scan_temporary_table().await.unwrap();
+        assert!(db.get_scan_temp_table_name().is_none());
+        assert!(!db.table_exists(&temp_table_name).await.unwrap());
+    }
+
+    /// 测试通用execute接口的参数绑定和返回结果
+    #[tokio::test]
+    async fn test_execute_with_params() {
+        let config = setup_sqlite_config("sqlite-memory", None);
+        let db = DatabaseFactory::create_database(&config, "execute".to_string()).unwrap();
+
+        db.execute(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, flag INTEGER)",
+            &[],
+        )
+        .await
+        .unwrap();
+        let inserted = db
+            .execute(
+                "INSERT INTO items (name, flag) VALUES (?, ?)",
+                &[json!("first"), json!(true)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted.affected_rows, 1);
+        assert_eq!(inserted.last_insert_id, Some(1));
+
+        let result = db
+            .execute(
+                "SELECT id, name, flag FROM items WHERE name = ?",
+                &[json!("first")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            result.rows,
+            vec![json!({"id": 1, "name": "first", "flag": 1})]
+        );
+
+        db.drop_table("items").await.unwrap();
+        assert!(!db.table_exists("items").await.unwrap());
+    }
+
+    /// 测试文件数据库在重新打开后保留数据
+    #[tokio::test]
+    async fn test_file_database_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("terrasync.db");
+        let config = setup_sqlite_config("sqlite", Some(path.to_string_lossy().to_string()));
+
+        let db = DatabaseFactory::create_database(&config, "file".to_string()).unwrap();
+        assert_eq!(db.database_type(), "sqlite");
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        db.batch_insert_base_record_sync(vec![test_record("/a", 1)])
+            .await
+            .unwrap();
+        db.close().await.unwrap();
+        drop(db);
+
+        let db = DatabaseFactory::create_database(&config, "file".to_string()).unwrap();
+        let records = db.query_scan_base_table(&["path"]).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].path, "/a");
+    }
+
+    /// 测试流式查询的过滤、排序和分页
+    #[tokio::test]
+    async fn test_query_stream_filter_order_and_page() {
+        let config = setup_sqlite_config("sqlite-memory", None);
+        let db = DatabaseFactory::create_database(&config, "stream".to_string()).unwrap();
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+
+        let records = (0..10)
+            .map(|i| test_record(&format!("/dir/file{}", i), i))
+            .collect();
+        db.batch_insert_base_record_sync(records).await.unwrap();
+
+        let query = ScanQuery::new()
+            .columns(&["path", "size"])
+            .filter("size", FilterOp::Ge, 2)
+            .filter("path", FilterOp::Like, "/dir/%")
+            .order_by("size", SortOrder::Desc)
+            .limit(3)
+            .offset(1);
+        let page: Vec<_> = db
+            .query_stream(&query)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let sizes: Vec<u64> = page.iter().map(|r| r.size).collect();
+        assert_eq!(sizes, vec![8, 7, 6]);
+        assert!(page.iter().all(|r| r.ext.is_none()));
+
+        // 只指定offset时返回其后的全部记录
+        let rest: Vec<_> = db
+            .query_stream(&ScanQuery::new().order_by("path", SortOrder::Asc).offset(8))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].path, "/dir/file8");
+
+        // 提前释放流后连接可继续使用
+        let mut stream = db.query_stream(&ScanQuery::new()).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(stream);
+        let nulls: Vec<_> = db
+            .query_stream(&ScanQuery::new().filter("perm", FilterOp::Eq, serde_json::Value::Null))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(nulls.is_empty());
+
+        assert!(
+            db.query_stream(&ScanQuery::new().filter("owner", FilterOp::Eq, "root"))
+                .await
+                .is_err()
+        );
+        assert!(
+            db.query_stream(&ScanQuery::new().order_by("size; DROP TABLE x", SortOrder::Asc))
+                .await
+                .is_err()
+        );
+    }
+}
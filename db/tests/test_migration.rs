@@ -0,0 +1,116 @@
+use db::migration::{
+    self, BASELINE_VERSION, Migration, migrate_all_with, migrate_table, migration_status_with,
+};
+use db::{Database, SCAN_BASE_TABLE_BASE_NAME, SqliteDatabase};
+use serde_json::json;
+
+/// 测试用迁移：在内置迁移之后为scan_base表新增owner列
+const TEST_MIGRATIONS: &[Migration] = &[Migration {
+    version: 3,
+    description: "add owner column",
+    column: "owner",
+    clickhouse_type: "Nullable(String)",
+    sqlite_type: "TEXT",
+    postgres_type: "TEXT",
+}];
+
+/// 查询表中某列是否存在
+async fn has_column(db: &SqliteDatabase, table_name: &str, column: &str) -> bool {
+    let result = db
+        .execute(
+            "SELECT count(*) AS total FROM pragma_table_info(?) WHERE name = ?",
+            &[json!(table_name), json!(column)],
+        )
+        .await
+        .unwrap();
+    result.rows == vec![json!({"total": 1})]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试新建的scan_base表记录为最新版本
+    #[tokio::test]
+    async fn test_new_table_records_latest_version() {
+        let db = SqliteDatabase::new_in_memory("fresh".to_string()).unwrap();
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+
+        let statuses = migration::migration_status(&db).await.unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].table_name, "scan_base_fresh");
+        assert_eq!(statuses[0].current_version, migration::latest_version());
+        assert!(statuses[0].pending.is_empty());
+
+        // 重复打开已有表不会改变版本
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        let statuses = migration::migration_status(&db).await.unwrap();
+        assert_eq!(statuses[0].current_version, migration::latest_version());
+    }
+
+    /// 测试旧表报告待执行迁移，应用后补齐列并更新版本
+    #[tokio::test]
+    async fn test_pending_migrations_are_reported_and_applied() {
+        let db = SqliteDatabase::new_in_memory("old".to_string()).unwrap();
+        // 引入版本管理前创建的表没有版本记录
+        db.execute("CREATE TABLE scan_base_legacy (path TEXT PRIMARY KEY)", &[])
+            .await
+            .unwrap();
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+
+        let statuses = migration_status_with(&db, TEST_MIGRATIONS).await.unwrap();
+        let tables: Vec<_> = statuses.iter().map(|s| s.table_name.as_str()).collect();
+        assert_eq!(tables, vec!["scan_base_legacy", "scan_base_old"]);
+        assert_eq!(statuses[0].current_version, BASELINE_VERSION);
+        assert_eq!(statuses[1].current_version, migration::latest_version());
+        assert!(statuses.iter().all(|s| s.pending == vec![3]));
+
+        let statuses = migrate_all_with(&db, TEST_MIGRATIONS).await.unwrap();
+        assert!(
+            statuses
+                .iter()
+                .all(|s| s.current_version == 3 && s.pending.is_empty())
+        );
+        assert!(has_column(&db, "scan_base_legacy", "owner").await);
+        assert!(has_column(&db, "scan_base_old", "owner").await);
+
+        // 已应用的迁移不会重复执行
+        let applied = migrate_table(&db, "scan_base_old", TEST_MIGRATIONS)
+            .await
+            .unwrap();
+        assert!(applied.is_empty());
+    }
+
+    /// 测试旧表打开时自动补齐内置迁移新增的checksum列
+    #[tokio::test]
+    async fn test_open_legacy_table_adds_checksum_column() {
+        let db = SqliteDatabase::new_in_memory("legacy".to_string()).unwrap();
+        db.execute("CREATE TABLE scan_base_legacy (path TEXT PRIMARY KEY)", &[])
+            .await
+            .unwrap();
+        assert!(!has_column(&db, "scan_base_legacy", "checksum").await);
+
+        db.create_table(SCAN_BASE_TABLE_BASE_NAME).await.unwrap();
+        assert!(has_column(&db, "scan_base_legacy", "checksum").await);
+        let statuses = migration::migration_status(&db).await.unwrap();
+        assert_eq!(statuses[0].current_version, migration::latest_version());
+    }
+
+    /// 测试列已存在但版本未记录时迁移仍可完成
+    #[tokio::test]
+    async fn test_migration_tolerates_existing_column() {
+        let db = SqliteDatabase::new_in_memory("partial".to_string()).unwrap();
+        db.execute(
+            "CREATE TABLE scan_base_partial (path TEXT PRIMARY KEY, owner TEXT)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let applied = migrate_table(&db, "scan_base_partial", TEST_MIGRATIONS)
+            .await
+            .unwrap();
+        assert_eq!(applied, vec![3]);
+        assert!(has_column(&db, "scan_base_partial", "owner").await);
+    }
+}
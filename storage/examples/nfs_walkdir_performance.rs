@@ -103,11 +103,11 @@ async fn test_nfs_walkdir_performance() {
                 }
 
                 // 显示软连接标识
-                if let Some(is_symlink) = entry.is_symlink {
-                    if is_symlink {
-                        symlink_flag = "🔗".to_string();
-                        symlink_count += 1;
-                    }
+                if let Some(is_symlink) = entry.is_symlink
+                    && is_symlink
+                {
+                    symlink_flag = "🔗".to_string();
+                    symlink_count += 1;
                 }
 
                 total_size += entry.size;
@@ -157,7 +157,7 @@ async fn test_nfs_walkdir_performance() {
                 // 格式化权限显示
                 let perms_str = entry
                     .mode
-                    .map(|mode| format_permissions(mode))
+                    .map(format_permissions)
                     .unwrap_or_else(|| "-".to_string());
 
                 println!(
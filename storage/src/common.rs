@@ -1,5 +1,6 @@
 use nfs3_client::nfs3_types::nfs3;
-use std::{path::PathBuf, time::SystemTime};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// 统一的文件系统条目类型，兼容LocalStorage和NFSStorage
 #[derive(Debug, Clone)]
@@ -35,9 +36,9 @@ impl StorageEntry {
     }
 }
 
-pub fn get_relative_path(target: &PathBuf, base: &PathBuf) -> String {
+pub fn get_relative_path(target: &Path, base: &Path) -> String {
     target
-        .strip_prefix(&base)
+        .strip_prefix(base)
         .ok()
         .map(|p| p.to_string_lossy().to_string())
         .unwrap()
@@ -1,7 +1,9 @@
 use crate::common::get_relative_path;
+use crate::throttle::RateLimiter;
 use std::io;
 use std::io::SeekFrom;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 
 #[cfg(unix)]
@@ -61,6 +63,7 @@ impl AsyncSectionWriter {
         let mut file = tokio::fs::OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(false)
             .open(path)
             .await?;
         file.seek(SeekFrom::Start(offset)).await?;
@@ -85,6 +88,12 @@ impl AsyncSectionWriter {
 
         Ok(bytes_written)
     }
+
+    /// 刷新缓冲并将已写入的数据落盘
+    pub async fn sync_data(&mut self) -> io::Result<()> {
+        self.file.flush().await?;
+        self.file.sync_data().await
+    }
 }
 
 impl Drop for AsyncSectionWriter {
@@ -97,12 +106,17 @@ impl Drop for AsyncSectionWriter {
 /// Local storage implementation with async support
 pub struct LocalStorage {
     root: String,
+    /// 元数据操作限速器，每个条目的stat计为一次操作
+    pub(crate) ops_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl LocalStorage {
     /// Create new local storage instance
     pub fn new(root: String) -> Self {
-        Self { root }
+        Self {
+            root,
+            ops_limiter: None,
+        }
     }
 
     /// Get the root path
@@ -123,6 +137,7 @@ impl LocalStorage {
             None => PathBuf::from(&self.root),
         };
 
+        let ops_limiter = self.ops_limiter.clone();
         tokio::task::spawn_blocking(move || {
             let mut walker = WalkDir::new(&target_path)
                 .follow_links(false) // 不跟随符号链接，避免循环
@@ -144,6 +159,9 @@ impl LocalStorage {
 
                 let path = path_buf.to_string_lossy().into_owned(); // 转换为String
 
+                if let Some(limiter) = &ops_limiter {
+                    limiter.acquire_blocking(1);
+                }
                 if let Ok(info) = entry.metadata() {
                     #[cfg(unix)]
                     let hard_links = info.nlink() as u8;
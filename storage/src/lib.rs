@@ -2,6 +2,7 @@ pub mod common;
 pub mod file;
 pub mod nfs;
 pub mod s3;
+pub mod throttle;
 use common::StorageEntry;
 use file::LocalStorage;
 use nfs::NFSStorage;
@@ -9,7 +10,9 @@ use nfs::parse_nfs_path;
 use s3::S3Storage;
 use s3::parse_s3_config;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use throttle::RateLimiter;
 
 /// 存储类型枚举
 pub enum StorageType {
@@ -18,11 +21,23 @@ pub enum StorageType {
     S3(S3Storage),
 }
 
+impl StorageType {
+    /// 设置元数据操作（READDIRPLUS、stat）的限速器，同一作业的存储共享一个实例
+    pub fn with_ops_limiter(mut self, limiter: Option<Arc<RateLimiter>>) -> Self {
+        match &mut self {
+            StorageType::Local(storage) => storage.ops_limiter = limiter,
+            StorageType::NFS(storage) => storage.ops_limiter = limiter,
+            StorageType::S3(_storage) => {}
+        }
+        self
+    }
+}
+
 /// 根据路径前缀创建对应的存储实例
 pub fn create_storage(path: &str) -> Result<StorageType, String> {
     match path {
-        p if p.starts_with("nfs://") => create_nfs_storage(&p),
-        p if p.starts_with("s3://") => create_s3_storage(&p),
+        p if p.starts_with("nfs://") => create_nfs_storage(p),
+        p if p.starts_with("s3://") => create_s3_storage(p),
         _ => create_local_storage(path),
     }
 }
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 
 use std::time::SystemTime;
 use tokio::sync::mpsc;
@@ -15,6 +16,7 @@ use nfs3_client::tokio::TokioConnector;
 
 use crate::common::get_relative_path;
 use crate::seconds_nanos_to_systemtime;
+use crate::throttle::RateLimiter;
 
 // 类型别名，简化复杂类型
 pub type NfsConnection =
@@ -212,6 +214,8 @@ pub struct NFSStorage {
     server_ip: String,
     portmapper_port: u16,
     path: Option<String>,
+    /// 元数据操作限速器，每次READDIRPLUS调用计为一次操作
+    pub(crate) ops_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl NFSStorage {
@@ -222,6 +226,7 @@ impl NFSStorage {
             server_ip,
             portmapper_port,
             path,
+            ops_limiter: None,
         }
     }
 
@@ -312,6 +317,7 @@ impl NFSStorage {
         let server_ip = self.server_ip.clone();
         let portmapper_port = self.portmapper_port;
         let max_depth = depth.unwrap_or(0); // 0 means scan all depths
+        let ops_limiter = self.ops_limiter.clone();
 
         tokio::spawn(async move {
             let auth_unix = auth_unix {
@@ -346,6 +352,7 @@ impl NFSStorage {
                 tx,
                 0, // current depth starts at 0
                 max_depth,
+                ops_limiter.as_deref(),
             )
             .await
             {
@@ -361,12 +368,16 @@ impl NFSStorage {
     fn list_dir_recursive_internal<'a>(
         connection: &'a mut NfsConnection, dir_path: &'a str, dir_handle: &'a nfs3::nfs_fh3,
         tx: tokio::sync::mpsc::Sender<crate::StorageEntry>, current_depth: usize, max_depth: usize,
+        ops_limiter: Option<&'a RateLimiter>,
     ) -> RecursiveFuture<'a> {
         Box::pin(async move {
             let mut cookie = nfs3::cookie3::default();
             let mut cookieverf = nfs3::cookieverf3::default();
 
             loop {
+                if let Some(limiter) = ops_limiter {
+                    limiter.acquire(1).await;
+                }
                 let readdirplus = connection
                     .readdirplus(&nfs3::READDIRPLUS3args {
                         dir: dir_handle.clone(),
@@ -395,21 +406,21 @@ impl NFSStorage {
                     }
 
                     // If it's a directory, recurse only if max_depth allows
-                    if is_dir && (max_depth == 0 || current_depth < max_depth - 1) {
-                        if let Nfs3Option::Some(child_handle) = entry.name_handle.clone() {
-                            if let Err(e) = Self::list_dir_recursive_internal(
-                                connection,
-                                &full_path,
-                                &child_handle,
-                                tx.clone(),
-                                current_depth + 1,
-                                max_depth,
-                            )
-                            .await
-                            {
-                                eprintln!("Error listing directory {}: {}", full_path, e);
-                            }
-                        }
+                    if is_dir
+                        && (max_depth == 0 || current_depth < max_depth - 1)
+                        && let Nfs3Option::Some(child_handle) = entry.name_handle.clone()
+                        && let Err(e) = Self::list_dir_recursive_internal(
+                            connection,
+                            &full_path,
+                            &child_handle,
+                            tx.clone(),
+                            current_depth + 1,
+                            max_depth,
+                            ops_limiter,
+                        )
+                        .await
+                    {
+                        eprintln!("Error listing directory {}: {}", full_path, e);
                     }
                 }
 
@@ -1,4 +1,4 @@
-/// S3存储结构（占位符，待实现）
+// S3存储结构（占位符，待实现）
 
 /// 解析S3配置，返回bucket和认证信息
 pub fn parse_s3_config(s3_path: &str) -> Result<(String, String, String, String), String> {
@@ -0,0 +1,102 @@
+use chrono::Timelike;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 一天中的限速时间段，`start`/`end` 为当天零点起的秒数
+/// 结束时间不大于开始时间时表示跨越午夜
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateWindow {
+    pub start: u32,
+    pub end: u32,
+    /// 时间段内每秒允许的数量，0表示不限速
+    pub rate: u64,
+}
+
+impl RateWindow {
+    fn contains(&self, seconds: u32) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&seconds)
+        } else {
+            seconds >= self.start || seconds < self.end
+        }
+    }
+}
+
+/// 令牌桶限速器，同一作业的所有worker共享一个实例
+/// 桶容量为一秒的额度，额度不足时按欠额计算等待时间，大块请求不会被饿死
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// 不在任何时间段内时每秒允许的数量，0表示不限速
+    default_rate: u64,
+    windows: Vec<RateWindow>,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(default_rate: u64, windows: Vec<RateWindow>) -> Self {
+        Self {
+            default_rate,
+            windows,
+            bucket: Mutex::new(Bucket {
+                tokens: default_rate as f64,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// 按本地时间取当前生效的速率，第一个匹配的时间段优先
+    pub fn current_rate(&self) -> u64 {
+        let seconds = chrono::Local::now().num_seconds_from_midnight();
+        self.rate_at(seconds)
+    }
+
+    /// 当天零点起第 `seconds` 秒生效的速率
+    pub fn rate_at(&self, seconds: u32) -> u64 {
+        self.windows
+            .iter()
+            .find(|window| window.contains(seconds))
+            .map_or(self.default_rate, |window| window.rate)
+    }
+
+    /// 在 `now` 时刻按 `rate` 补充令牌后取走 `amount` 个，返回需要等待的时间
+    /// 时间由调用方传入，便于不依赖实际时钟验证补充计算
+    pub fn reserve_at(&self, amount: u64, rate: u64, now: Instant) -> Option<Duration> {
+        if rate == 0 {
+            return None;
+        }
+
+        let rate = rate as f64;
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(rate);
+        bucket.last = now;
+        bucket.tokens -= amount as f64;
+
+        (bucket.tokens < 0.0).then(|| Duration::from_secs_f64(-bucket.tokens / rate))
+    }
+
+    /// 取走 `amount` 个令牌，返回需要等待的时间
+    fn reserve(&self, amount: u64) -> Option<Duration> {
+        self.reserve_at(amount, self.current_rate(), Instant::now())
+    }
+
+    /// 异步等待 `amount` 个令牌
+    pub async fn acquire(&self, amount: u64) {
+        if let Some(wait) = self.reserve(amount) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 在阻塞线程中等待 `amount` 个令牌
+    pub fn acquire_blocking(&self, amount: u64) {
+        if let Some(wait) = self.reserve(amount) {
+            std::thread::sleep(wait);
+        }
+    }
+}
@@ -2,7 +2,7 @@ mod common;
 mod test_walkdir;
 
 use common::create_test_structure;
-use storage::{create_storage, StorageType};
+use storage::{StorageType, create_storage};
 use tempfile::TempDir;
 
 /// 集成测试：验证整个存储系统的walkdir功能
@@ -112,13 +112,7 @@ async fn test_walkdir_edge_cases() {
             let root_entry = entries.iter().find(|e| e.is_dir).unwrap();
             assert!(
                 root_entry.name.is_empty()
-                    || root_entry.name
-                        == empty_temp
-                            .path()
-                            .file_name()
-                            .unwrap()
-                            .to_string_lossy()
-                            .to_string()
+                    || root_entry.name == empty_temp.path().file_name().unwrap().to_string_lossy()
             );
         }
         _ => panic!("应该创建LocalStorage"),
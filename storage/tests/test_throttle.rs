@@ -0,0 +1,73 @@
+use storage::throttle::{RateLimiter, RateWindow};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_rate_windows() {
+        let limiter = RateLimiter::new(
+            0,
+            vec![
+                RateWindow {
+                    start: 8 * 3600,
+                    end: 18 * 3600,
+                    rate: 100,
+                },
+                RateWindow {
+                    start: 22 * 3600,
+                    end: 6 * 3600,
+                    rate: 1000,
+                },
+            ],
+        );
+
+        assert_eq!(limiter.rate_at(12 * 3600), 100);
+        assert_eq!(limiter.rate_at(18 * 3600), 0);
+        assert_eq!(limiter.rate_at(23 * 3600), 1000);
+        assert_eq!(limiter.rate_at(3600), 1000);
+        assert_eq!(limiter.rate_at(7 * 3600), 0);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(100, Vec::new());
+        let now = Instant::now();
+        let ms = Duration::from_millis;
+
+        // 初始额度为一秒的速率，之后按速率补充
+        assert_eq!(limiter.reserve_at(100, 100, now), None);
+        assert_eq!(limiter.reserve_at(20, 100, now), Some(ms(200)));
+        // 欠额补足前继续请求时等待时间累加
+        assert_eq!(limiter.reserve_at(10, 100, now + ms(100)), Some(ms(200)));
+        assert_eq!(limiter.reserve_at(0, 100, now + ms(300)), None);
+
+        // 空闲再久也只补充一秒的额度
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.reserve_at(100, 100, later), None);
+        assert_eq!(limiter.reserve_at(50, 100, later), Some(ms(500)));
+    }
+
+    #[test]
+    fn test_shared_bucket() {
+        // 多个worker共享同一个桶，同时请求时按到达顺序排队等待
+        let limiter = RateLimiter::new(1000, Vec::new());
+        let now = Instant::now();
+        assert_eq!(limiter.reserve_at(1000, 1000, now), None);
+        let waits: Vec<_> = (0..4)
+            .map(|_| limiter.reserve_at(50, 1000, now).unwrap())
+            .collect();
+        assert_eq!(
+            waits,
+            [50, 100, 150, 200].map(Duration::from_millis).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let limiter = RateLimiter::new(0, Vec::new());
+        assert_eq!(limiter.current_rate(), 0);
+        assert_eq!(limiter.reserve_at(u64::MAX / 2, 0, Instant::now()), None);
+    }
+}
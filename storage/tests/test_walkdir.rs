@@ -80,7 +80,7 @@ async fn test_nfs_concurrent_walkdir_performance() {
     let total_duration = start_time.elapsed();
 
     // 汇总结果
-    let mut total_files = 0;
+    let mut total_files: usize = 0;
     let mut total_entries = 0;
 
     println!("\n=== 并发扫描结果 ===");
@@ -98,11 +98,7 @@ async fn test_nfs_concurrent_walkdir_performance() {
         total_entries += entries;
     }
 
-    let avg_files_per_task = if CONCURRENT_TASKS > 0 {
-        total_files / CONCURRENT_TASKS
-    } else {
-        0
-    };
+    let avg_files_per_task = total_files.checked_div(CONCURRENT_TASKS).unwrap_or(0);
     let concurrent_speed = if total_duration.as_secs_f64() > 0.0 {
         total_files as f64 / total_duration.as_secs_f64()
     } else {
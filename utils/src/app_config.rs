@@ -2,6 +2,7 @@ use config::builder::DefaultState;
 use config::{Config, ConfigBuilder, Environment};
 use lazy_static::{__Deref, lazy_static};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::RwLock;
 
@@ -19,6 +20,16 @@ pub struct LogConfig {
     pub max_size: u64,
     pub max_backups: u32,
     pub level: String,
+    /// 是否使用gzip压缩轮转后的审计日志
+    #[serde(default)]
+    pub compress: bool,
+    /// 审计日志记录范围："all" 记录所有实体，"changes" 仅记录上次运行后变化的实体
+    #[serde(default = "default_audit_mode")]
+    pub audit_mode: String,
+}
+
+fn default_audit_mode() -> String {
+    "all".to_string()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,8 +39,45 @@ pub struct ScanConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MigrateConfig {
+    /// 强制覆盖已存在的目标文件，仅在未设置 `conflict_policy` 时生效
+    /// 为false时仍按update-if-different更新内容不同的文件，不覆盖任何文件需设置 `conflict_policy = "skip"`
+    #[serde(default)]
     pub overwrite: bool,
+    /// 目标已存在时的处理策略：skip、overwrite、update-if-newer、update-if-different、rename-existing、fail
+    #[serde(default)]
+    pub conflict_policy: Option<String>,
     pub concurrency: u32,
+    /// 同时复制中的文件总字节数上限，超过该值的单个文件独占全部额度
+    #[serde(default = "default_max_inflight_bytes")]
+    pub max_inflight_bytes: u64,
+    /// 不小于该大小的文件分段并行传输，并在作业目录中记录断点
+    #[serde(default = "default_section_threshold")]
+    pub section_threshold: u64,
+    /// 分段传输时每段的字节数
+    #[serde(default = "default_section_size")]
+    pub section_size: u64,
+    /// 单个文件同时传输的分段数
+    #[serde(default = "default_section_concurrency")]
+    pub section_concurrency: u32,
+    /// 重命名到目标路径前将临时文件落盘
+    #[serde(default)]
+    pub fsync: bool,
+}
+
+fn default_max_inflight_bytes() -> u64 {
+    256 * 1024 * 1024
+}
+
+fn default_section_threshold() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_section_size() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_section_concurrency() -> u32 {
+    4
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,6 +88,96 @@ pub struct DatabaseClickhouse {
     pub database: String,
     pub username: String,
     pub password: Option<String>,
+    /// 流式写入累计达到该行数时提交
+    #[serde(default = "default_inserter_max_rows")]
+    pub inserter_max_rows: u64,
+    /// 流式写入累计达到该未压缩字节数时提交
+    #[serde(default = "default_inserter_max_bytes")]
+    pub inserter_max_bytes: u64,
+    /// 距上次提交超过该秒数时提交，0表示不按时间提交
+    #[serde(default = "default_inserter_period")]
+    pub inserter_period: u64,
+    /// 是否使用LZ4压缩传输数据
+    #[serde(default = "default_clickhouse_compression")]
+    pub compression: bool,
+    /// 从该环境变量读取密码，为空时不使用
+    pub password_env: Option<String>,
+    /// 从该文件读取密码，为空时不使用
+    pub password_file: Option<String>,
+    /// 额外信任的CA证书PEM文件，为空时仅信任内置根证书
+    pub ca_cert: Option<String>,
+    /// 双向TLS认证的客户端证书和私钥PEM文件
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    /// 透传给ClickHouse的会话设置
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+    /// 扫描结果表布局："per_job" 或 "shared"
+    #[serde(default = "default_table_layout")]
+    pub table_layout: String,
+    /// "shared" 布局下每个作业保留的最近代次数，0表示全部保留
+    #[serde(default = "default_retain_generations")]
+    pub retain_generations: u32,
+}
+
+fn default_table_layout() -> String {
+    "per_job".to_string()
+}
+
+fn default_retain_generations() -> u32 {
+    2
+}
+
+fn default_inserter_max_rows() -> u64 {
+    100_000
+}
+
+fn default_inserter_max_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_inserter_period() -> u64 {
+    10
+}
+
+fn default_clickhouse_compression() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatabaseSqlite {
+    /// 数据库文件路径，仅 "sqlite" 类型使用
+    pub path: String,
+}
+
+impl Default for DatabaseSqlite {
+    fn default() -> Self {
+        Self {
+            path: "jobs/terrasync.db".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DatabasePostgres {
+    pub dsn: String,
+    pub dial_timeout: u32,
+    pub database: String,
+    pub username: String,
+    pub password: Option<String>,
+}
+
+impl Default for DatabasePostgres {
+    fn default() -> Self {
+        Self {
+            dsn: "postgres://localhost:5432".to_string(),
+            dial_timeout: 10,
+            database: "postgres".to_string(),
+            username: "postgres".to_string(),
+            password: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -48,6 +186,24 @@ pub struct DatabaseConfig {
     pub r#type: String,
     pub batch_size: u32,
     pub clickhouse: DatabaseClickhouse,
+    #[serde(default)]
+    pub sqlite: DatabaseSqlite,
+    #[serde(default)]
+    pub postgres: DatabasePostgres,
+    /// 批量写入失败后的最大重试次数，仍失败的批次写入作业目录下的spool文件
+    #[serde(default = "default_database_retries")]
+    pub retries: u32,
+    /// 首次重试前的等待毫秒数，之后每次翻倍
+    #[serde(default = "default_database_backoff_ms")]
+    pub backoff_ms: u64,
+}
+
+fn default_database_retries() -> u32 {
+    3
+}
+
+fn default_database_backoff_ms() -> u64 {
+    500
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -57,6 +213,97 @@ pub struct KafkaConfig {
     pub port: u32,
     pub topic: String,
     pub concurrency: u32,
+    /// 生产者确认级别："0"、"1" 或 "all"
+    #[serde(default = "default_kafka_acks")]
+    pub acks: String,
+    /// 投递失败时的最大重试次数
+    #[serde(default = "default_kafka_retries")]
+    pub retries: u32,
+    /// 单个批次的最大消息数
+    #[serde(default = "default_kafka_batch_size")]
+    pub batch_size: u32,
+    /// 批次等待时间（毫秒）
+    #[serde(default = "default_kafka_linger_ms")]
+    pub linger_ms: u32,
+    /// 单条消息的投递超时时间（毫秒）
+    #[serde(default = "default_kafka_message_timeout_ms")]
+    pub message_timeout_ms: u32,
+}
+
+fn default_kafka_acks() -> String {
+    "all".to_string()
+}
+
+fn default_kafka_retries() -> u32 {
+    5
+}
+
+fn default_kafka_batch_size() -> u32 {
+    10000
+}
+
+fn default_kafka_linger_ms() -> u32 {
+    100
+}
+
+fn default_kafka_message_timeout_ms() -> u32 {
+    30000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    /// 接收事件的URL列表
+    pub urls: Vec<String>,
+    /// HMAC-SHA256签名密钥，为空时不签名
+    pub secret: Option<String>,
+    /// 单次请求超时时间（秒）
+    pub timeout: u64,
+    /// 请求失败时的最大重试次数
+    pub retries: u32,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    pub backoff_ms: u64,
+    /// 进度事件的发送间隔（秒），0表示不发送进度事件
+    pub progress_interval: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            urls: Vec::new(),
+            secret: None,
+            timeout: 10,
+            retries: 3,
+            backoff_ms: 500,
+            progress_interval: 30,
+        }
+    }
+}
+
+/// 作业级限速，同一作业的所有worker共享额度，0表示不限速
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ThrottleConfig {
+    /// 同步数据传输每秒的字节数
+    #[serde(default)]
+    pub bytes_per_sec: u64,
+    /// 每秒的元数据操作数（READDIRPLUS、stat、create）
+    #[serde(default)]
+    pub ops_per_sec: u64,
+    /// 按时间段覆盖上面的限速，第一个匹配的时间段生效
+    #[serde(default)]
+    pub schedule: Vec<ThrottleWindow>,
+}
+
+/// 一天中的限速时间段，时间格式为 `HH:MM`，结束时间早于开始时间表示跨越午夜
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThrottleWindow {
+    pub start: String,
+    pub end: String,
+    /// 时间段内的字节限速，为空时沿用默认值
+    pub bytes_per_sec: Option<u64>,
+    /// 时间段内的操作限速，为空时沿用默认值
+    pub ops_per_sec: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -66,6 +313,10 @@ pub struct AppConfig {
     pub migrate: MigrateConfig,
     pub database: DatabaseConfig,
     pub kafka: KafkaConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    #[serde(default)]
+    pub throttle: ThrottleConfig,
 }
 
 impl AppConfig {
@@ -170,6 +421,20 @@ impl TryFrom<Config> for AppConfig {
             migrate: config.get::<MigrateConfig>("migrate")?,
             database: config.get::<DatabaseConfig>("database")?,
             kafka: config.get::<KafkaConfig>("kafka")?,
+            webhook: get_or_default::<WebhookConfig>(&config, "webhook")?,
+            throttle: get_or_default::<ThrottleConfig>(&config, "throttle")?,
         })
     }
 }
+
+/// 读取可选配置段，未配置时使用默认值
+fn get_or_default<T>(config: &Config, key: &str) -> Result<T>
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    match config.get::<T>(key) {
+        Ok(value) => Ok(value),
+        Err(config::ConfigError::NotFound(_)) => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -76,7 +76,7 @@ fn get_log_level_from_config() -> Level {
     // 在测试环境中，配置可能未初始化，使用默认值
     #[cfg(test)]
     {
-        return Level::Info;
+        Level::Info
     }
 
     // 在生产环境中使用实际配置
@@ -142,7 +142,6 @@ fn default_file_drain() -> Result<slog_async::Async> {
 
     let file = OpenOptions::new()
         .create(true)
-        .write(true)
         .append(true)
         .open(log_file)?;
 
@@ -22,11 +22,11 @@ fn fetch_config() {
     assert_eq!(config.scan.concurrency, 5);
 
     // Test all migrate configuration items
-    assert_eq!(config.migrate.overwrite, false);
+    assert!(!config.migrate.overwrite);
     assert_eq!(config.migrate.concurrency, 1);
 
     // Test all database configuration items
-    assert_eq!(config.database.enabled, true);
+    assert!(config.database.enabled);
     assert_eq!(config.database.r#type, "sqlite");
     assert_eq!(config.database.batch_size, 10000);
 
@@ -36,7 +36,7 @@ fn fetch_config() {
     assert_eq!(config.database.clickhouse.read_timeout, 30);
 
     // Test all kafka configuration items
-    assert_eq!(config.kafka.enabled, false);
+    assert!(!config.kafka.enabled);
     assert_eq!(config.kafka.host, "localhost");
     assert_eq!(config.kafka.port, 9092);
     assert_eq!(config.kafka.topic, "test");
@@ -56,11 +56,11 @@ fn verify_get() {
     assert_eq!(AppConfig::get::<u32>("scan.concurrency").unwrap(), 5);
 
     // Test getting all migrate configuration items via get
-    assert_eq!(AppConfig::get::<bool>("migrate.overwrite").unwrap(), false);
+    assert!(!AppConfig::get::<bool>("migrate.overwrite").unwrap());
     assert_eq!(AppConfig::get::<u32>("migrate.concurrency").unwrap(), 1);
 
     // Test getting all database configuration items via get
-    assert_eq!(AppConfig::get::<bool>("database.enabled").unwrap(), true);
+    assert!(AppConfig::get::<bool>("database.enabled").unwrap());
     assert_eq!(AppConfig::get::<String>("database.type").unwrap(), "sqlite");
     assert_eq!(AppConfig::get::<u32>("database.batch_size").unwrap(), 10000);
 
@@ -79,7 +79,7 @@ fn verify_get() {
     );
 
     // Test getting all kafka configuration items via get
-    assert_eq!(AppConfig::get::<bool>("kafka.enabled").unwrap(), false);
+    assert!(!AppConfig::get::<bool>("kafka.enabled").unwrap());
     assert_eq!(AppConfig::get::<String>("kafka.host").unwrap(), "localhost");
     assert_eq!(AppConfig::get::<u16>("kafka.port").unwrap(), 9092);
     assert_eq!(AppConfig::get::<String>("kafka.topic").unwrap(), "test");
@@ -103,7 +103,7 @@ fn verify_set() {
     // Check all values were modified
     assert_eq!(config.log.level, "debug");
     assert_eq!(config.scan.concurrency, 10);
-    assert_eq!(config.migrate.overwrite, true);
+    assert!(config.migrate.overwrite);
     assert_eq!(config.database.batch_size, 50000);
     assert_eq!(config.kafka.concurrency, 50);
 }
@@ -150,7 +150,7 @@ fn test_nested_configuration_access() {
     assert_eq!(log_config.max_backups, 10);
 
     let db_config = AppConfig::get::<DatabaseConfig>("database").unwrap();
-    assert_eq!(db_config.enabled, true);
+    assert!(db_config.enabled);
     assert_eq!(db_config.r#type, "sqlite");
     assert_eq!(db_config.batch_size, 10000);
 
@@ -160,7 +160,7 @@ fn test_nested_configuration_access() {
     assert_eq!(clickhouse_config.read_timeout, 30);
 
     let kafka_config = AppConfig::get::<KafkaConfig>("kafka").unwrap();
-    assert_eq!(kafka_config.enabled, false);
+    assert!(!kafka_config.enabled);
     assert_eq!(kafka_config.host, "localhost");
     assert_eq!(kafka_config.port, 9092);
     assert_eq!(kafka_config.topic, "test");
@@ -197,10 +197,10 @@ fn test_configuration_types() {
     assert_eq!(concurrency, 5);
 
     let overwrite: bool = AppConfig::get("migrate.overwrite").unwrap();
-    assert_eq!(overwrite, false);
+    assert!(!overwrite);
 
     let enabled: bool = AppConfig::get("database.enabled").unwrap();
-    assert_eq!(enabled, true);
+    assert!(enabled);
 
     let port: u16 = AppConfig::get("kafka.port").unwrap();
     assert_eq!(port, 9092);